@@ -1,4 +1,12 @@
-use crate::{camera::Camera, debug, error, info, RustCraftWrapper, CAMERA_SYSTEM};
+use crate::{
+    assets::loader::ASSET_LOADER,
+    camera::Camera,
+    debug,
+    ecs::WORLD,
+    error,
+    hdr::{HdrPipeline, HdrSettings},
+    info, light, RustCraftWrapper, ASSETS, CAMERA_SYSTEM, GL_STATE, LIGHT_SYSTEM,
+};
 use glfw::*;
 use lazy_static::lazy_static;
 use std::{sync::mpsc::channel, thread::spawn, time::Instant};
@@ -9,6 +17,7 @@ lazy_static! {
     static ref APP_TIME: RustCraftWrapper<Instant> = RustCraftWrapper::new(Instant::now());
     static ref WINDOW_SIZE: RustCraftWrapper<(i32, i32)> = RustCraftWrapper::new((0, 0));
     static ref WINDOW: RustCraftWrapper<Option<PWindow>> = RustCraftWrapper::new(None);
+    static ref HDR_SETTINGS: RustCraftWrapper<Option<HdrSettings>> = RustCraftWrapper::new(None);
 }
 
 impl RustCraftWrapper<Option<PWindow>> {
@@ -38,6 +47,7 @@ pub struct AppBuilder {
         Option<Box<dyn FnMut(&mut Window, MouseButton, Action, Modifiers) + Send + 'static>>,
     fix_cursor: bool,
     vsync: bool,
+    hdr: Option<HdrSettings>,
 }
 
 impl Default for AppBuilder {
@@ -54,6 +64,7 @@ impl Default for AppBuilder {
             button_callback: None,
             fix_cursor: false,
             vsync: false,
+            hdr: None,
         }
     }
 }
@@ -79,6 +90,16 @@ impl AppBuilder {
         self
     }
 
+    /// 启用高动态范围渲染
+    ///
+    /// # 注解 Note
+    /// 渲染循环回调函数的绘制将输出到 RGBA16F 帧缓冲，
+    /// 回调返回后自动执行自动曝光、泛光与色调映射，参见 `HdrPipeline`
+    pub fn enable_hdr(&mut self, settings: HdrSettings) -> &mut Self {
+        self.hdr = Some(settings);
+        self
+    }
+
     /// 设置渲染线程初始化回调函数
     ///
     /// # 注解 Note
@@ -93,7 +114,8 @@ impl AppBuilder {
     /// 设置渲染线程循环回调函数
     ///
     /// # 注解 Note
-    /// 此函数通常是渲染函数
+    /// 此函数通常是渲染函数；调用前渲染线程已按 `WORLD.set_clear_color` 清屏并绘制实体，
+    /// 此函数中不应再清屏
     pub fn set_render_loop_callback<F>(&mut self, func: F) -> &mut Self
     where
         F: FnMut() + Send + 'static,
@@ -235,6 +257,10 @@ impl AppBuilder {
         WINDOW_SIZE.apply(|data| {
             *data = (self.size.0 as i32, self.size.1 as i32);
         });
+        let hdr = self.hdr;
+        HDR_SETTINGS.apply(|data| {
+            *data = hdr;
+        });
         if self.fix_cursor {
             window.set_cursor_mode(CursorMode::Disabled);
         }
@@ -259,8 +285,11 @@ impl AppBuilder {
                 gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
                 gl::Viewport::load_with(|symbol| window.get_proc_address(symbol) as *const _);
             });
+            // 立方体贴图在面之间过滤，避免接缝
+            unsafe { gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS) };
             debug!("App::new()/render", "初始化渲染依赖 ...");
             // 初始化渲染依赖
+            light::load_shaders();
             if let Some(func) = render_init_func {
                 func();
             }
@@ -268,6 +297,7 @@ impl AppBuilder {
             debug!("App::new()/render", "启动渲染循环 ...");
             // FPS 计数器
             let mut now = std::time::Instant::now();
+            let mut hdr = None;
             while !WINDOW.should_close() {
                 // 计算此帧渲染时间
                 let dt = now.elapsed().as_secs_f32();
@@ -277,12 +307,28 @@ impl AppBuilder {
                 });
                 // 更新窗口尺寸
                 if let Ok((w, h)) = size_rx.try_recv() {
-                    unsafe { gl::Viewport(0, 0, w as i32, h as i32) }
+                    GL_STATE.set_viewport([0, 0, w as i32, h as i32]);
                 }
+                // 上传异步加载完成的资源
+                ASSET_LOADER.upload_pending();
+                // 运行实体系统
+                WORLD.run_frame(dt);
                 // 渲染
+                LIGHT_SYSTEM.upload();
+                sync_hdr(&mut hdr);
+                if let Some(hdr) = hdr.as_mut() {
+                    hdr.begin();
+                }
+                // 清屏并绘制实体，渲染回调在其后绘制
+                WORLD.render();
                 if let Some(func) = render_loop_func.as_mut() {
                     func();
                 }
+                if let Some(hdr) = hdr.as_mut() {
+                    hdr.end();
+                }
+                // 卸载未被引用的资源
+                ASSETS.collect_garbage();
                 // 交换缓冲区
                 WINDOW.apply(|w| {
                     w.as_mut().unwrap().swap_buffers();
@@ -328,6 +374,31 @@ impl AppBuilder {
     }
 }
 
+/// 按当前设置创建、更新或销毁高动态范围渲染管线
+fn sync_hdr(pipeline: &mut Option<HdrPipeline>) {
+    let mut settings = None;
+    HDR_SETTINGS.apply(|data| {
+        settings = *data;
+    });
+    let result = match (pipeline.as_mut(), settings) {
+        (_, None) => {
+            *pipeline = None;
+            Ok(())
+        }
+        (Some(hdr), Some(settings)) => hdr.set_settings(&settings),
+        (None, Some(settings)) => HdrPipeline::new("hdr", &settings).map(|hdr| {
+            *pipeline = Some(hdr);
+        }),
+    };
+    if let Err(e) = result {
+        error!("App", "高动态范围渲染已停用, 由于 {}", e);
+        *pipeline = None;
+        HDR_SETTINGS.apply(|data| {
+            *data = None;
+        });
+    }
+}
+
 /// 应用程序结构体
 pub struct App {
     glfw: Glfw,
@@ -386,6 +457,25 @@ impl App {
         t
     }
 
+    /// 获取当前的高动态范围渲染参数，未启用时返回 `None`
+    pub fn hdr_settings() -> Option<HdrSettings> {
+        let mut settings = None;
+        HDR_SETTINGS.apply(|data| {
+            settings = *data;
+        });
+        settings
+    }
+
+    /// 修改高动态范围渲染参数，`None` 表示停用
+    ///
+    /// # 注解 Note
+    /// 新的参数将在下一帧开始时生效
+    pub fn set_hdr_settings(settings: Option<HdrSettings>) {
+        HDR_SETTINGS.apply(|data| {
+            *data = settings;
+        });
+    }
+
     /// 获取当前窗口尺寸
     pub fn window_size() -> (i32, i32) {
         let mut size = (0, 0);
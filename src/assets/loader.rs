@@ -0,0 +1,159 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::spawn,
+};
+
+use crate::{debug, RustCraftWrapper};
+
+/// 上传任务，在渲染线程中执行
+pub type UploadTask = Box<dyn FnOnce() + Send + 'static>;
+/// 加载任务，在工作线程中执行，完成后返回对应的上传任务
+pub type LoadTask = Box<dyn FnOnce() -> UploadTask + Send + 'static>;
+
+/// 异步资源加载器
+///
+/// 资源的解码与解析在工作线程池中完成，
+/// 需要 OpenGL 上下文的上传操作则在渲染线程中按批次执行
+pub struct AssetLoader {
+    sender: Option<Sender<LoadTask>>,
+    uploads: Arc<Mutex<VecDeque<UploadTask>>>,
+    total: usize,
+    loaded: usize,
+    budget: usize,
+}
+
+impl AssetLoader {
+    fn new() -> Self {
+        Self {
+            sender: None,
+            uploads: Arc::new(Mutex::new(VecDeque::new())),
+            total: 0,
+            loaded: 0,
+            budget: 8,
+        }
+    }
+
+    /// 启动工作线程池
+    fn start_workers(&mut self) -> &Sender<LoadTask> {
+        let (tx, rx) = channel::<LoadTask>();
+        let rx = Arc::new(Mutex::new(rx));
+        let count = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .clamp(1, 4);
+        debug!("AssetLoader", "启动 {} 个资源加载线程", count);
+        for _ in 0..count {
+            let rx: Arc<Mutex<Receiver<LoadTask>>> = rx.clone();
+            let uploads = self.uploads.clone();
+            spawn(move || loop {
+                let task = match rx.lock() {
+                    Ok(rx) => rx.recv(),
+                    Err(_) => return,
+                };
+                let task = match task {
+                    Ok(task) => task,
+                    Err(_) => return,
+                };
+                let upload = task();
+                if let Ok(mut uploads) = uploads.lock() {
+                    uploads.push_back(upload);
+                }
+            });
+        }
+        self.sender.insert(tx)
+    }
+
+    fn submit(&mut self, task: LoadTask) {
+        self.total += 1;
+        let sender = match self.sender.as_ref() {
+            Some(sender) => sender,
+            None => self.start_workers(),
+        };
+        // 工作线程不会主动退出，因此发送不会失败
+        let _ = sender.send(task);
+    }
+
+    /// 取出本帧需要执行的上传任务
+    fn take_batch(&mut self) -> Vec<UploadTask> {
+        let mut batch = vec![];
+        if let Ok(mut uploads) = self.uploads.lock() {
+            while batch.len() < self.budget {
+                match uploads.pop_front() {
+                    Some(task) => batch.push(task),
+                    None => break,
+                }
+            }
+        }
+        self.loaded += batch.len();
+        batch
+    }
+}
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// 异步资源加载器
+    pub static ref ASSET_LOADER: RustCraftWrapper<AssetLoader> =
+        RustCraftWrapper::new(AssetLoader::new());
+}
+
+impl RustCraftWrapper<AssetLoader> {
+    /// 提交一个异步加载任务
+    ///
+    /// # 参数 Parameters
+    /// * `task` - 在工作线程中执行的加载函数，返回在渲染线程中执行的上传函数
+    ///
+    /// # 注解 Note
+    ///
+    /// 加载函数中不应调用 OpenGL，所有 OpenGL 调用都应放在上传函数中
+    pub fn submit<F>(&self, task: F)
+    where
+        F: FnOnce() -> UploadTask + Send + 'static,
+    {
+        self.apply(|loader| loader.submit(Box::new(task)));
+    }
+
+    /// 设置每帧最多执行的上传任务数量
+    ///
+    /// # 注解 Note
+    ///
+    /// 默认值为 8，设置为 0 时将暂停上传
+    pub fn set_upload_budget(&self, budget: usize) {
+        self.apply(|loader| loader.budget = budget);
+    }
+
+    /// 执行一批待上传的任务
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，
+    /// 通常由渲染线程在每帧开始时自动调用
+    pub fn upload_pending(&self) {
+        let mut batch = vec![];
+        self.apply(|loader| batch = loader.take_batch());
+        // 上传任务在加载器锁之外执行，使其可以访问其他管理器
+        for task in batch {
+            task();
+        }
+    }
+
+    /// 获取加载进度
+    ///
+    /// # 返回值 Returns
+    /// `(已完成数量, 总数量)`，加载失败的资源同样计为已完成
+    pub fn progress(&self) -> (usize, usize) {
+        let mut ret = (0, 0);
+        self.apply(|loader| ret = (loader.loaded, loader.total));
+        ret
+    }
+
+    /// 是否所有已提交的资源都已加载完成
+    pub fn is_idle(&self) -> bool {
+        let (loaded, total) = self.progress();
+        loaded == total
+    }
+}
@@ -0,0 +1,566 @@
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use crate::{
+    debug, material::Material, model::Mesh, shader::Shader, texture::texture::Texture,
+    RustCraftWrapper,
+};
+
+pub mod loader;
+
+/// 资源类型接口
+///
+/// 实现此接口的类型可以由资源系统 `ASSETS` 统一管理
+pub trait Asset: Send + Sized + 'static {
+    /// 资源类型名称，用于日志输出与依赖标识
+    const TYPE_NAME: &'static str;
+
+    fn storage(assets: &Assets) -> &AssetStorage<Self>;
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self>;
+}
+
+/// 资源加载状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadState {
+    /// 已登记，尚未开始加载
+    Queued,
+    /// 正在加载
+    Loading,
+    /// 加载完成，可以使用
+    Ready,
+    /// 加载失败，附带失败原因
+    Failed(String),
+}
+
+/// 资源标识
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssetId {
+    pub type_name: &'static str,
+    pub name: String,
+}
+
+/// 类型化资源句柄
+///
+/// 句柄持有对资源的一份引用，资源在所有句柄被释放后会被自动卸载
+pub struct Handle<T> {
+    name: Arc<str>,
+    token: Arc<()>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            token: self.token.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle({})", self.name)
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.token, &other.token)
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T: Asset> Handle<T> {
+    /// 资源名称
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 资源标识
+    pub fn id(&self) -> AssetId {
+        AssetId {
+            type_name: T::TYPE_NAME,
+            name: self.name.to_string(),
+        }
+    }
+
+    /// 转换为无类型句柄，用于依赖记录
+    pub fn untyped(&self) -> UntypedHandle {
+        UntypedHandle {
+            id: self.id(),
+            _token: self.token.clone(),
+        }
+    }
+}
+
+/// 无类型资源句柄
+///
+/// 用于记录资源间的依赖关系，持有期间被依赖的资源不会被卸载
+#[derive(Clone)]
+pub struct UntypedHandle {
+    id: AssetId,
+    _token: Arc<()>,
+}
+
+impl UntypedHandle {
+    pub fn id(&self) -> &AssetId {
+        &self.id
+    }
+}
+
+struct AssetEntry<T> {
+    value: Option<T>,
+    state: LoadState,
+    token: Arc<()>,
+    dependencies: Vec<UntypedHandle>,
+}
+
+/// 单一类型资源的存储
+pub struct AssetStorage<T> {
+    entries: HashMap<String, AssetEntry<T>>,
+}
+
+impl<T: Asset> AssetStorage<T> {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    fn handle_of(name: &str, entry: &AssetEntry<T>) -> Handle<T> {
+        Handle {
+            name: Arc::from(name),
+            token: entry.token.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn entry(&self, handle: &Handle<T>) -> Option<&AssetEntry<T>> {
+        self.entries
+            .get(handle.name())
+            .filter(|entry| Arc::ptr_eq(&entry.token, &handle.token))
+    }
+
+    fn entry_mut(&mut self, handle: &Handle<T>) -> Option<&mut AssetEntry<T>> {
+        self.entries
+            .get_mut(handle.name())
+            .filter(|entry| Arc::ptr_eq(&entry.token, &handle.token))
+    }
+
+    fn reserve(&mut self, name: &str) -> Handle<T> {
+        let entry = self
+            .entries
+            .entry(name.to_string())
+            .or_insert_with(|| AssetEntry {
+                value: None,
+                state: LoadState::Queued,
+                token: Arc::new(()),
+                dependencies: vec![],
+            });
+        Self::handle_of(name, entry)
+    }
+
+    /// 卸载所有未被引用的资源，返回卸载的数量
+    fn collect(&mut self) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|name, entry| {
+            let keep = Arc::strong_count(&entry.token) > 1;
+            if !keep {
+                debug!("Assets", "卸载未被引用的{}资源: {}", T::TYPE_NAME, name);
+            }
+            keep
+        });
+        before - self.entries.len()
+    }
+}
+
+/// 资源系统
+///
+/// 统一管理纹理、着色器、材质与网格资源，负责引用计数、加载状态与依赖跟踪
+pub struct Assets {
+    textures: AssetStorage<Texture>,
+    shaders: AssetStorage<Shader>,
+    materials: AssetStorage<Material>,
+    meshes: AssetStorage<Mesh>,
+}
+
+impl Asset for Texture {
+    const TYPE_NAME: &'static str = "Texture";
+
+    fn storage(assets: &Assets) -> &AssetStorage<Self> {
+        &assets.textures
+    }
+
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self> {
+        &mut assets.textures
+    }
+}
+
+impl Asset for Shader {
+    const TYPE_NAME: &'static str = "Shader";
+
+    fn storage(assets: &Assets) -> &AssetStorage<Self> {
+        &assets.shaders
+    }
+
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self> {
+        &mut assets.shaders
+    }
+}
+
+impl Asset for Material {
+    const TYPE_NAME: &'static str = "Material";
+
+    fn storage(assets: &Assets) -> &AssetStorage<Self> {
+        &assets.materials
+    }
+
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self> {
+        &mut assets.materials
+    }
+}
+
+impl Asset for Mesh {
+    const TYPE_NAME: &'static str = "Mesh";
+
+    fn storage(assets: &Assets) -> &AssetStorage<Self> {
+        &assets.meshes
+    }
+
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self> {
+        &mut assets.meshes
+    }
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        Self {
+            textures: AssetStorage::new(),
+            shaders: AssetStorage::new(),
+            materials: AssetStorage::new(),
+            meshes: AssetStorage::new(),
+        }
+    }
+
+    /// 登记一个资源，若同名资源已存在则返回其句柄
+    pub fn reserve<T: Asset>(&mut self, name: &str) -> Handle<T> {
+        T::storage_mut(self).reserve(name)
+    }
+
+    /// 放入已加载完成的资源，同名资源将被替换，原有句柄保持有效
+    pub fn insert<T: Asset>(&mut self, name: &str, value: T) -> Handle<T> {
+        let handle = self.reserve::<T>(name);
+        let entry = T::storage_mut(self).entry_mut(&handle).unwrap();
+        entry.value = Some(value);
+        entry.state = LoadState::Ready;
+        handle
+    }
+
+    /// 以加载结果完成资源加载
+    pub fn complete<T: Asset>(&mut self, handle: &Handle<T>, result: Result<T, String>) {
+        match result {
+            Ok(value) => {
+                if let Some(entry) = T::storage_mut(self).entry_mut(handle) {
+                    entry.value = Some(value);
+                    entry.state = LoadState::Ready;
+                }
+            }
+            Err(e) => {
+                debug!(
+                    "Assets",
+                    "{}资源 {} 加载失败: {}",
+                    T::TYPE_NAME,
+                    handle.name(),
+                    e
+                );
+                self.set_state(handle, LoadState::Failed(e));
+            }
+        }
+    }
+
+    /// 设置资源加载状态
+    ///
+    /// # 注解 Note
+    ///
+    /// 状态被设置为非 `Ready` 时，已有的资源数据会被释放
+    pub fn set_state<T: Asset>(&mut self, handle: &Handle<T>, state: LoadState) {
+        if let Some(entry) = T::storage_mut(self).entry_mut(handle) {
+            if state != LoadState::Ready {
+                entry.value = None;
+            }
+            entry.state = state;
+        }
+    }
+
+    /// 查询资源加载状态
+    pub fn state<T: Asset>(&self, handle: &Handle<T>) -> Option<LoadState> {
+        T::storage(self)
+            .entry(handle)
+            .map(|entry| entry.state.clone())
+    }
+
+    /// 按名称查找资源句柄
+    pub fn find<T: Asset>(&self, name: &str) -> Option<Handle<T>> {
+        T::storage(self)
+            .entries
+            .get(name)
+            .map(|entry| AssetStorage::handle_of(name, entry))
+    }
+
+    /// 获取资源的引用
+    pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Option<&T> {
+        T::storage(self)
+            .entry(handle)
+            .and_then(|entry| entry.value.as_ref())
+    }
+
+    /// 获取资源的可变引用
+    pub fn get_mut<T: Asset>(&mut self, handle: &Handle<T>) -> Option<&mut T> {
+        T::storage_mut(self)
+            .entry_mut(handle)
+            .and_then(|entry| entry.value.as_mut())
+    }
+
+    /// 资源当前被句柄引用的次数
+    pub fn ref_count<T: Asset>(&self, handle: &Handle<T>) -> usize {
+        T::storage(self)
+            .entry(handle)
+            .map(|entry| Arc::strong_count(&entry.token) - 1)
+            .unwrap_or(0)
+    }
+
+    /// 记录资源依赖关系
+    ///
+    /// 在 `handle` 所指资源存活期间，`dependency` 所指资源不会被卸载
+    pub fn add_dependency<T: Asset>(&mut self, handle: &Handle<T>, dependency: UntypedHandle) {
+        if let Some(entry) = T::storage_mut(self).entry_mut(handle) {
+            if !entry.dependencies.iter().any(|dep| dep.id == dependency.id) {
+                entry.dependencies.push(dependency);
+            }
+        }
+    }
+
+    /// 获取资源的依赖列表
+    pub fn dependencies<T: Asset>(&self, handle: &Handle<T>) -> Vec<AssetId> {
+        T::storage(self)
+            .entry(handle)
+            .map(|entry| {
+                entry
+                    .dependencies
+                    .iter()
+                    .map(|dep| dep.id.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 卸载所有未被引用的资源，返回卸载的数量
+    ///
+    /// # 注解 Note
+    ///
+    /// 卸载资源会释放其依赖，因此会反复执行直至没有可卸载的资源
+    pub fn collect_garbage(&mut self) -> usize {
+        let mut total = 0;
+        loop {
+            let count = self.meshes.collect()
+                + self.materials.collect()
+                + self.shaders.collect()
+                + self.textures.collect();
+            if count == 0 {
+                break;
+            }
+            total += count;
+        }
+        total
+    }
+}
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// 资源系统
+    pub static ref ASSETS: RustCraftWrapper<Assets> = RustCraftWrapper::new(Assets::new());
+}
+
+impl RustCraftWrapper<Assets> {
+    /// 登记一个资源，若同名资源已存在则返回其句柄
+    pub fn reserve<T: Asset>(&self, name: &str) -> Handle<T> {
+        let mut ret = None;
+        self.apply(|assets| ret = Some(assets.reserve(name)));
+        ret.unwrap()
+    }
+
+    /// 放入已加载完成的资源
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 资源名称
+    /// * `value` - 资源数据
+    ///
+    /// # 返回值 Returns
+    /// 资源句柄
+    pub fn insert<T: Asset>(&self, name: &str, value: T) -> Handle<T> {
+        let mut value = Some(value);
+        let mut ret = None;
+        self.apply(|assets| ret = Some(assets.insert(name, value.take().unwrap())));
+        ret.unwrap()
+    }
+
+    /// 使用指定的加载函数加载资源
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 资源名称
+    /// * `loader` - 加载函数，失败时返回错误描述
+    ///
+    /// # 注解 Note
+    ///
+    /// 加载函数在资源系统锁之外执行，因此可以在其中加载其他资源
+    pub fn load_with<T, F>(&self, name: &str, loader: F) -> Handle<T>
+    where
+        T: Asset,
+        F: FnOnce() -> Result<T, String>,
+    {
+        let handle = self.reserve::<T>(name);
+        self.set_state(&handle, LoadState::Loading);
+        self.complete(&handle, loader());
+        handle
+    }
+
+    /// 以加载结果完成资源加载
+    ///
+    /// # 参数 Parameters
+    /// * `handle` - 由 `reserve` 获得的资源句柄
+    /// * `result` - 加载结果，失败时为错误描述
+    pub fn complete<T: Asset>(&self, handle: &Handle<T>, result: Result<T, String>) {
+        let mut result = Some(result);
+        self.apply(|assets| assets.complete(handle, result.take().unwrap()));
+    }
+
+    /// 设置资源加载状态
+    pub fn set_state<T: Asset>(&self, handle: &Handle<T>, state: LoadState) {
+        self.apply(|assets| assets.set_state(handle, state));
+    }
+
+    /// 查询资源加载状态
+    pub fn state<T: Asset>(&self, handle: &Handle<T>) -> Option<LoadState> {
+        let mut ret = None;
+        self.apply(|assets| ret = assets.state(handle));
+        ret
+    }
+
+    /// 按名称查找资源句柄
+    pub fn find<T: Asset>(&self, name: &str) -> Option<Handle<T>> {
+        let mut ret = None;
+        self.apply(|assets| ret = assets.find(name));
+        ret
+    }
+
+    /// 以资源的引用执行函数
+    ///
+    /// # 返回值 Returns
+    /// 资源就绪时返回函数的返回值，否则返回 `None`
+    ///
+    /// # 注解 Note
+    ///
+    /// 函数执行期间资源系统处于锁定状态，不应在函数内再次访问 `ASSETS`
+    pub fn with<T: Asset, R, F: FnOnce(&T) -> R>(&self, handle: &Handle<T>, f: F) -> Option<R> {
+        let mut ret = None;
+        self.apply(|assets| ret = assets.get(handle).map(f));
+        ret
+    }
+
+    /// 以资源的可变引用执行函数
+    pub fn with_mut<T: Asset, R, F: FnOnce(&mut T) -> R>(
+        &self,
+        handle: &Handle<T>,
+        f: F,
+    ) -> Option<R> {
+        let mut ret = None;
+        self.apply(|assets| ret = assets.get_mut(handle).map(f));
+        ret
+    }
+
+    /// 资源当前被句柄引用的次数
+    pub fn ref_count<T: Asset>(&self, handle: &Handle<T>) -> usize {
+        let mut ret = 0;
+        self.apply(|assets| ret = assets.ref_count(handle));
+        ret
+    }
+
+    /// 记录资源依赖关系
+    pub fn add_dependency<T: Asset>(&self, handle: &Handle<T>, dependency: UntypedHandle) {
+        self.apply(|assets| assets.add_dependency(handle, dependency));
+    }
+
+    /// 卸载所有未被引用的资源
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数由渲染线程在每帧结束时自动调用
+    pub fn collect_garbage(&self) -> usize {
+        let mut ret = 0;
+        self.apply(|assets| ret = assets.collect_garbage());
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Model;
+
+    struct EmptyModel;
+
+    impl Model for EmptyModel {
+        fn draw(&self) {}
+    }
+
+    fn mesh() -> Mesh {
+        Mesh::new(Box::new(EmptyModel))
+    }
+
+    #[test]
+    fn test_ref_count() {
+        let mut assets = Assets::new();
+        let a = assets.insert("a", mesh());
+        assert_eq!(assets.ref_count(&a), 1);
+        let b = a.clone();
+        assert_eq!(assets.ref_count(&a), 2);
+        drop(b);
+        assert_eq!(assets.ref_count(&a), 1);
+        assert_eq!(assets.state(&a), Some(LoadState::Ready));
+    }
+
+    #[test]
+    fn test_collect_unreferenced() {
+        let mut assets = Assets::new();
+        let a = assets.insert("a", mesh());
+        drop(assets.insert("b", mesh()));
+        assert_eq!(assets.collect_garbage(), 1);
+        assert!(assets.get(&a).is_some());
+        assert!(assets.find::<Mesh>("b").is_none());
+    }
+
+    #[test]
+    fn test_dependency_keeps_alive() {
+        let mut assets = Assets::new();
+        let parent = assets.insert("parent", mesh());
+        let child = assets.insert("child", mesh());
+        assets.add_dependency(&parent, child.untyped());
+        drop(child);
+        assert_eq!(assets.collect_garbage(), 0);
+        assert_eq!(assets.dependencies(&parent)[0].name, "child");
+        drop(parent);
+        assert_eq!(assets.collect_garbage(), 2);
+    }
+
+    #[test]
+    fn test_failed_state() {
+        let mut assets = Assets::new();
+        let a = assets.reserve::<Mesh>("a");
+        assert_eq!(assets.state(&a), Some(LoadState::Queued));
+        assets.set_state(&a, LoadState::Failed("bad".to_string()));
+        assert!(assets.get(&a).is_none());
+        assert_eq!(assets.state(&a), Some(LoadState::Failed("bad".to_string())));
+    }
+}
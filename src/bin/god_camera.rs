@@ -23,16 +23,11 @@ fn render_init() {
     MODEL_MANAGER.load_from_file("model/cube.json");
     CAMERA_SYSTEM.add_camera("main", GodCamera::new(2.0, 0.2, 0.12));
     CAMERA_SYSTEM.active_camera("main");
-    unsafe {
-        gl::Enable(gl::DEPTH_TEST);
-    }
+    GL_STATE.enable(gl_state::Capability::DepthTest);
+    WORLD.set_clear_color(Some([0.3, 0.5, 0.4, 1.0]));
 }
 
 fn render_loop() {
-    unsafe {
-        gl::ClearColor(0.3, 0.5, 0.4, 1.0);
-        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-    }
     let view = CAMERA_SYSTEM.view_matrix();
     let (w, h) = App::window_size();
     let proj = perspective(radian(45.0), w as f32 / h as f32, 0.1, 100.0);
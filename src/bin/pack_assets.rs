@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use rustcraft::{
+    error, info,
+    log::*,
+    vfs::{archive::write_archive, normalize},
+};
+
+/// 递归收集目录下的所有文件
+fn collect(path: &Path, files: &mut Vec<(String, Vec<u8>)>) {
+    if path.is_dir() {
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("pack_assets", "无法读取目录 {}: {}", path.display(), e);
+                return;
+            }
+        };
+        let mut entries = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+        entries.sort();
+        for entry in entries {
+            collect(&entry, files);
+        }
+    } else {
+        // 资源包中只保存相对的虚拟路径
+        let name = normalize(&path.to_string_lossy())
+            .trim_start_matches('/')
+            .to_string();
+        match std::fs::read(path) {
+            Ok(content) => {
+                info!("pack_assets", "打包文件 {}", name);
+                files.push((name, content));
+            }
+            Err(e) => {
+                error!("pack_assets", "无法读取文件 {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// 资源打包工具
+///
+/// 用法：`pack_assets <输出文件> <目录或文件>...`
+///
+/// 文件在资源包中的虚拟路径即为其在命令行中给出的相对路径，
+/// 例如 `pack_assets assets.pak shader texture model`
+fn main() {
+    Log::set_level(Level::Info);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.len() < 2 {
+        error!(
+            "pack_assets",
+            "用法: pack_assets <输出文件> <目录或文件>..."
+        );
+        std::process::exit(1);
+    }
+    let mut files = vec![];
+    for input in &args[1..] {
+        collect(Path::new(input), &mut files);
+    }
+    let data = write_archive(&files);
+    if let Err(e) = std::fs::write(&args[0], data) {
+        error!("pack_assets", "无法写入资源包 {}: {}", args[0], e);
+        std::process::exit(1);
+    }
+    info!(
+        "pack_assets",
+        "已将 {} 个文件打包至 {}",
+        files.len(),
+        args[0]
+    );
+}
@@ -20,16 +20,11 @@ fn render_init() {
     TEXTURE_MANAGER.load_from("texture/");
 
     MODEL_MANAGER.load_from_file("model/cube.json");
-    unsafe {
-        gl::Enable(gl::DEPTH_TEST);
-    }
+    GL_STATE.enable(gl_state::Capability::DepthTest);
+    WORLD.set_clear_color(Some([0.3, 0.5, 0.4, 1.0]));
 }
 
 fn render_loop() {
-    unsafe {
-        gl::ClearColor(0.3, 0.5, 0.4, 1.0);
-        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-    }
     let view = look_at(
         Vec3::from([0.0, 0.0, 3.0]),
         Vec3::from([0.0, 0.0, 0.0]),
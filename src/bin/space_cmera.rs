@@ -26,16 +26,11 @@ fn render_init() {
         SpaceCamera::new(Vec3::from([3.0, 0.0, 0.0]), 3.0, 0.08),
     );
     CAMERA_SYSTEM.active_camera("main");
-    unsafe {
-        gl::Enable(gl::DEPTH_TEST);
-    }
+    GL_STATE.enable(gl_state::Capability::DepthTest);
+    WORLD.set_clear_color(Some([0.3, 0.5, 0.4, 1.0]));
 }
 
 fn render_loop() {
-    unsafe {
-        gl::ClearColor(0.3, 0.5, 0.4, 1.0);
-        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-    }
     let view = CAMERA_SYSTEM.view_matrix();
     let (w, h) = App::window_size();
     let proj = perspective(radian(45.0), w as f32 / h as f32, 0.1, 100.0);
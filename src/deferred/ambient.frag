@@ -0,0 +1,30 @@
+#version 330 core
+
+in vec2 TexCoords;
+out vec4 FragColor;
+
+#include <lights>
+
+uniform sampler2D gAlbedoSpec;
+uniform sampler2D gNormal;
+uniform sampler2D gPosition;
+uniform vec3 viewPos;
+uniform sampler2D ambientOcclusion;
+uniform int useAmbientOcclusion;
+
+// 环境光与方向光以全屏方式计算
+void main(){
+    vec4 position = texture(gPosition, TexCoords);
+    if(position.w == 0.0) discard;
+    vec4 albedoSpec = texture(gAlbedoSpec, TexCoords);
+    vec4 normal = texture(gNormal, TexCoords);
+    vec3 viewDir = normalize(viewPos - position.xyz);
+    float occlusion = useAmbientOcclusion == 1 ? texture(ambientOcclusion, TexCoords).r : 1.0;
+    vec3 result = ambientLight.rgb * albedoSpec.rgb * occlusion;
+    for (int i = 0; i < lightCounts.x; ++i) {
+        vec3 lightDir = -directionalLights[i].direction.xyz;
+        result += blinnPhong(lightDir, directionalLights[i].color.rgb, normal.xyz, viewDir,
+                             albedoSpec.rgb, vec3(albedoSpec.a), normal.w);
+    }
+    FragColor = vec4(result, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 330 core
+
+// 延迟渲染几何阶段的输出约定：
+// 0: 漫反射颜色(rgb)与镜面反射强度(a)
+// 1: 世界空间法线(xyz)与高光指数(w)
+// 2: 世界空间位置(xyz)，w 为 1 表示此像素有几何体
+layout (location = 0) out vec4 gAlbedoSpec;
+layout (location = 1) out vec4 gNormal;
+layout (location = 2) out vec4 gPosition;
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoord;
+
+uniform sampler2D diffuseMap;
+uniform sampler2D specularMap;
+uniform float shininess;
+
+void main(){
+    gAlbedoSpec = vec4(texture(diffuseMap, TexCoord).rgb, texture(specularMap, TexCoord).r);
+    gNormal = vec4(normalize(Normal), shininess);
+    gPosition = vec4(FragPos, 1.0);
+}
@@ -0,0 +1,44 @@
+#version 330 core
+
+out vec4 FragColor;
+
+#include <lights>
+
+uniform sampler2D gAlbedoSpec;
+uniform sampler2D gNormal;
+uniform sampler2D gPosition;
+uniform vec3 viewPos;
+uniform vec2 screenSize;
+// 0 为点光源，1 为聚光灯
+uniform int lightType;
+// 光源在 pointLights 或 spotLights 中的下标
+uniform int lightIndex;
+
+// 单个点光源或聚光灯的光照，以加法混合累加
+void main(){
+    vec2 uv = gl_FragCoord.xy / screenSize;
+    vec4 position = texture(gPosition, uv);
+    if(position.w == 0.0) discard;
+    vec4 albedoSpec = texture(gAlbedoSpec, uv);
+    vec4 normal = texture(gNormal, uv);
+    vec3 viewDir = normalize(viewPos - position.xyz);
+    vec3 radiance;
+    vec3 lightDir;
+    if(lightType == 0){
+        PointLight light = pointLights[lightIndex];
+        vec3 toLight = light.position.xyz - position.xyz;
+        float dist = length(toLight);
+        lightDir = toLight / dist;
+        radiance = light.color.rgb * attenuate(light.attenuation, dist);
+    } else {
+        SpotLight light = spotLights[lightIndex];
+        vec3 toLight = light.position.xyz - position.xyz;
+        float dist = length(toLight);
+        lightDir = toLight / dist;
+        float theta = dot(lightDir, -light.direction.xyz);
+        float cone = clamp((theta - light.cutoff.y) / max(light.cutoff.x - light.cutoff.y, 0.0001), 0.0, 1.0);
+        radiance = light.color.rgb * attenuate(light.attenuation, dist) * cone;
+    }
+    vec3 result = blinnPhong(lightDir, radiance, normal.xyz, viewDir, albedoSpec.rgb, vec3(albedoSpec.a), normal.w);
+    FragColor = vec4(result, 1.0);
+}
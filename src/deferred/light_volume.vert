@@ -0,0 +1,12 @@
+#version 330 core
+
+layout (location = 0) in vec3 aPos;
+
+uniform mat4 view;
+uniform mat4 projection;
+// 光源包围球的球心(xyz)与半径(w)
+uniform vec4 lightVolume;
+
+void main(){
+    gl_Position = projection * view * vec4(aPos * lightVolume.w + lightVolume.xyz, 1.0);
+}
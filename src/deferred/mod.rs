@@ -0,0 +1,473 @@
+use gl::types::*;
+use mats::{Mat4, Vec2, Vec3};
+
+use crate::{
+    gl_state::{BlendFunc, Capability, Face},
+    gl_utils,
+    light::camera_position,
+    postprocess,
+    texture::{DepthFormat, PixelFormat, TextureOptions},
+    Framebuffer, FramebufferBuilder, Shader, UniformValue, ASSETS, GL_STATE, LIGHT_SYSTEM,
+    MATERIAL_MANAGER, MODEL_MANAGER, SHADER_MANAGER, TEXTURE_MANAGER,
+};
+
+const GEOMETRY_VERT: &str = include_str!("../light/blinn_phong.vert");
+const GEOMETRY_FRAG: &str = include_str!("geometry.frag");
+const AMBIENT_FRAG: &str = include_str!("ambient.frag");
+const LIGHT_VOLUME_VERT: &str = include_str!("light_volume.vert");
+const LIGHT_VOLUME_FRAG: &str = include_str!("light_volume.frag");
+
+/// 参考几何阶段着色器在 `SHADER_MANAGER` 中的名称
+///
+/// 顶点属性与 `light/blinn_phong` 一致，使用 `diffuseMap`、`specularMap` 与 `shininess`
+pub const GEOMETRY_SHADER: &str = "deferred/geometry";
+const AMBIENT_SHADER: &str = "deferred/ambient";
+const LIGHT_VOLUME_SHADER: &str = "deferred/light_volume";
+
+/// 光源包围球的经线与纬线分段数
+const SPHERE_SEGMENTS: u32 = 16;
+const SPHERE_RINGS: u32 = 8;
+
+/// 由渲染线程注册延迟渲染着色器
+fn register_shaders() -> Result<(), String> {
+    let shaders = [
+        (GEOMETRY_SHADER, GEOMETRY_VERT, GEOMETRY_FRAG),
+        (
+            AMBIENT_SHADER,
+            postprocess::fullscreen_vertex_shader(),
+            AMBIENT_FRAG,
+        ),
+        (LIGHT_VOLUME_SHADER, LIGHT_VOLUME_VERT, LIGHT_VOLUME_FRAG),
+    ];
+    for (name, vert, frag) in shaders {
+        if SHADER_MANAGER.handle(name).is_none() {
+            SHADER_MANAGER.add_from_source(name, vert, frag);
+            if SHADER_MANAGER.handle(name).is_none() {
+                return Err(format!("内置着色器 {} 加载失败", name));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 生成外接于单位球的经纬球网格
+///
+/// 多边形的面位于球面以内，因此顶点按分段角度放大，使网格完整包住单位球
+///
+/// # 返回值 Returns
+/// 顶点位置(`3f`)与三角形索引
+fn sphere_mesh(segments: u32, rings: u32) -> (Vec<f32>, Vec<u32>) {
+    use std::f32::consts::PI;
+    let scale = 1.0 / ((PI / segments as f32).cos() * (PI / 2.0 / rings as f32).cos());
+    let mut vertices = vec![];
+    for ring in 0..=rings {
+        let phi = PI * ring as f32 / rings as f32;
+        for segment in 0..=segments {
+            let theta = 2.0 * PI * segment as f32 / segments as f32;
+            vertices.push(phi.sin() * theta.cos() * scale);
+            vertices.push(phi.cos() * scale);
+            vertices.push(phi.sin() * theta.sin() * scale);
+        }
+    }
+    let mut indices = vec![];
+    let stride = segments + 1;
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = ring * stride + segment;
+            let b = a + stride;
+            // 逆时针环绕，正面朝外
+            indices.extend_from_slice(&[a, a + 1, b, a + 1, b + 1, b]);
+        }
+    }
+    (vertices, indices)
+}
+
+/// 光源的亮度，即颜色分量乘以强度后的最大值
+fn brightness(color: [f32; 3], intensity: f32) -> f32 {
+    color.iter().fold(0.0_f32, |m, c| m.max(c * intensity))
+}
+
+/// 延迟渲染器
+///
+/// 几何阶段将不透明物体写入 G-buffer，光照阶段以全屏方式计算环境光与方向光，
+/// 并以包围球绘制点光源与聚光灯的光照体积，累加到当前绑定的帧缓冲；
+/// 随后将 G-buffer 的深度复制到当前帧缓冲，使透明物体能以前向渲染正确遮挡
+///
+/// G-buffer 的颜色附件依次为：
+/// + `名称.color0` - 漫反射颜色(rgb)与镜面反射强度(a)
+/// + `名称.color1` - 世界空间法线(xyz)与高光指数(w)
+/// + `名称.color2` - 世界空间位置(xyz)，w 为 1 表示此像素有几何体
+///
+/// 自定义几何阶段着色器需按 `GEOMETRY_SHADER` 的约定输出到这三个位置
+///
+/// # 注解 Note
+///
+/// 光照结果写入的帧缓冲不能启用多重采样，与高动态范围渲染同时使用时
+/// `HdrSettings::samples` 应为 0
+///
+/// # 示例 Examples
+/// ```ignore
+/// let mut deferred = DeferredRenderer::new("gbuffer")?;
+/// MATERIAL_MANAGER.add("crate", Material::new(deferred::GEOMETRY_SHADER)
+///     .texture("diffuseMap", "container2")
+///     .texture("specularMap", "container2_specular")
+///     .uniform("shininess", 32.0));
+/// MATERIAL_MANAGER.add("glass", Material::new("glass").transparent(true));
+/// MODEL_MANAGER.set_material("cube", Some("crate"));
+/// MODEL_MANAGER.set_material("window", Some("glass"));
+/// // 渲染循环：不透明物体延迟渲染，透明物体自动按由远及近的顺序前向渲染
+/// deferred.render_models(&[("cube", cube), ("window", window)], view, projection);
+/// ```
+pub struct DeferredRenderer {
+    gbuffer: Framebuffer,
+    sphere: (GLuint, GLuint, GLuint),
+    sphere_count: i32,
+    /// 绘制全屏三角形使用的空顶点数组对象
+    vao: GLuint,
+    /// 几何阶段开始前绑定的帧缓冲与视口
+    saved: (GLint, [GLint; 4]),
+    /// 调制环境光的遮蔽纹理在 `TEXTURE_MANAGER` 中的名称
+    ambient_occlusion: Option<String>,
+}
+
+impl DeferredRenderer {
+    /// 创建延迟渲染器
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `name` - G-buffer 帧缓冲的名称，尺寸与窗口一致
+    pub fn new(name: &str) -> Result<Self, String> {
+        register_shaders()?;
+        let exact = TextureOptions::default().half_float(false);
+        let gbuffer = FramebufferBuilder::new(name)
+            .color(PixelFormat::Rgba8)
+            .color(PixelFormat::Rgba32F)
+            .color_with(PixelFormat::Rgba32F, exact)
+            .depth_texture(DepthFormat::Depth24Stencil8)
+            .build()?;
+        let (vertices, indices) = sphere_mesh(SPHERE_SEGMENTS, SPHERE_RINGS);
+        let sphere = unsafe { gl_utils::create_element_model_context(&vertices, &indices, "3f") };
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        Ok(Self {
+            gbuffer,
+            sphere,
+            sphere_count: indices.len() as i32,
+            vao,
+            saved: (0, [0; 4]),
+            ambient_occlusion: None,
+        })
+    }
+
+    /// G-buffer 帧缓冲
+    pub fn gbuffer(&self) -> &Framebuffer {
+        &self.gbuffer
+    }
+
+    /// 设置调制环境光的遮蔽纹理，例如 `Ssao::texture_name()`，`None` 表示不使用
+    pub fn set_ambient_occlusion(&mut self, texture: Option<&str>) {
+        self.ambient_occlusion = texture.map(|name| name.to_string());
+    }
+
+    /// 开始几何阶段：绑定并清空 G-buffer
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn begin_geometry(&mut self) {
+        let mut framebuffer = 0;
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut framebuffer);
+        }
+        self.saved = (framebuffer, GL_STATE.viewport());
+        self.gbuffer.bind();
+        let zero = [0.0f32; 4];
+        unsafe {
+            // 不修改清屏颜色，背景像素的位置 w 分量必须为 0
+            for i in 0..self.gbuffer.color_count() {
+                gl::ClearBufferfv(gl::COLOR, i as GLint, zero.as_ptr());
+            }
+            gl::ClearBufferfi(gl::DEPTH_STENCIL, 0, 1.0, 0);
+        }
+        GL_STATE.enable(Capability::DepthTest);
+        GL_STATE.disable(Capability::Blend);
+    }
+
+    /// 结束几何阶段：恢复几何阶段开始前的帧缓冲与视口
+    pub fn end_geometry(&mut self) {
+        let (framebuffer, viewport) = self.saved;
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as GLuint);
+        }
+        GL_STATE.set_viewport(viewport);
+    }
+
+    /// 光照阶段：将光照结果累加到当前绑定的帧缓冲，并复制 G-buffer 的深度
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，光源数据取自 `LIGHT_SYSTEM`
+    ///
+    /// 没有几何体的像素保持不变，因此可以先绘制天空盒等背景；
+    /// 调用后深度测试处于启用状态，可直接以前向渲染绘制透明物体
+    ///
+    /// # 参数 Parameters
+    /// * `view` - 摄像机的视图矩阵
+    /// * `projection` - 摄像机的投影矩阵
+    pub fn lighting(&self, view: Mat4<f32>, projection: Mat4<f32>) {
+        let (ambient, volume) = match (
+            SHADER_MANAGER.get(AMBIENT_SHADER),
+            SHADER_MANAGER.get(LIGHT_VOLUME_SHADER),
+        ) {
+            (Some(ambient), Some(volume)) => (ambient, volume),
+            _ => return,
+        };
+        let view_pos = Vec3::from(camera_position(view));
+        let (width, height) = self.gbuffer.size();
+        for i in 0..3 {
+            self.gbuffer.bind_color(i, i as u32);
+        }
+        let bind_gbuffer = |shader: &Shader| {
+            shader.use_program();
+            shader.set_uniform("gAlbedoSpec", 0);
+            shader.set_uniform("gNormal", 1);
+            shader.set_uniform("gPosition", 2);
+            shader.set_uniform("viewPos", view_pos);
+        };
+        let pipeline = GL_STATE.pipeline();
+
+        // 环境光与方向光
+        bind_gbuffer(&ambient);
+        let occlusion = self
+            .ambient_occlusion
+            .as_ref()
+            .and_then(|name| TEXTURE_MANAGER.handle(name));
+        if let Some(handle) = &occlusion {
+            ASSETS.with(handle, |texture| texture.bind(3));
+        }
+        ambient.set_uniform("ambientOcclusion", 3);
+        ambient.set_uniform("useAmbientOcclusion", occlusion.is_some() as i32);
+        GL_STATE.disable(Capability::DepthTest);
+        GL_STATE.disable(Capability::Blend);
+        GL_STATE.disable(Capability::CullFace);
+        GL_STATE.bind_vertex_array(self.vao);
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+
+        // 点光源与聚光灯的光照体积，只绘制背面使摄像机位于体积内时仍能覆盖；
+        // 启用深度钳制，使超出远裁切面的背面不被裁切
+        bind_gbuffer(&volume);
+        volume.set_uniform("view", view);
+        volume.set_uniform("projection", projection);
+        volume.set_uniform("screenSize", Vec2::from([width as f32, height as f32]));
+        GL_STATE.enable(Capability::Blend);
+        GL_STATE.set_blend_func(BlendFunc::ADDITIVE);
+        GL_STATE.enable(Capability::CullFace);
+        GL_STATE.set_cull_mode(Face::Front);
+        GL_STATE.bind_vertex_array(self.sphere.0);
+        unsafe {
+            gl::Enable(gl::DEPTH_CLAMP);
+        }
+        let (points, spots) = LIGHT_SYSTEM.local_lights();
+        let volumes = points
+            .iter()
+            .map(|l| {
+                (
+                    0,
+                    l.position,
+                    l.attenuation,
+                    brightness(l.color, l.intensity),
+                )
+            })
+            .enumerate()
+            .chain(
+                spots
+                    .iter()
+                    .map(|l| {
+                        (
+                            1,
+                            l.position,
+                            l.attenuation,
+                            brightness(l.color, l.intensity),
+                        )
+                    })
+                    .enumerate(),
+            );
+        for (index, (kind, [x, y, z], attenuation, brightness)) in volumes {
+            let radius = attenuation.cutoff_distance(brightness);
+            if radius <= 0.0 {
+                continue;
+            }
+            volume.set_uniform("lightVolume", UniformValue::from([x, y, z, radius]));
+            volume.set_uniform("lightType", kind);
+            volume.set_uniform("lightIndex", index as i32);
+            unsafe {
+                gl::DrawElements(
+                    gl::TRIANGLES,
+                    self.sphere_count,
+                    gl::UNSIGNED_INT,
+                    std::ptr::null(),
+                );
+            }
+        }
+
+        unsafe {
+            gl::Disable(gl::DEPTH_CLAMP);
+        }
+        GL_STATE.bind_vertex_array(0);
+        GL_STATE.set_pipeline(&pipeline);
+        GL_STATE.enable(Capability::DepthTest);
+        unsafe {
+            // 复制深度，使前向渲染的物体被不透明物体正确遮挡
+            let mut target = 0;
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut target);
+            let (w, h) = (width as GLint, height as GLint);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.gbuffer.id());
+            gl::BlitFramebuffer(0, 0, w, h, 0, 0, w, h, gl::DEPTH_BUFFER_BIT, gl::NEAREST);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target as GLuint);
+        }
+    }
+
+    /// 以延迟渲染绘制一组模型，透明材质的模型以前向渲染绘制
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 模型使用其默认材质，参见 `MODEL_MANAGER.set_material`；不透明材质的着色器需遵循
+    /// G-buffer 的输出约定，没有材质的模型以 `GEOMETRY_SHADER` 绘制。
+    /// 透明物体在光照阶段之后按由远及近的顺序绘制，启用 alpha 混合且不写入深度。
+    /// 每个模型绘制时设置 `model`、`view` 与 `projection` uniform
+    ///
+    /// # 参数 Parameters
+    /// * `models` - 模型名称与模型矩阵
+    /// * `view` - 摄像机的视图矩阵
+    /// * `projection` - 摄像机的投影矩阵
+    pub fn render_models(
+        &mut self,
+        models: &[(&str, Mat4<f32>)],
+        view: Mat4<f32>,
+        projection: Mat4<f32>,
+    ) {
+        let mut opaque = vec![];
+        let mut transparent = vec![];
+        for &(name, model) in models {
+            let material = MODEL_MANAGER.material(name);
+            let is_transparent = material
+                .and_then(|material| MATERIAL_MANAGER.get(&material))
+                .map(|material| material.is_transparent())
+                .unwrap_or(false);
+            if is_transparent {
+                transparent.push((name, model));
+            } else {
+                opaque.push((name, model));
+            }
+        }
+        let draw = |name: &str, model: Mat4<f32>| {
+            MODEL_MANAGER.draw_model_with(
+                name,
+                &[
+                    ("model", model.into()),
+                    ("view", view.into()),
+                    ("projection", projection.into()),
+                ],
+            );
+        };
+
+        self.begin_geometry();
+        if let Some(geometry) = SHADER_MANAGER.get(GEOMETRY_SHADER) {
+            for &(name, model) in &opaque {
+                // 没有默认材质的模型沿用此着色器
+                geometry.use_program();
+                draw(name, model);
+            }
+        }
+        self.end_geometry();
+        self.lighting(view, projection);
+
+        if transparent.is_empty() {
+            return;
+        }
+        let eye = camera_position(view);
+        let distance = |m: &Mat4<f32>| (0..3).map(|i| (m[i][3] - eye[i]).powi(2)).sum::<f32>();
+        transparent.sort_by(|a, b| distance(&b.1).total_cmp(&distance(&a.1)));
+        GL_STATE.enable(Capability::Blend);
+        GL_STATE.set_blend_func(BlendFunc::ALPHA);
+        GL_STATE.set_depth_mask(false);
+        for (name, model) in transparent {
+            draw(name, model);
+        }
+        GL_STATE.set_depth_mask(true);
+        GL_STATE.disable(Capability::Blend);
+    }
+}
+
+impl Drop for DeferredRenderer {
+    fn drop(&mut self) {
+        let (vao, vbo, ebo) = self.sphere;
+        GL_STATE.delete_vertex_array(vao);
+        GL_STATE.delete_vertex_array(self.vao);
+        unsafe {
+            gl::DeleteBuffers(1, &vbo);
+            gl::DeleteBuffers(1, &ebo);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sphere_mesh() {
+        let (vertices, indices) = sphere_mesh(16, 8);
+        assert_eq!(vertices.len(), 17 * 9 * 3);
+        assert_eq!(indices.len(), 16 * 8 * 6);
+        let count = (vertices.len() / 3) as u32;
+        assert!(indices.iter().all(|&i| i < count));
+        // 所有顶点都在单位球外
+        for v in vertices.chunks(3) {
+            let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+            assert!(len >= 1.0);
+        }
+        // 所有非退化三角形的法线背向球心
+        let vertex = |i: u32| {
+            let i = i as usize * 3;
+            [vertices[i], vertices[i + 1], vertices[i + 2]]
+        };
+        for triangle in indices.chunks(3) {
+            let [p, q, r] = [
+                vertex(triangle[0]),
+                vertex(triangle[1]),
+                vertex(triangle[2]),
+            ];
+            let u = [q[0] - p[0], q[1] - p[1], q[2] - p[2]];
+            let v = [r[0] - p[0], r[1] - p[1], r[2] - p[2]];
+            let normal = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let area = (0..3).map(|i| normal[i] * normal[i]).sum::<f32>();
+            if area < 1e-12 {
+                continue;
+            }
+            let facing = (0..3)
+                .map(|i| normal[i] * (p[i] + q[i] + r[i]))
+                .sum::<f32>();
+            assert!(facing > 0.0, "三角形 {:?} 朝内", triangle);
+        }
+    }
+
+    #[test]
+    fn test_brightness() {
+        assert_eq!(brightness([1.0, 0.5, 0.25], 2.0), 2.0);
+        assert_eq!(brightness([0.0; 3], 10.0), 0.0);
+    }
+}
@@ -0,0 +1,107 @@
+use mats::{perspective, radian, Mat4};
+
+/// 网格渲染组件，与实体的 `Transform` 一起由渲染系统绘制
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::ecs::MeshRenderer;
+///
+/// let renderer = MeshRenderer::new("cube").material("wood");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshRenderer {
+    /// `MODEL_MANAGER` 中的模型名称
+    pub model: String,
+    /// `MATERIAL_MANAGER` 中的材质名称，`None` 表示使用模型的默认材质
+    pub material: Option<String>,
+    /// 是否绘制
+    pub visible: bool,
+}
+
+impl MeshRenderer {
+    /// 以模型的默认材质绘制指定模型
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            material: None,
+            visible: true,
+        }
+    }
+
+    /// 设置材质
+    pub fn material(mut self, material: &str) -> Self {
+        self.material = Some(material.to_string());
+        self
+    }
+
+    /// 设置是否绘制
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+}
+
+/// 摄像机组件，视图矩阵为实体 `Transform` 的逆(忽略缩放)，朝向局部 -z 方向
+///
+/// 默认值为：垂直视角 45 度、近平面 0.1、远平面 100
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::ecs::Camera;
+///
+/// let camera = Camera::default().fov(60.0).far(500.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// 垂直视角(角度)
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+    /// 存在多个摄像机时，渲染系统使用第一个活动的摄像机
+    pub active: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            fov: 45.0,
+            near: 0.1,
+            far: 100.0,
+            active: true,
+        }
+    }
+}
+
+impl Camera {
+    /// 设置垂直视角(角度)
+    pub fn fov(mut self, fov: f32) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    /// 设置近平面距离
+    pub fn near(mut self, near: f32) -> Self {
+        self.near = near;
+        self
+    }
+
+    /// 设置远平面距离
+    pub fn far(mut self, far: f32) -> Self {
+        self.far = far;
+        self
+    }
+
+    /// 设置是否活动
+    pub fn active(mut self, active: bool) -> Self {
+        self.active = active;
+        self
+    }
+
+    /// 透视投影矩阵
+    ///
+    /// # 参数 Parameters
+    /// * `aspect` - 纵横比
+    pub fn projection(&self, aspect: f32) -> Mat4<f32> {
+        perspective(radian(self.fov), aspect, self.near, self.far)
+    }
+}
@@ -0,0 +1,665 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+use lazy_static::lazy_static;
+use mats::Mat4;
+
+use crate::{
+    gl_state::Capability,
+    render_queue::{RenderItem, RenderQueue, RenderStats},
+    scene::{place_light, view_from_world},
+    App, RustCraftWrapper, CAMERA_SYSTEM, GL_STATE, LIGHT_SYSTEM,
+};
+
+mod components;
+pub use crate::{light::Light, scene::Transform};
+pub use components::{Camera, MeshRenderer};
+
+/// 实体，由索引与代数组成，实体销毁后其索引可被复用，代数随之递增
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// 实体索引
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// 实体同步到 `LIGHT_SYSTEM` 的光源名称
+    pub fn light_name(&self) -> String {
+        format!("ecs/{}", self)
+    }
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+/// 以实体索引为下标的组件存储
+trait ComponentStorage: Send {
+    fn remove_index(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Send + 'static> ComponentStorage for Vec<Option<T>> {
+    fn remove_index(&mut self, index: usize) {
+        if let Some(slot) = self.get_mut(index) {
+            *slot = None;
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+type Insert = Box<dyn FnOnce(&mut World, Entity) + Send>;
+type System = Box<dyn FnMut(&mut World, f32) + Send>;
+
+/// 实体构建器，收集组件后由 `World::spawn` 创建实体
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::ecs::{EntityBuilder, MeshRenderer, Transform, World};
+///
+/// let mut world = World::new();
+/// let cube = world.spawn(
+///     EntityBuilder::new()
+///         .with(Transform::default().translation([0.0, 0.0, -3.0]))
+///         .with(MeshRenderer::new("cube")),
+/// );
+/// assert!(world.has::<MeshRenderer>(cube));
+/// ```
+#[derive(Default)]
+pub struct EntityBuilder {
+    components: Vec<Insert>,
+}
+
+impl EntityBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加组件，同类组件重复添加时保留最后一个
+    pub fn with<T: Send + 'static>(mut self, component: T) -> Self {
+        self.components.push(Box::new(move |world, entity| {
+            world.insert(entity, component)
+        }));
+        self
+    }
+}
+
+/// 实体与组件的容器
+///
+/// 任意 `Send + 'static` 类型均可作为组件；`Transform`、`MeshRenderer`、`Camera` 与 `Light`
+/// 组件由应用程序的渲染线程每帧处理：光源同步到 `LIGHT_SYSTEM`，网格以活动摄像机绘制
+#[derive(Default)]
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    components: HashMap<TypeId, Box<dyn ComponentStorage>>,
+    systems: Vec<(String, System)>,
+    /// 已同步到 `LIGHT_SYSTEM` 的光源名称
+    lights: BTreeSet<String>,
+    clear_color: Option<[f32; 4]>,
+    render_stats: RenderStats,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            clear_color: Some([0.0, 0.0, 0.0, 1.0]),
+            ..Self::default()
+        }
+    }
+
+    /// 创建实体
+    ///
+    /// # 参数 Parameters
+    /// * `builder` - 实体的组件
+    ///
+    /// # 返回值 Returns
+    /// 新实体
+    pub fn spawn(&mut self, builder: EntityBuilder) -> Entity {
+        let entity = match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity {
+                    index,
+                    generation: self.generations[index as usize],
+                }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+        for insert in builder.components {
+            insert(self, entity);
+        }
+        entity
+    }
+
+    /// 销毁实体及其全部组件
+    ///
+    /// # 返回值 Returns
+    /// 实体存在时返回 `true`
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let index = entity.index as usize;
+        for storage in self.components.values_mut() {
+            storage.remove_index(index);
+        }
+        self.alive[index] = false;
+        self.generations[index] += 1;
+        self.free.push(entity.index);
+        true
+    }
+
+    /// 实体是否存在
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index).copied().unwrap_or(false)
+            && self.generations[index] == entity.generation
+    }
+
+    /// 全部存在的实体，按索引排序
+    pub fn entities(&self) -> Vec<Entity> {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive)
+            .map(|(i, _)| Entity {
+                index: i as u32,
+                generation: self.generations[i],
+            })
+            .collect()
+    }
+
+    fn storage<T: Send + 'static>(&self) -> Option<&Vec<Option<T>>> {
+        self.components
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref())
+    }
+
+    fn storage_mut<T: Send + 'static>(&mut self) -> Option<&mut Vec<Option<T>>> {
+        self.components
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut())
+    }
+
+    fn storage_or_insert<T: Send + 'static>(&mut self) -> &mut Vec<Option<T>> {
+        self.components
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Vec::<Option<T>>::new()))
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// 为实体添加或替换组件，实体不存在时忽略
+    pub fn insert<T: Send + 'static>(&mut self, entity: Entity, component: T) {
+        if !self.is_alive(entity) {
+            return;
+        }
+        let index = entity.index as usize;
+        let storage = self.storage_or_insert::<T>();
+        if storage.len() <= index {
+            storage.resize_with(index + 1, || None);
+        }
+        storage[index] = Some(component);
+    }
+
+    /// 移除实体的组件
+    pub fn remove<T: Send + 'static>(&mut self, entity: Entity) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>()?
+            .get_mut(entity.index as usize)
+            .and_then(Option::take)
+    }
+
+    /// 获取实体的组件
+    pub fn get<T: Send + 'static>(&self, entity: Entity) -> Option<&T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage::<T>()?
+            .get(entity.index as usize)
+            .and_then(Option::as_ref)
+    }
+
+    /// 获取实体组件的可变引用
+    pub fn get_mut<T: Send + 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>()?
+            .get_mut(entity.index as usize)
+            .and_then(Option::as_mut)
+    }
+
+    /// 实体是否具有指定组件
+    pub fn has<T: Send + 'static>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    /// 遍历具有指定组件的实体，按索引排序
+    pub fn query<T: Send + 'static>(&self) -> impl Iterator<Item = (Entity, &T)> + '_ {
+        let generations = &self.generations;
+        self.storage::<T>()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(move |(i, component)| {
+                let entity = Entity {
+                    index: i as u32,
+                    generation: generations[i],
+                };
+                component.as_ref().map(|component| (entity, component))
+            })
+    }
+
+    /// 以可变引用遍历具有指定组件的实体，按索引排序
+    pub fn query_mut<T: Send + 'static>(&mut self) -> impl Iterator<Item = (Entity, &mut T)> + '_ {
+        let generations = &self.generations;
+        let storage = self
+            .components
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<Vec<Option<T>>>());
+        storage
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(move |(i, component)| {
+                let entity = Entity {
+                    index: i as u32,
+                    generation: generations[i],
+                };
+                component.as_mut().map(|component| (entity, component))
+            })
+    }
+
+    /// 添加每帧运行的系统，系统按添加顺序在渲染之前运行
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 系统名称，用于移除
+    /// * `system` - 系统函数，参数为世界与渲染帧时间(秒)
+    pub fn add_system<F>(&mut self, name: &str, system: F)
+    where
+        F: FnMut(&mut World, f32) + Send + 'static,
+    {
+        self.systems.push((name.to_string(), Box::new(system)));
+    }
+
+    /// 移除系统
+    ///
+    /// # 返回值 Returns
+    /// 存在该名称的系统时返回 `true`
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        let count = self.systems.len();
+        self.systems.retain(|(system, _)| system != name);
+        self.systems.len() != count
+    }
+
+    /// 依次运行全部系统
+    ///
+    /// # 参数 Parameters
+    /// * `dt` - 帧时间(秒)
+    pub fn run_systems(&mut self, dt: f32) {
+        let mut systems = std::mem::take(&mut self.systems);
+        for (_, system) in systems.iter_mut() {
+            system(self, dt);
+        }
+        // 保留运行期间新添加的系统
+        systems.append(&mut self.systems);
+        self.systems = systems;
+    }
+
+    /// 设置渲染前的清屏颜色，默认为黑色，`None` 表示不清屏
+    pub fn set_clear_color(&mut self, color: Option<[f32; 4]>) {
+        self.clear_color = color;
+    }
+
+    /// 上一帧渲染的统计
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats
+    }
+
+    /// 实体的世界变换矩阵，没有 `Transform` 组件时为单位矩阵
+    pub fn world_matrix(&self, entity: Entity) -> Mat4<f32> {
+        self.get::<Transform>(entity)
+            .map(|transform| transform.matrix())
+            .unwrap_or_else(Mat4::I)
+    }
+
+    /// 第一个活动摄像机的视图矩阵与投影矩阵
+    ///
+    /// # 参数 Parameters
+    /// * `aspect` - 纵横比
+    pub fn camera_matrices(&self, aspect: f32) -> Option<(Mat4<f32>, Mat4<f32>)> {
+        self.query::<Camera>()
+            .find(|(_, camera)| camera.active)
+            .map(|(entity, camera)| {
+                (
+                    view_from_world(self.world_matrix(entity)),
+                    camera.projection(aspect),
+                )
+            })
+    }
+
+    /// 按实体的 `Transform` 放置的光源，以及不再存在、应从 `LIGHT_SYSTEM` 移除的光源名称
+    fn sync_lights(&mut self) -> (Vec<(String, Light)>, Vec<String>) {
+        let lights = self
+            .query::<Light>()
+            .map(|(entity, light)| {
+                let mut light = *light;
+                if let Some(transform) = self.get::<Transform>(entity) {
+                    place_light(&mut light, transform.matrix());
+                }
+                (entity.light_name(), light)
+            })
+            .collect::<Vec<_>>();
+        let current = lights
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<BTreeSet<_>>();
+        let removed = self.lights.difference(&current).cloned().collect();
+        self.lights = current;
+        (lights, removed)
+    }
+
+    /// 可见网格的渲染条目，按实体索引排序
+    fn drawables(&self) -> Vec<RenderItem> {
+        self.query::<MeshRenderer>()
+            .filter(|(_, renderer)| renderer.visible)
+            .map(|(entity, renderer)| {
+                let item = RenderItem::new(&renderer.model, self.world_matrix(entity));
+                match &renderer.material {
+                    Some(material) => item.material(material),
+                    None => item,
+                }
+            })
+            .collect()
+    }
+}
+
+lazy_static! {
+    /// 应用程序的渲染线程每帧运行的世界
+    pub static ref WORLD: RustCraftWrapper<World> = RustCraftWrapper::new(World::new());
+}
+
+impl RustCraftWrapper<World> {
+    /// 在锁定的世界上执行函数并返回其结果
+    ///
+    /// # 注解 Note
+    ///
+    /// 函数内不应再调用 `WORLD` 的方法，否则会导致死锁
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// let count = WORLD.with(|world| world.query::<MeshRenderer>().count());
+    /// ```
+    pub fn with<R, F: FnOnce(&mut World) -> R>(&self, f: F) -> R {
+        let mut ret = None;
+        self.apply(|world| ret = Some(f(world)));
+        ret.unwrap()
+    }
+
+    /// 创建实体，参见 `World::spawn`
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// WORLD.spawn(
+    ///     EntityBuilder::new()
+    ///         .with(Transform::default().translation([0.0, 0.0, 3.0]))
+    ///         .with(Camera::default()),
+    /// );
+    /// WORLD.spawn(
+    ///     EntityBuilder::new()
+    ///         .with(Transform::default())
+    ///         .with(MeshRenderer::new("cube").material("wood")),
+    /// );
+    /// WORLD.spawn(
+    ///     EntityBuilder::new()
+    ///         .with(Transform::default().translation([0.0, 2.0, 0.0]))
+    ///         .with(Light::from(PointLight::new([0.0; 3], [1.0; 3]).range(20.0))),
+    /// );
+    /// ```
+    pub fn spawn(&self, builder: EntityBuilder) -> Entity {
+        self.with(|world| world.spawn(builder))
+    }
+
+    /// 销毁实体，参见 `World::despawn`
+    pub fn despawn(&self, entity: Entity) -> bool {
+        self.with(|world| world.despawn(entity))
+    }
+
+    /// 为实体添加或替换组件
+    pub fn insert<T: Send + 'static>(&self, entity: Entity, component: T) {
+        self.apply(|world| world.insert(entity, component));
+    }
+
+    /// 移除实体的组件
+    pub fn remove<T: Send + 'static>(&self, entity: Entity) -> Option<T> {
+        self.with(|world| world.remove::<T>(entity))
+    }
+
+    /// 获取实体组件的副本
+    pub fn get<T: Clone + Send + 'static>(&self, entity: Entity) -> Option<T> {
+        self.with(|world| world.get::<T>(entity).cloned())
+    }
+
+    /// 修改实体的组件
+    ///
+    /// # 返回值 Returns
+    /// 实体具有该组件时返回 `true`
+    pub fn update<T: Send + 'static, F: FnOnce(&mut T)>(&self, entity: Entity, f: F) -> bool {
+        self.with(|world| world.get_mut::<T>(entity).map(f).is_some())
+    }
+
+    /// 添加每帧运行的系统，参见 `World::add_system`
+    ///
+    /// # 注解 Note
+    ///
+    /// 系统运行时 `WORLD` 处于锁定状态，系统内应通过参数访问世界
+    pub fn add_system<F>(&self, name: &str, system: F)
+    where
+        F: FnMut(&mut World, f32) + Send + 'static,
+    {
+        self.apply(|world| world.add_system(name, system));
+    }
+
+    /// 移除系统
+    pub fn remove_system(&self, name: &str) -> bool {
+        self.with(|world| world.remove_system(name))
+    }
+
+    /// 上一帧渲染的统计
+    pub fn render_stats(&self) -> RenderStats {
+        self.with(|world| world.render_stats())
+    }
+
+    /// 设置渲染前的清屏颜色，默认为黑色，`None` 表示不清屏
+    ///
+    /// # 注解 Note
+    ///
+    /// 渲染线程每帧先清屏并绘制实体，再调用渲染回调，渲染回调中不应再清屏，
+    /// 否则实体的绘制结果会被清除
+    pub fn set_clear_color(&self, color: Option<[f32; 4]>) {
+        self.apply(|world| world.set_clear_color(color));
+    }
+
+    /// 运行系统并将光源组件同步到 `LIGHT_SYSTEM`，由渲染线程在上传光源之前调用
+    pub(crate) fn run_frame(&self, dt: f32) {
+        let (lights, removed) = self.with(|world| {
+            world.run_systems(dt);
+            world.sync_lights()
+        });
+        for name in removed {
+            LIGHT_SYSTEM.remove(&name);
+        }
+        for (name, light) in lights {
+            LIGHT_SYSTEM.set(&name, light);
+        }
+    }
+
+    /// 清屏并以活动摄像机绘制全部可见网格，由渲染线程在渲染回调之前调用
+    ///
+    /// # 注解 Note
+    ///
+    /// 没有活动摄像机时使用 `CAMERA_SYSTEM` 的视图矩阵与默认投影；绘制经由 `RenderQueue` 排序，
+    /// 统计可由 `render_stats` 获取
+    pub(crate) fn render(&self) {
+        let (w, h) = App::window_size();
+        let aspect = if h > 0 { w as f32 / h as f32 } else { 1.0 };
+        let (clear_color, camera, drawables) = self.with(|world| {
+            (
+                world.clear_color,
+                world.camera_matrices(aspect),
+                world.drawables(),
+            )
+        });
+        if let Some([r, g, b, a]) = clear_color {
+            unsafe {
+                gl::ClearColor(r, g, b, a);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            }
+        }
+        if drawables.is_empty() {
+            self.apply(|world| world.render_stats = RenderStats::default());
+            return;
+        }
+        let (view, projection) = camera.unwrap_or_else(|| {
+            (
+                CAMERA_SYSTEM.view_matrix(),
+                Camera::default().projection(aspect),
+            )
+        });
+        let pipeline = GL_STATE.pipeline();
+        GL_STATE.enable(Capability::DepthTest);
+        let mut queue = RenderQueue::new();
+        for item in drawables {
+            queue.submit(item);
+        }
+        let stats = queue.flush(view, projection);
+        GL_STATE.set_pipeline(&pipeline);
+        self.apply(|world| world.render_stats = stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::PointLight;
+
+    #[test]
+    fn test_entities() {
+        let mut world = World::new();
+        let a = world.spawn(EntityBuilder::new().with(1u32).with("a"));
+        let b = world.spawn(EntityBuilder::new().with(2u32));
+        assert_eq!(world.get::<u32>(a), Some(&1));
+        assert_eq!(world.get::<&str>(b), None);
+        *world.get_mut::<u32>(b).unwrap() += 1;
+        assert_eq!(
+            world
+                .query::<u32>()
+                .map(|(e, v)| (e, *v))
+                .collect::<Vec<_>>(),
+            vec![(a, 1), (b, 3)]
+        );
+
+        assert!(world.despawn(a));
+        assert!(!world.despawn(a));
+        assert_eq!(world.get::<u32>(a), None);
+        // 索引复用后旧实体仍然无效
+        let c = world.spawn(EntityBuilder::new());
+        assert_eq!(c.index(), a.index());
+        assert!(!world.is_alive(a));
+        assert!(!world.has::<&str>(c));
+        assert_eq!(world.entities(), vec![c, b]);
+        assert_eq!(world.remove::<u32>(b), Some(3));
+        assert_eq!(world.query::<u32>().count(), 0);
+        // 查询未插入过的组件类型不会分配存储
+        let count = world.components.len();
+        assert_eq!(world.get_mut::<u64>(b), None);
+        assert_eq!(world.remove::<u64>(b), None);
+        assert_eq!(world.components.len(), count);
+    }
+
+    #[test]
+    fn test_systems() {
+        let mut world = World::new();
+        let e = world.spawn(EntityBuilder::new().with(0.0f32));
+        world.add_system("time", |world, dt| {
+            for (_, t) in world.query_mut::<f32>() {
+                *t += dt;
+            }
+        });
+        world.run_systems(0.5);
+        world.run_systems(0.25);
+        assert_eq!(world.get::<f32>(e), Some(&0.75));
+        assert!(world.remove_system("time"));
+        world.run_systems(1.0);
+        assert_eq!(world.get::<f32>(e), Some(&0.75));
+    }
+
+    #[test]
+    fn test_render_data() {
+        let mut world = World::new();
+        let camera = world.spawn(
+            EntityBuilder::new()
+                .with(Transform::default().translation([0.0, 1.0, 5.0]))
+                .with(Camera::default()),
+        );
+        let lamp = world.spawn(
+            EntityBuilder::new()
+                .with(Transform::default().translation([1.0, 2.0, 3.0]))
+                .with(Light::from(PointLight::new([0.0; 3], [1.0; 3])))
+                .with(MeshRenderer::new("lamp").visible(false)),
+        );
+        world.spawn(EntityBuilder::new().with(MeshRenderer::new("cube").material("wood")));
+
+        let (view, _) = world.camera_matrices(1.0).unwrap();
+        assert_eq!(crate::light::camera_position(view), [0.0, 1.0, 5.0]);
+        world.insert(camera, Camera::default().active(false));
+        assert!(world.camera_matrices(1.0).is_none());
+
+        let drawables = world.drawables();
+        assert_eq!(drawables.len(), 1);
+        assert_eq!(drawables[0].material.as_deref(), Some("wood"));
+
+        let (lights, removed) = world.sync_lights();
+        assert!(removed.is_empty());
+        assert_eq!(lights[0].0, lamp.light_name());
+        match lights[0].1 {
+            Light::Point(light) => assert_eq!(light.position, [1.0, 2.0, 3.0]),
+            _ => unreachable!(),
+        }
+        world.despawn(lamp);
+        let (lights, removed) = world.sync_lights();
+        assert!(lights.is_empty());
+        assert_eq!(removed, vec![lamp.light_name()]);
+    }
+}
@@ -0,0 +1,544 @@
+use gl::types::*;
+
+use crate::{
+    debug, gl_utils,
+    texture::{DepthFormat, PixelFormat, TextureOptions, Wrap},
+    warn, App, Handle, Texture, ASSETS, GL_STATE, TEXTURE_MANAGER,
+};
+
+/// 帧缓冲尺寸
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FramebufferSize {
+    /// 与窗口尺寸一致
+    Window,
+    /// 窗口尺寸乘以比例，例如 `0.5` 表示半分辨率
+    Scaled(f32),
+    /// 固定尺寸，不随窗口变化
+    Fixed(u32, u32),
+}
+
+impl FramebufferSize {
+    /// 按窗口尺寸计算实际尺寸，宽高至少为 1
+    pub fn resolve(self, window: (i32, i32)) -> (u32, u32) {
+        let (w, h) = match self {
+            FramebufferSize::Window => (window.0.max(0) as u32, window.1.max(0) as u32),
+            FramebufferSize::Scaled(scale) => (
+                (window.0 as f32 * scale).round() as u32,
+                (window.1 as f32 * scale).round() as u32,
+            ),
+            FramebufferSize::Fixed(w, h) => (w, h),
+        };
+        (w.max(1), h.max(1))
+    }
+}
+
+/// 深度附件
+#[derive(Debug, Clone, Copy)]
+enum DepthAttachment {
+    /// 渲染缓冲，无法在着色器中采样
+    Renderbuffer(DepthFormat),
+    /// 深度纹理，登记于 `TEXTURE_MANAGER`
+    Texture(DepthFormat, TextureOptions),
+}
+
+impl DepthAttachment {
+    fn format(&self) -> DepthFormat {
+        match self {
+            DepthAttachment::Renderbuffer(format) | DepthAttachment::Texture(format, _) => *format,
+        }
+    }
+}
+
+/// 颜色附件在 `TEXTURE_MANAGER` 中的名称
+fn color_name(name: &str, index: usize) -> String {
+    format!("{}.color{}", name, index)
+}
+
+/// 深度附件在 `TEXTURE_MANAGER` 中的名称
+fn depth_name(name: &str) -> String {
+    format!("{}.depth", name)
+}
+
+/// 附件纹理的默认选项：边缘截取、线性过滤、不生成多级渐远纹理
+fn attachment_options() -> TextureOptions {
+    TextureOptions::default()
+        .wrap(Wrap::ClampToEdge)
+        .mipmaps(false)
+}
+
+/// 帧缓冲构建器
+///
+/// # 示例 Examples
+/// ```ignore
+/// // 两个颜色附件(颜色与法线)加可采样的深度附件，4 倍多重采样
+/// let mut gbuffer = FramebufferBuilder::new("scene")
+///     .color(PixelFormat::Rgba8)
+///     .color(PixelFormat::Rgba32F)
+///     .depth_texture(DepthFormat::Depth24)
+///     .samples(4)
+///     .build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct FramebufferBuilder {
+    name: String,
+    size: FramebufferSize,
+    colors: Vec<(PixelFormat, TextureOptions)>,
+    depth: Option<DepthAttachment>,
+    samples: u32,
+}
+
+impl FramebufferBuilder {
+    /// 创建构建器
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 帧缓冲名称，附件纹理以 `名称.color0`、`名称.depth` 的形式登记于 `TEXTURE_MANAGER`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            size: FramebufferSize::Window,
+            colors: vec![],
+            depth: None,
+            samples: 0,
+        }
+    }
+
+    /// 设置帧缓冲尺寸，默认与窗口尺寸一致
+    pub fn size(mut self, size: FramebufferSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// 添加颜色附件，按添加顺序对应着色器中的输出位置 0、1、2 ...
+    ///
+    /// # 注解 Note
+    ///
+    /// 浮点格式默认以 16 位浮点存储，参见 `TextureOptions::half_float`
+    pub fn color(self, format: PixelFormat) -> Self {
+        self.color_with(format, attachment_options())
+    }
+
+    /// 以指定选项添加颜色附件
+    ///
+    /// # 注解 Note
+    ///
+    /// 附件纹理不生成多级渐远纹理，`options.mipmaps` 将被忽略
+    pub fn color_with(mut self, format: PixelFormat, options: TextureOptions) -> Self {
+        self.colors.push((format, options.mipmaps(false)));
+        self
+    }
+
+    /// 添加深度(与模板)附件，以渲染缓冲存储，无法在着色器中采样
+    pub fn depth(mut self, format: DepthFormat) -> Self {
+        self.depth = Some(DepthAttachment::Renderbuffer(format));
+        self
+    }
+
+    /// 添加可在着色器中采样的深度(与模板)附件
+    pub fn depth_texture(mut self, format: DepthFormat) -> Self {
+        let options = attachment_options();
+        self.depth = Some(DepthAttachment::Texture(format, options));
+        self
+    }
+
+    /// 设置多重采样数，`0` 表示不启用多重采样
+    ///
+    /// # 注解 Note
+    ///
+    /// 启用多重采样时实际渲染至多重采样渲染缓冲，`Framebuffer::unbind` 或
+    /// `Framebuffer::resolve` 将其解析到附件纹理中；超过显卡上限时取上限
+    pub fn samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// 创建帧缓冲
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn build(&self) -> Result<Framebuffer, String> {
+        if self.colors.is_empty() && self.depth.is_none() {
+            return Err(format!("帧缓冲 {} 没有任何附件", self.name));
+        }
+        let (mut max_colors, mut max_samples) = (0, 0);
+        unsafe {
+            gl::GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max_colors);
+            gl::GetIntegerv(gl::MAX_SAMPLES, &mut max_samples);
+        }
+        if self.colors.len() > max_colors as usize {
+            return Err(format!(
+                "帧缓冲 {} 有 {} 个颜色附件, 超过了显卡上限 {}",
+                self.name,
+                self.colors.len(),
+                max_colors
+            ));
+        }
+        let mut spec = self.clone();
+        if spec.samples > max_samples as u32 {
+            warn!(
+                "FramebufferBuilder",
+                "多重采样数 {} 超过了显卡上限 {}", spec.samples, max_samples
+            );
+            spec.samples = max_samples as u32;
+        }
+        let (width, height) = spec.size.resolve(App::window_size());
+        let mut framebuffer = Framebuffer {
+            spec,
+            width,
+            height,
+            fbo: 0,
+            msaa_fbo: 0,
+            renderbuffers: vec![],
+            textures: vec![],
+        };
+        framebuffer.allocate()?;
+        debug!(
+            "FramebufferBuilder",
+            "已创建 {}x{} 的帧缓冲 {}", width, height, framebuffer.spec.name
+        );
+        Ok(framebuffer)
+    }
+}
+
+/// 帧缓冲对象，用于渲染到纹理
+///
+/// 附件纹理登记于 `TEXTURE_MANAGER`，后续渲染阶段可按名称绑定并采样；
+/// 尺寸变化时附件纹理被原地替换，其名称与句柄保持不变
+///
+/// # 示例 Examples
+/// ```ignore
+/// // 初始化
+/// let mut fb = FramebufferBuilder::new("scene")
+///     .color(PixelFormat::Rgba8)
+///     .depth(DepthFormat::Depth24Stencil8)
+///     .build()?;
+/// // 渲染循环
+/// fb.bind();
+/// draw_scene();
+/// fb.unbind();
+/// TEXTURE_MANAGER.bind("scene.color0", 0);
+/// draw_fullscreen_quad();
+/// ```
+pub struct Framebuffer {
+    spec: FramebufferBuilder,
+    width: u32,
+    height: u32,
+    /// 附件纹理所在的帧缓冲
+    fbo: GLuint,
+    /// 多重采样帧缓冲，未启用多重采样时为 0
+    msaa_fbo: GLuint,
+    renderbuffers: Vec<GLuint>,
+    /// 颜色附件纹理，可采样的深度附件纹理位于末尾
+    textures: Vec<Handle<Texture>>,
+}
+
+impl Framebuffer {
+    /// 按当前尺寸(重新)分配全部附件
+    fn allocate(&mut self) -> Result<(), String> {
+        self.release();
+        let (width, height) = (self.width, self.height);
+        let colors = self.spec.colors.clone();
+        let mut ids = vec![];
+        for (i, (format, options)) in colors.iter().enumerate() {
+            let texture = Texture::empty(width, height, *format, options);
+            ids.push(self.store(i, &color_name(&self.spec.name, i), texture));
+        }
+        if let Some(DepthAttachment::Texture(format, options)) = self.spec.depth {
+            let texture = Texture::depth(width, height, format, &options);
+            let index = self.spec.colors.len();
+            ids.push(self.store(index, &depth_name(&self.spec.name), texture));
+        }
+        let samples = self.spec.samples;
+        let depth = self.spec.depth;
+        unsafe {
+            gl::GenFramebuffers(1, &mut self.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            for (i, id) in ids.iter().enumerate() {
+                let attachment = match depth {
+                    Some(depth) if i == colors.len() => depth.format().gl_attachment(),
+                    _ => gl::COLOR_ATTACHMENT0 + i as GLenum,
+                };
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, *id, 0);
+            }
+            if let (Some(DepthAttachment::Renderbuffer(format)), 0) = (depth, samples) {
+                self.attach_renderbuffer(format.gl_attachment(), format.gl_internal_format(), 0);
+            }
+            gl_utils::set_draw_buffers(self.spec.colors.len());
+            let mut result = gl_utils::check_framebuffer();
+
+            if samples > 0 && result.is_ok() {
+                gl::GenFramebuffers(1, &mut self.msaa_fbo);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.msaa_fbo);
+                for (i, (format, options)) in colors.iter().enumerate() {
+                    let attachment = gl::COLOR_ATTACHMENT0 + i as GLenum;
+                    let internal_format = format.gl_internal_format(options);
+                    self.attach_renderbuffer(attachment, internal_format, samples);
+                }
+                if let Some(depth) = depth {
+                    let format = depth.format();
+                    let internal_format = format.gl_internal_format();
+                    self.attach_renderbuffer(format.gl_attachment(), internal_format, samples);
+                }
+                gl_utils::set_draw_buffers(self.spec.colors.len());
+                result = gl_utils::check_framebuffer();
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            result.map_err(|e| format!("无法创建帧缓冲 {}: {}", self.spec.name, e))
+        }
+    }
+
+    /// 替换或登记第 `index` 个附件纹理，返回其纹理对象名称
+    fn store(&mut self, index: usize, name: &str, texture: Texture) -> GLuint {
+        let id = texture.id;
+        let mut texture = Some(texture);
+        if let Some(handle) = self.textures.get(index) {
+            ASSETS.with_mut(handle, |old| *old = texture.take().unwrap());
+        }
+        // 附件纹理尚未登记，或已被资源系统移除
+        if let Some(texture) = texture {
+            let handle = TEXTURE_MANAGER.add(name, texture);
+            match self.textures.get_mut(index) {
+                Some(old) => *old = handle,
+                None => self.textures.push(handle),
+            }
+        }
+        id
+    }
+
+    /// 创建渲染缓冲并附加到当前绑定的帧缓冲
+    unsafe fn attach_renderbuffer(
+        &mut self,
+        attachment: GLenum,
+        internal_format: GLenum,
+        samples: u32,
+    ) {
+        let rbo = gl_utils::create_renderbuffer(self.width, self.height, internal_format, samples);
+        gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment, gl::RENDERBUFFER, rbo);
+        self.renderbuffers.push(rbo);
+    }
+
+    /// 释放帧缓冲与渲染缓冲，附件纹理由资源系统管理
+    fn release(&mut self) {
+        unsafe {
+            if self.fbo != 0 {
+                gl::DeleteFramebuffers(1, &self.fbo);
+            }
+            if self.msaa_fbo != 0 {
+                gl::DeleteFramebuffers(1, &self.msaa_fbo);
+            }
+            if !self.renderbuffers.is_empty() {
+                gl::DeleteRenderbuffers(
+                    self.renderbuffers.len() as i32,
+                    self.renderbuffers.as_ptr(),
+                );
+            }
+        }
+        self.fbo = 0;
+        self.msaa_fbo = 0;
+        self.renderbuffers.clear();
+    }
+
+    /// 绑定帧缓冲并将视口设置为帧缓冲尺寸
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 尺寸随窗口变化时，将在此处重新分配附件
+    pub fn bind(&mut self) {
+        let (width, height) = self.spec.size.resolve(App::window_size());
+        if (width, height) != (self.width, self.height) {
+            debug!(
+                "Framebuffer",
+                "帧缓冲 {} 的尺寸变为 {}x{}", self.spec.name, width, height
+            );
+            self.width = width;
+            self.height = height;
+            if let Err(e) = self.allocate() {
+                warn!("Framebuffer", "{}", e);
+            }
+        }
+        let fbo = if self.msaa_fbo != 0 {
+            self.msaa_fbo
+        } else {
+            self.fbo
+        };
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+        }
+        GL_STATE.set_viewport([0, 0, width as i32, height as i32]);
+    }
+
+    /// 解析多重采样结果并恢复默认帧缓冲与窗口视口
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn unbind(&self) {
+        self.resolve();
+        let (width, height) = App::window_size();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        GL_STATE.set_viewport([0, 0, width, height]);
+    }
+
+    /// 将多重采样渲染缓冲解析到附件纹理，未启用多重采样时不执行任何操作
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，调用后绑定的帧缓冲为默认帧缓冲
+    pub fn resolve(&self) {
+        if self.msaa_fbo == 0 {
+            return;
+        }
+        let (w, h) = (self.width as i32, self.height as i32);
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.msaa_fbo);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.fbo);
+            // 每次仅能从一个读缓冲解析到对应的绘制缓冲
+            for i in 0..self.spec.colors.len() {
+                let attachment = gl::COLOR_ATTACHMENT0 + i as GLenum;
+                gl::ReadBuffer(attachment);
+                gl::DrawBuffers(1, &attachment);
+                gl::BlitFramebuffer(0, 0, w, h, 0, 0, w, h, gl::COLOR_BUFFER_BIT, gl::NEAREST);
+            }
+            if let Some(DepthAttachment::Texture(format, _)) = self.spec.depth {
+                let mut mask = gl::DEPTH_BUFFER_BIT;
+                if format.has_stencil() {
+                    mask |= gl::STENCIL_BUFFER_BIT;
+                }
+                gl::BlitFramebuffer(0, 0, w, h, 0, 0, w, h, mask, gl::NEAREST);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl_utils::set_draw_buffers(self.spec.colors.len());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// 修改帧缓冲尺寸，附件将在下次 `bind` 时重新分配
+    pub fn set_size(&mut self, size: FramebufferSize) {
+        self.spec.size = size;
+    }
+
+    /// 帧缓冲名称
+    pub fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    /// 当前尺寸 `(宽度, 高度)`
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// 多重采样数，未启用时为 0
+    pub fn samples(&self) -> u32 {
+        self.spec.samples
+    }
+
+    /// 附件纹理所在帧缓冲的对象名称，可用于 `glBlitFramebuffer`
+    pub fn id(&self) -> GLuint {
+        self.fbo
+    }
+
+    /// 颜色附件的数量
+    pub fn color_count(&self) -> usize {
+        self.spec.colors.len()
+    }
+
+    /// 第 `index` 个颜色附件在 `TEXTURE_MANAGER` 中的名称
+    pub fn color_name(&self, index: usize) -> Option<String> {
+        (index < self.spec.colors.len()).then(|| color_name(&self.spec.name, index))
+    }
+
+    /// 深度附件在 `TEXTURE_MANAGER` 中的名称，深度附件不可采样时为 `None`
+    pub fn depth_name(&self) -> Option<String> {
+        match self.spec.depth {
+            Some(DepthAttachment::Texture(..)) => Some(depth_name(&self.spec.name)),
+            _ => None,
+        }
+    }
+
+    /// 第 `index` 个颜色附件纹理的资源句柄
+    pub fn color(&self, index: usize) -> Option<Handle<Texture>> {
+        (index < self.spec.colors.len())
+            .then(|| self.textures.get(index).cloned())
+            .flatten()
+    }
+
+    /// 深度附件纹理的资源句柄，深度附件不可采样时为 `None`
+    pub fn depth(&self) -> Option<Handle<Texture>> {
+        match self.spec.depth {
+            Some(DepthAttachment::Texture(..)) => self.textures.last().cloned(),
+            _ => None,
+        }
+    }
+
+    /// 将第 `index` 个颜色附件纹理绑定到指定纹理单元
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn bind_color(&self, index: usize, unit: u32) {
+        match self.color(index) {
+            Some(handle) => {
+                ASSETS.with(&handle, |texture| texture.bind(unit));
+            }
+            None => {
+                warn!(
+                    "Framebuffer",
+                    "帧缓冲 {} 没有第 {} 个颜色附件", self.spec.name, index
+                );
+            }
+        }
+    }
+
+    /// 将深度附件纹理绑定到指定纹理单元
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn bind_depth(&self, unit: u32) {
+        match self.depth() {
+            Some(handle) => {
+                ASSETS.with(&handle, |texture| texture.bind(unit));
+            }
+            None => {
+                warn!(
+                    "Framebuffer",
+                    "帧缓冲 {} 没有可采样的深度附件", self.spec.name
+                );
+            }
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    /// 释放帧缓冲对象；附件纹理仍登记于 `TEXTURE_MANAGER`，直至被同名纹理覆盖
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(FramebufferSize::Window.resolve((800, 600)), (800, 600));
+        assert_eq!(FramebufferSize::Scaled(0.5).resolve((801, 600)), (401, 300));
+        assert_eq!(
+            FramebufferSize::Fixed(1024, 1024).resolve((800, 600)),
+            (1024, 1024)
+        );
+        // 窗口最小化时尺寸为 0
+        assert_eq!(FramebufferSize::Window.resolve((0, 0)), (1, 1));
+    }
+
+    #[test]
+    fn test_attachment_names() {
+        assert_eq!(color_name("gbuffer", 2), "gbuffer.color2");
+        assert_eq!(depth_name("shadow"), "shadow.depth");
+    }
+}
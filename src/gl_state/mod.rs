@@ -0,0 +1,721 @@
+use std::collections::HashMap;
+
+use gl::types::*;
+use lazy_static::lazy_static;
+
+use crate::RustCraftWrapper;
+
+/// 由 `GL_STATE` 管理的开关
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    DepthTest,
+    Blend,
+    CullFace,
+}
+
+impl Capability {
+    const ALL: [Capability; 3] = [
+        Capability::DepthTest,
+        Capability::Blend,
+        Capability::CullFace,
+    ];
+
+    fn gl(self) -> GLenum {
+        match self {
+            Capability::DepthTest => gl::DEPTH_TEST,
+            Capability::Blend => gl::BLEND,
+            Capability::CullFace => gl::CULL_FACE,
+        }
+    }
+}
+
+/// 混合因子
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+}
+
+impl BlendFactor {
+    const ALL: [BlendFactor; 15] = [
+        BlendFactor::Zero,
+        BlendFactor::One,
+        BlendFactor::SrcColor,
+        BlendFactor::OneMinusSrcColor,
+        BlendFactor::DstColor,
+        BlendFactor::OneMinusDstColor,
+        BlendFactor::SrcAlpha,
+        BlendFactor::OneMinusSrcAlpha,
+        BlendFactor::DstAlpha,
+        BlendFactor::OneMinusDstAlpha,
+        BlendFactor::ConstantColor,
+        BlendFactor::OneMinusConstantColor,
+        BlendFactor::ConstantAlpha,
+        BlendFactor::OneMinusConstantAlpha,
+        BlendFactor::SrcAlphaSaturate,
+    ];
+
+    fn gl(self) -> GLenum {
+        match self {
+            BlendFactor::Zero => gl::ZERO,
+            BlendFactor::One => gl::ONE,
+            BlendFactor::SrcColor => gl::SRC_COLOR,
+            BlendFactor::OneMinusSrcColor => gl::ONE_MINUS_SRC_COLOR,
+            BlendFactor::DstColor => gl::DST_COLOR,
+            BlendFactor::OneMinusDstColor => gl::ONE_MINUS_DST_COLOR,
+            BlendFactor::SrcAlpha => gl::SRC_ALPHA,
+            BlendFactor::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+            BlendFactor::DstAlpha => gl::DST_ALPHA,
+            BlendFactor::OneMinusDstAlpha => gl::ONE_MINUS_DST_ALPHA,
+            BlendFactor::ConstantColor => gl::CONSTANT_COLOR,
+            BlendFactor::OneMinusConstantColor => gl::ONE_MINUS_CONSTANT_COLOR,
+            BlendFactor::ConstantAlpha => gl::CONSTANT_ALPHA,
+            BlendFactor::OneMinusConstantAlpha => gl::ONE_MINUS_CONSTANT_ALPHA,
+            BlendFactor::SrcAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+        }
+    }
+
+    fn from_gl(value: GLenum) -> Option<Self> {
+        Self::ALL.into_iter().find(|factor| factor.gl() == value)
+    }
+}
+
+/// 颜色与 alpha 分量的混合函数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlendFunc {
+    pub src_rgb: BlendFactor,
+    pub dst_rgb: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+}
+
+impl BlendFunc {
+    /// OpenGL 的默认混合函数，即不混合
+    pub const REPLACE: BlendFunc = BlendFunc::new(BlendFactor::One, BlendFactor::Zero);
+    /// 按源 alpha 混合
+    pub const ALPHA: BlendFunc =
+        BlendFunc::new(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha);
+    /// 叠加
+    pub const ADDITIVE: BlendFunc = BlendFunc::new(BlendFactor::One, BlendFactor::One);
+
+    /// 颜色与 alpha 分量使用相同因子的混合函数
+    pub const fn new(src: BlendFactor, dst: BlendFactor) -> Self {
+        Self {
+            src_rgb: src,
+            dst_rgb: dst,
+            src_alpha: src,
+            dst_alpha: dst,
+        }
+    }
+}
+
+impl Default for BlendFunc {
+    fn default() -> Self {
+        Self::REPLACE
+    }
+}
+
+/// 深度比较函数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DepthFunc {
+    Never,
+    #[default]
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl DepthFunc {
+    const ALL: [DepthFunc; 8] = [
+        DepthFunc::Never,
+        DepthFunc::Less,
+        DepthFunc::Equal,
+        DepthFunc::LessEqual,
+        DepthFunc::Greater,
+        DepthFunc::NotEqual,
+        DepthFunc::GreaterEqual,
+        DepthFunc::Always,
+    ];
+
+    fn gl(self) -> GLenum {
+        match self {
+            DepthFunc::Never => gl::NEVER,
+            DepthFunc::Less => gl::LESS,
+            DepthFunc::Equal => gl::EQUAL,
+            DepthFunc::LessEqual => gl::LEQUAL,
+            DepthFunc::Greater => gl::GREATER,
+            DepthFunc::NotEqual => gl::NOTEQUAL,
+            DepthFunc::GreaterEqual => gl::GEQUAL,
+            DepthFunc::Always => gl::ALWAYS,
+        }
+    }
+
+    fn from_gl(value: GLenum) -> Option<Self> {
+        Self::ALL.into_iter().find(|func| func.gl() == value)
+    }
+}
+
+/// 面剔除时剔除的面
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Face {
+    Front,
+    #[default]
+    Back,
+    FrontAndBack,
+}
+
+impl Face {
+    fn gl(self) -> GLenum {
+        match self {
+            Face::Front => gl::FRONT,
+            Face::Back => gl::BACK,
+            Face::FrontAndBack => gl::FRONT_AND_BACK,
+        }
+    }
+
+    fn from_gl(value: GLenum) -> Option<Self> {
+        [Face::Front, Face::Back, Face::FrontAndBack]
+            .into_iter()
+            .find(|face| face.gl() == value)
+    }
+}
+
+/// 管线状态，用于整体保存与恢复
+///
+/// # 示例 Examples
+/// ```ignore
+/// let saved = GL_STATE.pipeline();
+/// GL_STATE.disable(Capability::DepthTest);
+/// // 绘制全屏四边形...
+/// GL_STATE.set_pipeline(&saved);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineState {
+    pub depth_test: bool,
+    pub blend: bool,
+    pub cull_face: bool,
+    pub blend_func: BlendFunc,
+    pub depth_func: DepthFunc,
+    pub depth_mask: bool,
+    pub cull_mode: Face,
+    /// 视口 `[x, y, width, height]`
+    pub viewport: [i32; 4],
+}
+
+/// 渲染线程的 OpenGL 状态缓存
+///
+/// 记录当前的着色器程序、顶点数组、各纹理单元绑定的纹理、开关、混合、深度、面剔除与视口状态，
+/// 设置与缓存相同的值时不调用 OpenGL；未知的状态在首次设置或查询时确定
+///
+/// # 注解 Note
+///
+/// 只有在 OpenGL 上下文激活后才能在渲染线程使用
+///
+/// 库内对这些状态的修改均经由 `GL_STATE`，直接调用 OpenGL 修改了这些状态后应调用
+/// `GL_STATE.invalidate()`，否则缓存与实际状态不一致
+#[derive(Debug, Default)]
+pub struct GlState {
+    program: Option<GLuint>,
+    vertex_array: Option<GLuint>,
+    active_unit: Option<u32>,
+    /// (纹理单元, 纹理目标) 对应的纹理
+    textures: HashMap<(u32, GLenum), GLuint>,
+    capabilities: [Option<bool>; 3],
+    blend_func: Option<BlendFunc>,
+    depth_func: Option<DepthFunc>,
+    depth_mask: Option<bool>,
+    cull_mode: Option<Face>,
+    viewport: Option<[i32; 4]>,
+    /// 跳过的冗余调用次数
+    skipped: usize,
+}
+
+impl GlState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 跳过的冗余调用次数
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// 将缓存值设为 `value`，返回是否需要调用 OpenGL
+    fn update<T: PartialEq>(slot: &mut Option<T>, value: T, skipped: &mut usize) -> bool {
+        if slot.as_ref() == Some(&value) {
+            *skipped += 1;
+            return false;
+        }
+        *slot = Some(value);
+        true
+    }
+
+    fn set_program(&mut self, program: GLuint) -> bool {
+        Self::update(&mut self.program, program, &mut self.skipped)
+    }
+
+    fn set_vertex_array(&mut self, vao: GLuint) -> bool {
+        Self::update(&mut self.vertex_array, vao, &mut self.skipped)
+    }
+
+    fn set_active_unit(&mut self, unit: u32) -> bool {
+        Self::update(&mut self.active_unit, unit, &mut self.skipped)
+    }
+
+    fn set_texture(&mut self, unit: u32, target: GLenum, texture: GLuint) -> bool {
+        if self.textures.insert((unit, target), texture) == Some(texture) {
+            self.skipped += 1;
+            return false;
+        }
+        true
+    }
+
+    /// 返回是否需要切换纹理单元与绑定纹理
+    ///
+    /// 纹理已绑定时仍切换到该纹理单元，调用者之后对纹理目标的修改作用于此纹理
+    fn bind_texture(&mut self, unit: u32, target: GLenum, texture: GLuint) -> (bool, bool) {
+        (
+            self.set_active_unit(unit),
+            self.set_texture(unit, target, texture),
+        )
+    }
+
+    fn set_capability(&mut self, capability: Capability, enabled: bool) -> bool {
+        let index = Capability::ALL
+            .iter()
+            .position(|&c| c == capability)
+            .unwrap();
+        Self::update(&mut self.capabilities[index], enabled, &mut self.skipped)
+    }
+
+    fn set_blend_func(&mut self, func: BlendFunc) -> bool {
+        Self::update(&mut self.blend_func, func, &mut self.skipped)
+    }
+
+    fn set_depth_func(&mut self, func: DepthFunc) -> bool {
+        Self::update(&mut self.depth_func, func, &mut self.skipped)
+    }
+
+    fn set_depth_mask(&mut self, mask: bool) -> bool {
+        Self::update(&mut self.depth_mask, mask, &mut self.skipped)
+    }
+
+    fn set_cull_mode(&mut self, face: Face) -> bool {
+        Self::update(&mut self.cull_mode, face, &mut self.skipped)
+    }
+
+    fn set_viewport(&mut self, viewport: [i32; 4]) -> bool {
+        Self::update(&mut self.viewport, viewport, &mut self.skipped)
+    }
+
+    /// 删除程序后，正在使用的程序状态变为未知
+    fn forget_program(&mut self, program: GLuint) {
+        if self.program == Some(program) {
+            self.program = None;
+        }
+    }
+
+    /// 删除顶点数组后，若其正被绑定则绑定变为 0
+    fn forget_vertex_array(&mut self, vao: GLuint) {
+        if self.vertex_array == Some(vao) {
+            self.vertex_array = Some(0);
+        }
+    }
+
+    /// 删除纹理后，绑定了该纹理的纹理单元变为绑定 0
+    fn forget_texture(&mut self, texture: GLuint) {
+        for bound in self.textures.values_mut() {
+            if *bound == texture {
+                *bound = 0;
+            }
+        }
+    }
+
+    /// 清空缓存
+    fn clear(&mut self) {
+        *self = Self {
+            skipped: self.skipped,
+            ..Self::default()
+        };
+    }
+}
+
+lazy_static! {
+    /// 渲染线程的 OpenGL 状态缓存
+    pub static ref GL_STATE: RustCraftWrapper<GlState> = RustCraftWrapper::new(GlState::new());
+}
+
+impl RustCraftWrapper<GlState> {
+    /// 在缓存上运行 `f`，返回其结果
+    fn query<R: Default, F: FnOnce(&mut GlState) -> R>(&self, f: F) -> R {
+        let mut ret = R::default();
+        self.apply(|state| ret = f(state));
+        ret
+    }
+
+    /// 跳过的冗余调用次数
+    pub fn skipped(&self) -> usize {
+        self.query(|state| state.skipped())
+    }
+
+    /// 清空缓存，在直接调用 OpenGL 修改了缓存的状态之后调用
+    pub fn invalidate(&self) {
+        self.apply(|state| state.clear());
+    }
+
+    /// 启用着色器程序
+    pub fn use_program(&self, program: GLuint) {
+        if self.query(|state| state.set_program(program)) {
+            unsafe {
+                gl::UseProgram(program);
+            }
+        }
+    }
+
+    /// 当前启用的着色器程序
+    pub fn program(&self) -> GLuint {
+        let cached = self.query(|state| state.program);
+        cached.unwrap_or_else(|| {
+            let mut program = 0;
+            unsafe {
+                gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut program);
+            }
+            self.apply(|state| state.program = Some(program as GLuint));
+            program as GLuint
+        })
+    }
+
+    /// 删除着色器程序
+    pub fn delete_program(&self, program: GLuint) {
+        self.apply(|state| state.forget_program(program));
+        unsafe {
+            gl::DeleteProgram(program);
+        }
+    }
+
+    /// 绑定顶点数组
+    pub fn bind_vertex_array(&self, vao: GLuint) {
+        if self.query(|state| state.set_vertex_array(vao)) {
+            unsafe {
+                gl::BindVertexArray(vao);
+            }
+        }
+    }
+
+    /// 删除顶点数组
+    pub fn delete_vertex_array(&self, vao: GLuint) {
+        self.apply(|state| state.forget_vertex_array(vao));
+        unsafe {
+            gl::DeleteVertexArrays(1, &vao);
+        }
+    }
+
+    /// 将纹理绑定到纹理单元
+    ///
+    /// # 注解 Note
+    ///
+    /// 调用后该纹理单元总是处于活动状态，之后直接调用 OpenGL 修改纹理目标时作用于此纹理
+    ///
+    /// # 参数 Parameters
+    /// * `unit` - 纹理单元序号
+    /// * `target` - 纹理目标，例如 `gl::TEXTURE_2D`
+    /// * `texture` - 纹理对象，0 表示解除绑定
+    pub fn bind_texture(&self, unit: u32, target: GLenum, texture: GLuint) {
+        let (activate, bind) = self.query(|state| state.bind_texture(unit, target, texture));
+        unsafe {
+            if activate {
+                gl::ActiveTexture(gl::TEXTURE0 + unit);
+            }
+            if bind {
+                gl::BindTexture(target, texture);
+            }
+        }
+    }
+
+    /// 删除纹理
+    pub fn delete_texture(&self, texture: GLuint) {
+        self.apply(|state| state.forget_texture(texture));
+        unsafe {
+            gl::DeleteTextures(1, &texture);
+        }
+    }
+
+    /// 启用或禁用开关
+    pub fn set_enabled(&self, capability: Capability, enabled: bool) {
+        if self.query(|state| state.set_capability(capability, enabled)) {
+            unsafe {
+                match enabled {
+                    true => gl::Enable(capability.gl()),
+                    false => gl::Disable(capability.gl()),
+                }
+            }
+        }
+    }
+
+    /// 启用开关
+    pub fn enable(&self, capability: Capability) {
+        self.set_enabled(capability, true);
+    }
+
+    /// 禁用开关
+    pub fn disable(&self, capability: Capability) {
+        self.set_enabled(capability, false);
+    }
+
+    /// 开关是否启用
+    pub fn is_enabled(&self, capability: Capability) -> bool {
+        let index = Capability::ALL
+            .iter()
+            .position(|&c| c == capability)
+            .unwrap();
+        let cached = self.query(|state| state.capabilities[index]);
+        cached.unwrap_or_else(|| {
+            let enabled = unsafe { gl::IsEnabled(capability.gl()) == gl::TRUE };
+            self.apply(|state| state.capabilities[index] = Some(enabled));
+            enabled
+        })
+    }
+
+    /// 设置混合函数
+    pub fn set_blend_func(&self, func: BlendFunc) {
+        if self.query(|state| state.set_blend_func(func)) {
+            unsafe {
+                gl::BlendFuncSeparate(
+                    func.src_rgb.gl(),
+                    func.dst_rgb.gl(),
+                    func.src_alpha.gl(),
+                    func.dst_alpha.gl(),
+                );
+            }
+        }
+    }
+
+    /// 当前的混合函数
+    pub fn blend_func(&self) -> BlendFunc {
+        let cached = self.query(|state| state.blend_func);
+        cached.unwrap_or_else(|| {
+            let factor = |name| {
+                let mut value = 0;
+                unsafe {
+                    gl::GetIntegerv(name, &mut value);
+                }
+                BlendFactor::from_gl(value as GLenum).unwrap_or(BlendFactor::One)
+            };
+            let func = BlendFunc {
+                src_rgb: factor(gl::BLEND_SRC_RGB),
+                dst_rgb: factor(gl::BLEND_DST_RGB),
+                src_alpha: factor(gl::BLEND_SRC_ALPHA),
+                dst_alpha: factor(gl::BLEND_DST_ALPHA),
+            };
+            self.apply(|state| state.blend_func = Some(func));
+            func
+        })
+    }
+
+    /// 设置深度比较函数
+    pub fn set_depth_func(&self, func: DepthFunc) {
+        if self.query(|state| state.set_depth_func(func)) {
+            unsafe {
+                gl::DepthFunc(func.gl());
+            }
+        }
+    }
+
+    /// 当前的深度比较函数
+    pub fn depth_func(&self) -> DepthFunc {
+        let cached = self.query(|state| state.depth_func);
+        cached.unwrap_or_else(|| {
+            let mut value = 0;
+            unsafe {
+                gl::GetIntegerv(gl::DEPTH_FUNC, &mut value);
+            }
+            let func = DepthFunc::from_gl(value as GLenum).unwrap_or_default();
+            self.apply(|state| state.depth_func = Some(func));
+            func
+        })
+    }
+
+    /// 设置是否写入深度
+    pub fn set_depth_mask(&self, mask: bool) {
+        if self.query(|state| state.set_depth_mask(mask)) {
+            unsafe {
+                gl::DepthMask(if mask { gl::TRUE } else { gl::FALSE });
+            }
+        }
+    }
+
+    /// 是否写入深度
+    pub fn depth_mask(&self) -> bool {
+        let cached = self.query(|state| state.depth_mask);
+        cached.unwrap_or_else(|| {
+            let mut value = gl::TRUE;
+            unsafe {
+                gl::GetBooleanv(gl::DEPTH_WRITEMASK, &mut value);
+            }
+            let mask = value == gl::TRUE;
+            self.apply(|state| state.depth_mask = Some(mask));
+            mask
+        })
+    }
+
+    /// 设置面剔除时剔除的面
+    pub fn set_cull_mode(&self, face: Face) {
+        if self.query(|state| state.set_cull_mode(face)) {
+            unsafe {
+                gl::CullFace(face.gl());
+            }
+        }
+    }
+
+    /// 面剔除时剔除的面
+    pub fn cull_mode(&self) -> Face {
+        let cached = self.query(|state| state.cull_mode);
+        cached.unwrap_or_else(|| {
+            let mut value = 0;
+            unsafe {
+                gl::GetIntegerv(gl::CULL_FACE_MODE, &mut value);
+            }
+            let face = Face::from_gl(value as GLenum).unwrap_or_default();
+            self.apply(|state| state.cull_mode = Some(face));
+            face
+        })
+    }
+
+    /// 设置视口
+    ///
+    /// # 参数 Parameters
+    /// * `viewport` - `[x, y, width, height]`
+    pub fn set_viewport(&self, viewport: [i32; 4]) {
+        if self.query(|state| state.set_viewport(viewport)) {
+            let [x, y, w, h] = viewport;
+            unsafe {
+                gl::Viewport(x, y, w, h);
+            }
+        }
+    }
+
+    /// 当前视口 `[x, y, width, height]`
+    pub fn viewport(&self) -> [i32; 4] {
+        let cached = self.query(|state| state.viewport);
+        cached.unwrap_or_else(|| {
+            let mut viewport = [0; 4];
+            unsafe {
+                gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            }
+            self.apply(|state| state.viewport = Some(viewport));
+            viewport
+        })
+    }
+
+    /// 当前的管线状态
+    pub fn pipeline(&self) -> PipelineState {
+        PipelineState {
+            depth_test: self.is_enabled(Capability::DepthTest),
+            blend: self.is_enabled(Capability::Blend),
+            cull_face: self.is_enabled(Capability::CullFace),
+            blend_func: self.blend_func(),
+            depth_func: self.depth_func(),
+            depth_mask: self.depth_mask(),
+            cull_mode: self.cull_mode(),
+            viewport: self.viewport(),
+        }
+    }
+
+    /// 设置管线状态，通常用于恢复 `pipeline` 保存的状态
+    pub fn set_pipeline(&self, pipeline: &PipelineState) {
+        self.set_enabled(Capability::DepthTest, pipeline.depth_test);
+        self.set_enabled(Capability::Blend, pipeline.blend);
+        self.set_enabled(Capability::CullFace, pipeline.cull_face);
+        self.set_blend_func(pipeline.blend_func);
+        self.set_depth_func(pipeline.depth_func);
+        self.set_depth_mask(pipeline.depth_mask);
+        self.set_cull_mode(pipeline.cull_mode);
+        self.set_viewport(pipeline.viewport);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let mut state = GlState::new();
+        assert!(state.set_program(3));
+        assert!(!state.set_program(3));
+        assert!(state.set_program(4));
+        assert!(state.set_capability(Capability::Blend, true));
+        assert!(!state.set_capability(Capability::Blend, true));
+        assert!(state.set_capability(Capability::DepthTest, true));
+        assert!(state.set_blend_func(BlendFunc::ALPHA));
+        assert!(!state.set_blend_func(BlendFunc::ALPHA));
+        assert!(state.set_viewport([0, 0, 800, 600]));
+        assert!(!state.set_viewport([0, 0, 800, 600]));
+        assert_eq!(state.skipped(), 4);
+
+        state.clear();
+        assert!(state.set_program(4));
+        assert!(state.set_viewport([0, 0, 800, 600]));
+        assert_eq!(state.skipped(), 4);
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut state = GlState::new();
+        assert!(state.set_texture(0, gl::TEXTURE_2D, 7));
+        assert!(state.set_texture(1, gl::TEXTURE_2D, 7));
+        assert!(state.set_texture(1, gl::TEXTURE_CUBE_MAP, 7));
+        assert!(!state.set_texture(0, gl::TEXTURE_2D, 7));
+        state.forget_texture(7);
+        // 新纹理可能复用被删除纹理的名称
+        assert!(state.set_texture(0, gl::TEXTURE_2D, 7));
+
+        assert!(state.set_vertex_array(2));
+        state.forget_vertex_array(2);
+        assert!(!state.set_vertex_array(0));
+        assert!(state.set_vertex_array(2));
+
+        assert!(state.set_program(5));
+        state.forget_program(5);
+        assert!(state.set_program(5));
+    }
+
+    #[test]
+    fn test_bind_texture() {
+        let mut state = GlState::new();
+        assert_eq!(state.bind_texture(0, gl::TEXTURE_2D, 1), (true, true));
+        assert_eq!(state.bind_texture(1, gl::TEXTURE_2D, 2), (true, true));
+        // 纹理已绑定但其它纹理单元处于活动状态时仍需切换纹理单元
+        assert_eq!(state.bind_texture(0, gl::TEXTURE_2D, 1), (true, false));
+        assert_eq!(state.bind_texture(0, gl::TEXTURE_2D, 1), (false, false));
+    }
+
+    #[test]
+    fn test_gl_enum() {
+        for factor in BlendFactor::ALL {
+            assert_eq!(BlendFactor::from_gl(factor.gl()), Some(factor));
+        }
+        for func in DepthFunc::ALL {
+            assert_eq!(DepthFunc::from_gl(func.gl()), Some(func));
+        }
+        assert_eq!(Face::from_gl(gl::FRONT), Some(Face::Front));
+        assert_eq!(DepthFunc::from_gl(0), None);
+    }
+}
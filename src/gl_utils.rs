@@ -3,6 +3,11 @@ use std::ffi::CString;
 
 use mats::uniform::SetUniform;
 
+use crate::{
+    texture::{DepthFormat, PixelFormat, TextureOptions},
+    GL_STATE,
+};
+
 pub unsafe fn complie_shader(shader_type: GLenum, source: &str) -> Result<GLuint, String> {
     let shader = gl::CreateShader(shader_type);
     let source = CString::new(source).unwrap();
@@ -55,10 +60,6 @@ pub unsafe fn link_program(vshader: GLuint, fshader: GLuint) -> Result<GLuint, S
     Ok(program)
 }
 
-pub unsafe fn use_program(program: GLuint) {
-    gl::UseProgram(program);
-}
-
 pub unsafe fn create_element_model_context(
     vertices: &Vec<f32>,
     indices: &Vec<u32>,
@@ -70,7 +71,7 @@ pub unsafe fn create_element_model_context(
     gl::GenVertexArrays(1, &mut vao);
     gl::GenBuffers(1, &mut vbo);
     gl::GenBuffers(1, &mut ebo);
-    gl::BindVertexArray(vao);
+    GL_STATE.bind_vertex_array(vao);
     gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
     gl::BufferData(
         gl::ARRAY_BUFFER,
@@ -123,7 +124,7 @@ pub unsafe fn create_element_model_context(
     }
 
     gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-    gl::BindVertexArray(0);
+    GL_STATE.bind_vertex_array(0);
     (vao, vbo, ebo)
 }
 
@@ -135,7 +136,7 @@ pub unsafe fn create_array_model_context(
     let mut vbo: GLuint = 0;
     gl::GenVertexArrays(1, &mut vao);
     gl::GenBuffers(1, &mut vbo);
-    gl::BindVertexArray(vao);
+    GL_STATE.bind_vertex_array(vao);
     gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
     gl::BufferData(
         gl::ARRAY_BUFFER,
@@ -181,39 +182,381 @@ pub unsafe fn create_array_model_context(
     }
 
     gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-    gl::BindVertexArray(0);
+    GL_STATE.bind_vertex_array(0);
     (vao, vbo)
 }
 
-pub unsafe fn load_texture_from_file(path: &str) -> Result<GLuint, Box<dyn std::error::Error>> {
-    use image::*;
-    let img = open(path)?.to_rgba8();
-    let (width, height) = img.dimensions();
-    let data = img.into_raw();
+/// `GL_EXT_texture_filter_anisotropic` 中定义的常量
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
 
+pub unsafe fn has_extension(name: &str) -> bool {
+    let mut count = 0;
+    gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+    for i in 0..count {
+        let ext = gl::GetStringi(gl::EXTENSIONS, i as GLuint);
+        if ext.is_null() {
+            continue;
+        }
+        if std::ffi::CStr::from_ptr(ext as *const _).to_bytes() == name.as_bytes() {
+            return true;
+        }
+    }
+    false
+}
+
+/// 向当前绑定到 `target` 的纹理应用采样参数
+pub unsafe fn apply_texture_options(target: GLenum, options: &TextureOptions) {
+    gl::TexParameteri(target, gl::TEXTURE_WRAP_S, options.wrap_s.gl_enum() as i32);
+    gl::TexParameteri(target, gl::TEXTURE_WRAP_T, options.wrap_t.gl_enum() as i32);
+    if target == gl::TEXTURE_CUBE_MAP {
+        gl::TexParameteri(target, gl::TEXTURE_WRAP_R, options.wrap_t.gl_enum() as i32);
+    }
+    gl::TexParameteri(
+        target,
+        gl::TEXTURE_MIN_FILTER,
+        options.gl_min_filter() as i32,
+    );
+    gl::TexParameteri(
+        target,
+        gl::TEXTURE_MAG_FILTER,
+        options.gl_mag_filter() as i32,
+    );
+    if options.anisotropy > 1.0 && has_extension("GL_EXT_texture_filter_anisotropic") {
+        let mut max = 1.0;
+        gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+        gl::TexParameterf(target, TEXTURE_MAX_ANISOTROPY, options.anisotropy.min(max));
+    }
+}
+
+/// 上传二维纹理，返回纹理对象名称
+///
+/// `data` 为 `None` 时仅分配存储空间，内容未定义
+pub unsafe fn upload_texture(
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    data: Option<&[u8]>,
+    options: &TextureOptions,
+) -> GLuint {
     let mut texture: GLuint = 0;
     gl::GenTextures(1, &mut texture);
-    gl::BindTexture(gl::TEXTURE_2D, texture);
-    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D, texture);
+    apply_texture_options(gl::TEXTURE_2D, options);
+    if format.channels() == 1 {
+        // 单通道纹理在着色器中以灰度形式采样
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_G, gl::RED as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_B, gl::RED as i32);
+    }
 
+    // 单通道与 RGB 数据的行宽不一定是 4 字节的整数倍
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
     gl::TexImage2D(
         gl::TEXTURE_2D,
         0,
-        gl::RGBA as i32,
+        format.gl_internal_format(options) as i32,
+        width as i32,
+        height as i32,
+        0,
+        format.gl_format(),
+        format.gl_type(),
+        data.map_or(std::ptr::null(), |data| data.as_ptr() as *const GLvoid),
+    );
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+    if options.mipmaps {
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+    }
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D, 0);
+
+    texture
+}
+
+/// 更新二维纹理的部分区域
+pub unsafe fn update_texture_region(
+    texture: GLuint,
+    (x, y, width, height): (u32, u32, u32, u32),
+    format: PixelFormat,
+    data: &[u8],
+    mipmaps: bool,
+) {
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D, texture);
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    gl::TexSubImage2D(
+        gl::TEXTURE_2D,
+        0,
+        x as i32,
+        y as i32,
         width as i32,
         height as i32,
+        format.gl_format(),
+        format.gl_type(),
+        data.as_ptr() as *const GLvoid,
+    );
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+    if mipmaps {
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+    }
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D, 0);
+}
+
+/// 上传带有完整多级渐远纹理链的二维纹理
+///
+/// # 参数 Parameters
+/// * `levels` - 各级纹理数据，第 0 级为原始尺寸
+/// * `compressed` - 为 `Some(内部格式)` 时以块压缩格式上传，否则以 `format` 上传
+/// * `format` - 未压缩数据的像素格式
+pub unsafe fn upload_texture_levels(
+    width: u32,
+    height: u32,
+    levels: &[&[u8]],
+    compressed: Option<GLenum>,
+    format: PixelFormat,
+    options: &TextureOptions,
+) -> GLuint {
+    let mut texture: GLuint = 0;
+    gl::GenTextures(1, &mut texture);
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D, texture);
+    // 多级渐远纹理由文件提供，不再生成
+    let options = TextureOptions {
+        mipmaps: options.mipmaps && levels.len() > 1,
+        ..*options
+    };
+    apply_texture_options(gl::TEXTURE_2D, &options);
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_MAX_LEVEL,
+        levels.len() as i32 - 1,
+    );
+
+    for (level, data) in levels.iter().enumerate() {
+        let w = (width >> level).max(1) as i32;
+        let h = (height >> level).max(1) as i32;
+        match compressed {
+            Some(internal_format) => gl::CompressedTexImage2D(
+                gl::TEXTURE_2D,
+                level as i32,
+                internal_format,
+                w,
+                h,
+                0,
+                data.len() as i32,
+                data.as_ptr() as *const GLvoid,
+            ),
+            None => gl::TexImage2D(
+                gl::TEXTURE_2D,
+                level as i32,
+                format.gl_internal_format(&options) as i32,
+                w,
+                h,
+                0,
+                format.gl_format(),
+                format.gl_type(),
+                data.as_ptr() as *const GLvoid,
+            ),
+        }
+    }
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D, 0);
+
+    texture
+}
+
+/// 上传二维纹理数组，`layers` 中的每一项为一层 RGBA 数据
+pub unsafe fn upload_texture_array(
+    width: u32,
+    height: u32,
+    layers: &[&[u8]],
+    options: &TextureOptions,
+) -> GLuint {
+    let mut texture: GLuint = 0;
+    gl::GenTextures(1, &mut texture);
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D_ARRAY, texture);
+    apply_texture_options(gl::TEXTURE_2D_ARRAY, options);
+
+    gl::TexImage3D(
+        gl::TEXTURE_2D_ARRAY,
+        0,
+        PixelFormat::Rgba8.gl_internal_format(options) as i32,
+        width as i32,
+        height as i32,
+        layers.len() as i32,
         0,
         gl::RGBA,
         gl::UNSIGNED_BYTE,
-        data.as_ptr() as *const GLvoid,
+        std::ptr::null(),
     );
-    gl::GenerateMipmap(gl::TEXTURE_2D);
-    gl::BindTexture(gl::TEXTURE_2D, 0);
+    for (i, layer) in layers.iter().enumerate() {
+        gl::TexSubImage3D(
+            gl::TEXTURE_2D_ARRAY,
+            0,
+            0,
+            0,
+            i as i32,
+            width as i32,
+            height as i32,
+            1,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            layer.as_ptr() as *const GLvoid,
+        );
+    }
+    if options.mipmaps {
+        gl::GenerateMipmap(gl::TEXTURE_2D_ARRAY);
+    }
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D_ARRAY, 0);
+
+    texture
+}
+
+/// 上传立方体贴图，`faces` 按 +X、-X、+Y、-Y、+Z、-Z 的顺序排列
+pub unsafe fn upload_cubemap(size: u32, faces: &[&[u8]], options: &TextureOptions) -> GLuint {
+    let mut texture: GLuint = 0;
+    gl::GenTextures(1, &mut texture);
+    GL_STATE.bind_texture(0, gl::TEXTURE_CUBE_MAP, texture);
+    apply_texture_options(gl::TEXTURE_CUBE_MAP, options);
+
+    for (i, face) in faces.iter().enumerate() {
+        gl::TexImage2D(
+            gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as GLenum,
+            0,
+            PixelFormat::Rgba8.gl_internal_format(options) as i32,
+            size as i32,
+            size as i32,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            face.as_ptr() as *const GLvoid,
+        );
+    }
+    if options.mipmaps {
+        gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+    }
+    GL_STATE.bind_texture(0, gl::TEXTURE_CUBE_MAP, 0);
+
+    texture
+}
+
+/// 分配深度纹理的存储空间，返回纹理对象名称
+pub unsafe fn upload_depth_texture(
+    width: u32,
+    height: u32,
+    format: DepthFormat,
+    options: &TextureOptions,
+) -> GLuint {
+    let mut texture: GLuint = 0;
+    gl::GenTextures(1, &mut texture);
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D, texture);
+    apply_texture_options(gl::TEXTURE_2D, options);
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        format.gl_internal_format() as i32,
+        width as i32,
+        height as i32,
+        0,
+        format.gl_format(),
+        format.gl_type(),
+        std::ptr::null(),
+    );
+    GL_STATE.bind_texture(0, gl::TEXTURE_2D, 0);
+
+    texture
+}
 
-    Ok(texture)
+/// 创建渲染缓冲，`samples` 大于 0 时为多重采样渲染缓冲
+pub unsafe fn create_renderbuffer(
+    width: u32,
+    height: u32,
+    internal_format: GLenum,
+    samples: u32,
+) -> GLuint {
+    let mut rbo: GLuint = 0;
+    gl::GenRenderbuffers(1, &mut rbo);
+    gl::BindRenderbuffer(gl::RENDERBUFFER, rbo);
+    if samples > 0 {
+        gl::RenderbufferStorageMultisample(
+            gl::RENDERBUFFER,
+            samples as i32,
+            internal_format,
+            width as i32,
+            height as i32,
+        );
+    } else {
+        gl::RenderbufferStorage(
+            gl::RENDERBUFFER,
+            internal_format,
+            width as i32,
+            height as i32,
+        );
+    }
+    gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+    rbo
+}
+
+/// 设置帧缓冲的绘制缓冲，`count` 为 0 时不绘制任何颜色附件
+pub unsafe fn set_draw_buffers(count: usize) {
+    if count == 0 {
+        gl::DrawBuffer(gl::NONE);
+        gl::ReadBuffer(gl::NONE);
+    } else {
+        let buffers = (0..count)
+            .map(|i| gl::COLOR_ATTACHMENT0 + i as GLenum)
+            .collect::<Vec<_>>();
+        gl::DrawBuffers(count as i32, buffers.as_ptr());
+    }
+}
+
+/// 检查当前绑定的帧缓冲是否完整
+pub unsafe fn check_framebuffer() -> Result<(), String> {
+    let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+    let reason = match status {
+        gl::FRAMEBUFFER_COMPLETE => return Ok(()),
+        gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "附件不完整",
+        gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "缺少附件",
+        gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => "附件的采样数不一致",
+        gl::FRAMEBUFFER_UNSUPPORTED => "附件格式组合不受支持",
+        _ => "未知错误",
+    };
+    Err(format!("帧缓冲不完整: {} (0x{:X})", reason, status))
+}
+
+/// 创建指定长度(以 `f32` 计)的 uniform 缓冲对象
+pub unsafe fn create_uniform_buffer(len: usize) -> GLuint {
+    let mut ubo = 0;
+    gl::GenBuffers(1, &mut ubo);
+    gl::BindBuffer(gl::UNIFORM_BUFFER, ubo);
+    gl::BufferData(
+        gl::UNIFORM_BUFFER,
+        (len * std::mem::size_of::<f32>()) as GLsizeiptr,
+        std::ptr::null(),
+        gl::DYNAMIC_DRAW,
+    );
+    gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+    ubo
+}
+
+/// 从起始位置更新 uniform 缓冲对象的内容
+pub unsafe fn update_uniform_buffer(ubo: GLuint, data: &[f32]) {
+    gl::BindBuffer(gl::UNIFORM_BUFFER, ubo);
+    gl::BufferSubData(
+        gl::UNIFORM_BUFFER,
+        0,
+        std::mem::size_of_val(data) as GLsizeiptr,
+        data.as_ptr() as *const _,
+    );
+    gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+}
+
+/// 将着色器程序中的 uniform 块绑定到指定绑定点，程序未声明该块时不做任何操作
+pub unsafe fn bind_uniform_block(program: GLuint, name: &str, binding: GLuint) {
+    let c_name = match CString::new(name) {
+        Ok(name) => name,
+        Err(_) => return,
+    };
+    let index = gl::GetUniformBlockIndex(program, c_name.as_ptr());
+    if index != gl::INVALID_INDEX {
+        gl::UniformBlockBinding(program, index, binding);
+    }
 }
 
 pub unsafe fn set_uniform<T: SetUniform>(
@@ -0,0 +1,18 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D currentLuminance;
+uniform sampler2D previousLuminance;
+// 本帧向目标亮度靠近的比例
+uniform float alpha;
+
+void main(){
+    float current = texture(currentLuminance, vec2(0.5)).r;
+    float previous = texture(previousLuminance, vec2(0.5)).r;
+    // 首帧时上一帧的亮度未定义，直接采用本帧亮度
+    float adapted = alpha >= 1.0 ? current : mix(previous, current, alpha);
+    FragColor = vec4(adapted, 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,20 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+// 亮度阈值，超过阈值的部分产生泛光
+uniform float threshold;
+// 阈值附近的柔和过渡宽度
+uniform float knee;
+
+void main(){
+    vec3 color = texture(screenTexture, TexCoords).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    float soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 0.00001);
+    float contribution = max(soft, brightness - threshold) / max(brightness, 0.00001);
+    FragColor = vec4(color * contribution, 1.0);
+}
@@ -0,0 +1,19 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+// 源纹理半个像素的纹理坐标尺寸
+uniform vec2 halfPixel;
+
+void main(){
+    // 双重滤波(Dual Filtering)降采样
+    vec4 sum = texture(screenTexture, TexCoords) * 4.0;
+    sum += texture(screenTexture, TexCoords - halfPixel);
+    sum += texture(screenTexture, TexCoords + halfPixel);
+    sum += texture(screenTexture, TexCoords + vec2(halfPixel.x, -halfPixel.y));
+    sum += texture(screenTexture, TexCoords - vec2(halfPixel.x, -halfPixel.y));
+    FragColor = sum / 8.0;
+}
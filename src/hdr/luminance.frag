@@ -0,0 +1,14 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+
+void main(){
+    vec3 color = texture(screenTexture, TexCoords).rgb;
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    // 存储对数亮度，逐级平均后即为几何平均亮度的对数
+    FragColor = vec4(log(max(luma, 0.0001)), 0.0, 0.0, 1.0);
+}
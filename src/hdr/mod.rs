@@ -0,0 +1,561 @@
+use gl::types::*;
+use mats::Vec2;
+
+use crate::{
+    gl_state::Capability,
+    postprocess::{register_shaders, BUILTIN_SHADERS},
+    texture::{DepthFormat, Filter, PixelFormat, TextureOptions, Wrap},
+    App, Framebuffer, FramebufferBuilder, FramebufferSize, TimeType, UniformValue, GL_STATE,
+    SHADER_MANAGER,
+};
+
+/// 内置高动态范围着色器的名称与片段着色器源码
+const HDR_SHADERS: [(&str, &str); 6] = [
+    ("hdr/luminance", include_str!("luminance.frag")),
+    ("hdr/adapt", include_str!("adapt.frag")),
+    ("hdr/bright", include_str!("bright.frag")),
+    ("hdr/downsample", include_str!("downsample.frag")),
+    ("hdr/upsample", include_str!("upsample.frag")),
+    ("hdr/tonemap", include_str!("tonemap.frag")),
+];
+
+/// 亮度链的起始分辨率，此后逐级减半至 1x1
+const LUMINANCE_SIZE: u32 = 256;
+
+/// 泛光降采样的最大级数
+const MAX_BLOOM_LEVELS: u32 = 8;
+
+/// 色调映射算子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemap {
+    /// `x / (1 + x)`，高光过渡柔和但整体偏灰
+    Reinhard,
+    /// ACES 电影曲线的拟合，对比度较高
+    Aces,
+    /// 《神秘海域 2》中使用的 Hable 曲线
+    Uncharted2,
+}
+
+impl Tonemap {
+    /// 着色器中的算子序号
+    fn index(self) -> i32 {
+        match self {
+            Tonemap::Reinhard => 0,
+            Tonemap::Aces => 1,
+            Tonemap::Uncharted2 => 2,
+        }
+    }
+
+    /// 在 CPU 上对单个分量应用色调映射，与着色器中的实现一致
+    pub fn apply(self, x: f32) -> f32 {
+        match self {
+            Tonemap::Reinhard => x / (1.0 + x),
+            Tonemap::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(0.0, 1.0)
+            }
+            Tonemap::Uncharted2 => {
+                let curve = |x: f32| {
+                    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+                    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+                };
+                curve(x * 2.0) / curve(11.2)
+            }
+        }
+    }
+}
+
+/// 曝光方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Exposure {
+    /// 固定曝光值
+    Fixed(f32),
+    /// 根据场景的平均亮度自动调整曝光
+    Auto {
+        /// 目标中灰值，通常为 `0.18`
+        key: f32,
+        /// 亮度适应速度，值越大适应越快
+        speed: f32,
+        /// 最小曝光值
+        min: f32,
+        /// 最大曝光值
+        max: f32,
+    },
+}
+
+impl Exposure {
+    /// 默认参数的自动曝光
+    pub fn auto() -> Self {
+        Exposure::Auto {
+            key: 0.18,
+            speed: 1.5,
+            min: 0.1,
+            max: 10.0,
+        }
+    }
+
+    /// 按场景的几何平均亮度计算曝光值，与着色器中的实现一致
+    pub fn value(self, average_luminance: f32) -> f32 {
+        match self {
+            Exposure::Fixed(exposure) => exposure,
+            Exposure::Auto { key, min, max, .. } => {
+                (key / average_luminance.max(0.0001)).clamp(min, max)
+            }
+        }
+    }
+}
+
+/// 泛光参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    /// 亮度阈值，超过阈值的部分产生泛光
+    pub threshold: f32,
+    /// 阈值附近的柔和过渡宽度
+    pub knee: f32,
+    /// 泛光叠加到场景上的强度
+    pub intensity: f32,
+    /// 降采样级数，级数越多泛光范围越大，取值为 1 至 8
+    pub levels: u32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 0.5,
+            levels: 5,
+        }
+    }
+}
+
+/// 高动态范围渲染参数
+///
+/// 默认值为：ACES 色调映射、自动曝光、伽马值 2.2、默认参数的泛光、不启用多重采样
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::hdr::{Exposure, HdrSettings, Tonemap};
+///
+/// let settings = HdrSettings::default()
+///     .tonemap(Tonemap::Uncharted2)
+///     .exposure(Exposure::Fixed(1.5))
+///     .bloom(None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrSettings {
+    pub tonemap: Tonemap,
+    pub exposure: Exposure,
+    /// 输出时的伽马值
+    pub gamma: f32,
+    /// 泛光参数，`None` 表示不启用泛光
+    pub bloom: Option<BloomSettings>,
+    /// 场景帧缓冲的多重采样数，`0` 表示不启用
+    pub samples: u32,
+}
+
+impl Default for HdrSettings {
+    fn default() -> Self {
+        Self {
+            tonemap: Tonemap::Aces,
+            exposure: Exposure::auto(),
+            gamma: 2.2,
+            bloom: Some(BloomSettings::default()),
+            samples: 0,
+        }
+    }
+}
+
+impl HdrSettings {
+    /// 设置色调映射算子
+    pub fn tonemap(mut self, tonemap: Tonemap) -> Self {
+        self.tonemap = tonemap;
+        self
+    }
+
+    /// 设置曝光方式
+    pub fn exposure(mut self, exposure: Exposure) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    /// 设置输出时的伽马值
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// 设置泛光参数，`None` 表示不启用泛光
+    pub fn bloom(mut self, bloom: Option<BloomSettings>) -> Self {
+        self.bloom = bloom;
+        self
+    }
+
+    /// 设置场景帧缓冲的多重采样数
+    pub fn samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+/// 亮度链各级的边长，从 `LUMINANCE_SIZE` 逐级减半至 1
+fn luminance_sizes() -> Vec<u32> {
+    let mut sizes = vec![LUMINANCE_SIZE];
+    while *sizes.last().unwrap() > 1 {
+        sizes.push(sizes.last().unwrap() / 2);
+    }
+    sizes
+}
+
+/// 泛光第 `level` 级相对窗口的缩放比例，第 0 级为半分辨率
+fn bloom_scale(level: u32) -> f32 {
+    0.5f32.powi(level as i32 + 1)
+}
+
+/// 以指数衰减计算本帧向目标亮度靠近的比例，使适应速度与帧率无关
+fn adapt_alpha(delta_time: f32, speed: f32) -> f32 {
+    (1.0 - (-delta_time.max(0.0) * speed).exp()).clamp(0.0, 1.0)
+}
+
+/// 源纹理半个像素的纹理坐标尺寸
+fn half_pixel((width, height): (u32, u32)) -> UniformValue {
+    Vec2::from([0.5 / width as f32, 0.5 / height as f32]).into()
+}
+
+/// 以全屏三角形执行一次绘制
+fn draw(shader: &str, uniforms: &[(&str, UniformValue)]) {
+    if let Some(shader) = SHADER_MANAGER.get(shader) {
+        shader.use_program();
+        for (name, value) in uniforms {
+            shader.set_uniform(name, *value);
+        }
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+    }
+}
+
+/// 高动态范围渲染管线
+///
+/// 场景渲染到 RGBA16F 帧缓冲，结束时依次执行自动曝光、泛光与色调映射，
+/// 并将结果输出到默认帧缓冲。通常通过 `AppBuilder::enable_hdr` 启用，
+/// 由渲染线程在每帧的渲染回调前后自动调用 `begin` 与 `end`
+///
+/// # 示例 Examples
+/// ```ignore
+/// // 手动使用
+/// let mut hdr = HdrPipeline::new("hdr", &HdrSettings::default())?;
+/// // 渲染循环
+/// hdr.begin();
+/// draw_scene();
+/// hdr.end();
+/// ```
+pub struct HdrPipeline {
+    name: String,
+    settings: HdrSettings,
+    scene: Framebuffer,
+    /// 对数亮度链，最后一级为 1x1
+    luminance: Vec<Framebuffer>,
+    /// 交替读写的适应后亮度
+    adapted: [Framebuffer; 2],
+    /// 最近一次写入的适应后亮度序号
+    current: usize,
+    /// 下一帧是否直接采用当前亮度，而不经过适应过程
+    reset_adaptation: bool,
+    bloom: Vec<Framebuffer>,
+    /// 绘制全屏三角形使用的空顶点数组对象
+    vao: GLuint,
+}
+
+impl HdrPipeline {
+    /// 创建高动态范围渲染管线
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 场景颜色以 `名称.scene.color0` 的形式登记于 `TEXTURE_MANAGER`
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 管线名称，用作各帧缓冲名称的前缀
+    /// * `settings` - 渲染参数
+    pub fn new(name: &str, settings: &HdrSettings) -> Result<Self, String> {
+        register_shaders(&BUILTIN_SHADERS)?;
+        register_shaders(&HDR_SHADERS)?;
+        let luminance = luminance_sizes()
+            .into_iter()
+            .enumerate()
+            .map(|(i, size)| {
+                FramebufferBuilder::new(&format!("{}.luminance{}", name, i))
+                    .size(FramebufferSize::Fixed(size, size))
+                    .color(PixelFormat::R32F)
+                    .build()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let adapted_options = TextureOptions::default()
+            .wrap(Wrap::ClampToEdge)
+            .filter(Filter::Nearest)
+            .half_float(false);
+        let adapted = |i: usize| {
+            FramebufferBuilder::new(&format!("{}.adapted{}", name, i))
+                .size(FramebufferSize::Fixed(1, 1))
+                .color_with(PixelFormat::R32F, adapted_options)
+                .build()
+        };
+        let adapted = [adapted(0)?, adapted(1)?];
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            settings: *settings,
+            scene: Self::build_scene(name, settings.samples)?,
+            luminance,
+            adapted,
+            current: 0,
+            reset_adaptation: true,
+            bloom: Self::build_bloom(name, settings.bloom)?,
+            vao,
+        })
+    }
+
+    fn build_scene(name: &str, samples: u32) -> Result<Framebuffer, String> {
+        FramebufferBuilder::new(&format!("{}.scene", name))
+            .color(PixelFormat::Rgba32F)
+            .depth(DepthFormat::Depth24Stencil8)
+            .samples(samples)
+            .build()
+    }
+
+    fn build_bloom(name: &str, bloom: Option<BloomSettings>) -> Result<Vec<Framebuffer>, String> {
+        let levels = bloom.map_or(0, |b| b.levels.clamp(1, MAX_BLOOM_LEVELS));
+        (0..levels)
+            .map(|i| {
+                FramebufferBuilder::new(&format!("{}.bloom{}", name, i))
+                    .size(FramebufferSize::Scaled(bloom_scale(i)))
+                    .color(PixelFormat::Rgba32F)
+                    .build()
+            })
+            .collect()
+    }
+
+    /// 当前的渲染参数
+    pub fn settings(&self) -> &HdrSettings {
+        &self.settings
+    }
+
+    /// 修改渲染参数，多重采样数或泛光级数变化时重新创建对应的帧缓冲
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn set_settings(&mut self, settings: &HdrSettings) -> Result<(), String> {
+        if *settings == self.settings {
+            return Ok(());
+        }
+        if settings.samples != self.settings.samples {
+            self.scene = Self::build_scene(&self.name, settings.samples)?;
+        }
+        let levels = |bloom: Option<BloomSettings>| bloom.map(|b| b.levels);
+        if levels(settings.bloom) != levels(self.settings.bloom) {
+            self.bloom = Self::build_bloom(&self.name, settings.bloom)?;
+        }
+        self.settings = *settings;
+        Ok(())
+    }
+
+    /// 场景帧缓冲
+    pub fn scene(&self) -> &Framebuffer {
+        &self.scene
+    }
+
+    /// 开始渲染场景，此后的绘制将输出到高动态范围帧缓冲
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，不会清空帧缓冲
+    pub fn begin(&mut self) {
+        self.scene.bind();
+    }
+
+    /// 结束渲染场景，执行自动曝光、泛光与色调映射并输出到默认帧缓冲
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，执行期间关闭深度测试，结束后恢复
+    pub fn end(&mut self) {
+        self.scene.unbind();
+        let depth_test = GL_STATE.is_enabled(Capability::DepthTest);
+        GL_STATE.disable(Capability::DepthTest);
+        GL_STATE.bind_vertex_array(self.vao);
+        if let Exposure::Auto { speed, .. } = self.settings.exposure {
+            self.update_luminance(speed);
+        } else {
+            self.reset_adaptation = true;
+        }
+        if let Some(bloom) = self.settings.bloom {
+            self.update_bloom(&bloom);
+        }
+        self.tonemap();
+        GL_STATE.bind_vertex_array(0);
+        GL_STATE.set_enabled(Capability::DepthTest, depth_test);
+    }
+
+    /// 计算场景的对数平均亮度，并使其随时间向目标亮度靠近
+    fn update_luminance(&mut self, speed: f32) {
+        let screen = ("screenTexture", UniformValue::Int(0));
+        self.scene.bind_color(0, 0);
+        self.luminance[0].bind();
+        draw("hdr/luminance", &[screen]);
+        // 线性过滤下在输出像素中心采样即为 2x2 像素的平均值
+        for i in 1..self.luminance.len() {
+            self.luminance[i - 1].bind_color(0, 0);
+            self.luminance[i].bind();
+            draw("postprocess/copy", &[screen]);
+        }
+        let alpha = if self.reset_adaptation {
+            1.0
+        } else {
+            adapt_alpha(App::delta_time(TimeType::Render), speed)
+        };
+        let (previous, current) = (self.current, 1 - self.current);
+        self.luminance.last().unwrap().bind_color(0, 0);
+        self.adapted[previous].bind_color(0, 1);
+        self.adapted[current].bind();
+        draw(
+            "hdr/adapt",
+            &[
+                ("currentLuminance", UniformValue::Int(0)),
+                ("previousLuminance", UniformValue::Int(1)),
+                ("alpha", UniformValue::Float(alpha)),
+            ],
+        );
+        self.current = current;
+        self.reset_adaptation = false;
+    }
+
+    /// 提取高亮部分，并以双重滤波逐级降采样再升采样
+    fn update_bloom(&mut self, bloom: &BloomSettings) {
+        let screen = ("screenTexture", UniformValue::Int(0));
+        self.scene.bind_color(0, 0);
+        self.bloom[0].bind();
+        draw(
+            "hdr/bright",
+            &[
+                screen,
+                ("threshold", UniformValue::Float(bloom.threshold)),
+                ("knee", UniformValue::Float(bloom.knee)),
+            ],
+        );
+        for i in 1..self.bloom.len() {
+            self.bloom[i - 1].bind_color(0, 0);
+            let source = self.bloom[i - 1].size();
+            self.bloom[i].bind();
+            draw(
+                "hdr/downsample",
+                &[screen, ("halfPixel", half_pixel(source))],
+            );
+        }
+        for i in (1..self.bloom.len()).rev() {
+            self.bloom[i].bind_color(0, 0);
+            let source = self.bloom[i].size();
+            self.bloom[i - 1].bind();
+            draw("hdr/upsample", &[screen, ("halfPixel", half_pixel(source))]);
+        }
+    }
+
+    /// 合成泛光并执行色调映射与伽马校正，输出到默认帧缓冲
+    fn tonemap(&self) {
+        let (width, height) = App::window_size();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        GL_STATE.set_viewport([0, 0, width, height]);
+        let settings = &self.settings;
+        // 未启用的输入绑定为场景纹理，以免采样未绑定的纹理单元
+        self.scene.bind_color(0, 0);
+        match self.bloom.first() {
+            Some(bloom) if settings.bloom.is_some() => bloom.bind_color(0, 1),
+            _ => self.scene.bind_color(0, 1),
+        }
+        let (exposure, auto, min, max) = match settings.exposure {
+            Exposure::Fixed(exposure) => {
+                self.scene.bind_color(0, 2);
+                (exposure, 0, 0.0, 0.0)
+            }
+            Exposure::Auto { key, min, max, .. } => {
+                self.adapted[self.current].bind_color(0, 2);
+                (key, 1, min, max)
+            }
+        };
+        let intensity = settings.bloom.map_or(0.0, |b| b.intensity);
+        draw(
+            "hdr/tonemap",
+            &[
+                ("screenTexture", UniformValue::Int(0)),
+                ("bloomTexture", UniformValue::Int(1)),
+                ("luminanceTexture", UniformValue::Int(2)),
+                (
+                    "tonemapOperator",
+                    UniformValue::Int(settings.tonemap.index()),
+                ),
+                ("bloomIntensity", UniformValue::Float(intensity)),
+                ("exposure", UniformValue::Float(exposure)),
+                ("autoExposure", UniformValue::Int(auto)),
+                ("minExposure", UniformValue::Float(min)),
+                ("maxExposure", UniformValue::Float(max)),
+                ("gamma", UniformValue::Float(settings.gamma)),
+            ],
+        );
+    }
+}
+
+impl Drop for HdrPipeline {
+    fn drop(&mut self) {
+        GL_STATE.delete_vertex_array(self.vao);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tonemap() {
+        for tonemap in [Tonemap::Reinhard, Tonemap::Aces, Tonemap::Uncharted2] {
+            assert!(tonemap.apply(0.0).abs() < 1e-6);
+            let mut last = 0.0;
+            for i in 1..100 {
+                let value = tonemap.apply(i as f32 * 0.1);
+                assert!(value >= last);
+                // Hable 曲线在超过白点后继续增长，由输出时截断
+                assert!(value <= 1.0 + 1e-6 || tonemap == Tonemap::Uncharted2);
+                last = value;
+            }
+        }
+        assert!((Tonemap::Reinhard.apply(1.0) - 0.5).abs() < 1e-6);
+        assert!((Tonemap::Uncharted2.apply(5.6) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_exposure() {
+        assert_eq!(Exposure::Fixed(2.0).value(100.0), 2.0);
+        let auto = Exposure::auto();
+        assert!((auto.value(0.18) - 1.0).abs() < 1e-6);
+        assert_eq!(auto.value(0.0), 10.0);
+        assert_eq!(auto.value(1000.0), 0.1);
+    }
+
+    #[test]
+    fn test_chains() {
+        let sizes = luminance_sizes();
+        assert_eq!(sizes.len(), 9);
+        assert_eq!(sizes[0], 256);
+        assert_eq!(*sizes.last().unwrap(), 1);
+        assert_eq!(bloom_scale(0), 0.5);
+        assert_eq!(bloom_scale(2), 0.125);
+        assert_eq!(adapt_alpha(0.0, 1.5), 0.0);
+        assert!(adapt_alpha(100.0, 1.5) > 0.999);
+    }
+}
@@ -0,0 +1,66 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform sampler2D bloomTexture;
+uniform sampler2D luminanceTexture;
+// 0: Reinhard, 1: ACES, 2: Uncharted 2
+uniform int tonemapOperator;
+uniform float bloomIntensity;
+// 自动曝光时为目标中灰值，否则为固定曝光值
+uniform float exposure;
+uniform int autoExposure;
+uniform float minExposure;
+uniform float maxExposure;
+uniform float gamma;
+
+vec3 reinhard(vec3 x){
+    return x / (1.0 + x);
+}
+
+vec3 aces(vec3 x){
+    // Krzysztof Narkowicz 的 ACES 拟合曲线
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+}
+
+vec3 uncharted2Curve(vec3 x){
+    const float A = 0.15;
+    const float B = 0.50;
+    const float C = 0.10;
+    const float D = 0.20;
+    const float E = 0.02;
+    const float F = 0.30;
+    return ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F;
+}
+
+vec3 uncharted2(vec3 x){
+    const float W = 11.2;
+    return uncharted2Curve(x * 2.0) / uncharted2Curve(vec3(W));
+}
+
+void main(){
+    vec3 hdr = texture(screenTexture, TexCoords).rgb;
+    hdr += texture(bloomTexture, TexCoords).rgb * bloomIntensity;
+    float scale = exposure;
+    if (autoExposure != 0) {
+        float average = exp(texture(luminanceTexture, vec2(0.5)).r);
+        scale = clamp(exposure / max(average, 0.0001), minExposure, maxExposure);
+    }
+    vec3 color = hdr * scale;
+    if (tonemapOperator == 0) {
+        color = reinhard(color);
+    } else if (tonemapOperator == 1) {
+        color = aces(color);
+    } else {
+        color = uncharted2(color);
+    }
+    FragColor = vec4(pow(color, vec3(1.0 / gamma)), 1.0);
+}
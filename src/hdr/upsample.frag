@@ -0,0 +1,22 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+// 源纹理半个像素的纹理坐标尺寸
+uniform vec2 halfPixel;
+
+void main(){
+    // 双重滤波(Dual Filtering)升采样
+    vec4 sum = texture(screenTexture, TexCoords + vec2(-halfPixel.x * 2.0, 0.0));
+    sum += texture(screenTexture, TexCoords + vec2(-halfPixel.x, halfPixel.y)) * 2.0;
+    sum += texture(screenTexture, TexCoords + vec2(0.0, halfPixel.y * 2.0));
+    sum += texture(screenTexture, TexCoords + vec2(halfPixel.x, halfPixel.y)) * 2.0;
+    sum += texture(screenTexture, TexCoords + vec2(halfPixel.x * 2.0, 0.0));
+    sum += texture(screenTexture, TexCoords + vec2(halfPixel.x, -halfPixel.y)) * 2.0;
+    sum += texture(screenTexture, TexCoords + vec2(0.0, -halfPixel.y * 2.0));
+    sum += texture(screenTexture, TexCoords + vec2(-halfPixel.x, -halfPixel.y)) * 2.0;
+    FragColor = sum / 12.0;
+}
@@ -0,0 +1,79 @@
+//! 读取 JSON 对象中可缺省字段的工具函数，字段为 `null` 或不存在时返回 `Ok(None)`，
+//! 类型不符时返回形如 "{字段} 字段不是…" 的错误
+
+use json::JsonValue;
+
+/// 读取 JSON 对象中可缺省的数值字段
+pub(crate) fn f32_field(json: &JsonValue, field: &str) -> Result<Option<f32>, String> {
+    match &json[field] {
+        JsonValue::Null => Ok(None),
+        value => value
+            .as_f32()
+            .map(Some)
+            .ok_or_else(|| format!("{} 字段不是数值", field)),
+    }
+}
+
+/// 读取 JSON 对象中可缺省的定长数组字段
+pub(crate) fn array_field<const N: usize>(
+    json: &JsonValue,
+    field: &str,
+) -> Result<Option<[f32; N]>, String> {
+    let value = &json[field];
+    if value.is_null() {
+        return Ok(None);
+    }
+    let values = value
+        .members()
+        .map(|v| v.as_f32())
+        .collect::<Option<Vec<_>>>()
+        .filter(|values| value.is_array() && values.len() == N)
+        .ok_or_else(|| format!("{} 字段不是长度为 {} 的数组", field, N))?;
+    let mut array = [0.0; N];
+    array.copy_from_slice(&values);
+    Ok(Some(array))
+}
+
+/// 读取 JSON 对象中可缺省的字符串字段
+pub(crate) fn str_field<'a>(json: &'a JsonValue, field: &str) -> Result<Option<&'a str>, String> {
+    match &json[field] {
+        JsonValue::Null => Ok(None),
+        value => value
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| format!("{} 字段不是字符串", field)),
+    }
+}
+
+/// 读取 JSON 对象中可缺省的布尔字段
+pub(crate) fn bool_field(json: &JsonValue, field: &str) -> Result<Option<bool>, String> {
+    match &json[field] {
+        JsonValue::Null => Ok(None),
+        value => value
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| format!("{} 字段不是布尔值", field)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields() {
+        let json = json::parse(r#"{ "a": 1.5, "v": [1, 2, 3], "s": "x", "b": true }"#).unwrap();
+        assert_eq!(f32_field(&json, "a"), Ok(Some(1.5)));
+        assert_eq!(array_field(&json, "v"), Ok(Some([1.0, 2.0, 3.0])));
+        assert_eq!(str_field(&json, "s"), Ok(Some("x")));
+        assert_eq!(bool_field(&json, "b"), Ok(Some(true)));
+        assert_eq!(f32_field(&json, "missing"), Ok(None));
+        assert_eq!(
+            array_field::<4>(&json, "v"),
+            Err("v 字段不是长度为 4 的数组".to_string())
+        );
+        assert_eq!(f32_field(&json, "s"), Err("s 字段不是数值".to_string()));
+        assert!(str_field(&json, "a").is_err());
+        assert!(bool_field(&json, "a").is_err());
+    }
+}
@@ -3,18 +3,46 @@ use std::sync::Mutex;
 pub use {gl, glfw, image, json, mats};
 
 mod app;
+mod assets;
 pub mod camera;
+pub mod deferred;
+pub mod ecs;
+mod framebuffer;
+pub mod gl_state;
 pub(crate) mod gl_utils;
+pub(crate) mod json_utils;
+pub mod hdr;
+pub mod light;
 pub mod log;
+pub mod material;
 mod model;
+pub mod postprocess;
+pub mod render_queue;
+pub mod scene;
 mod shader;
-mod texture;
+pub mod shadow;
+mod skybox;
+pub mod ssao;
+pub mod texture;
+pub mod vfs;
 
 pub use app::{App, AppBuilder, TimeType};
+pub use assets::{
+    loader::{UploadTask, ASSET_LOADER},
+    Asset, AssetId, Handle, LoadState, UntypedHandle, ASSETS,
+};
 pub use camera::CAMERA_SYSTEM;
-pub use model::MODEL_MANAGER;
-pub use shader::SHADER_MANAGER;
-pub use texture::TEXTURE_MANAGER;
+pub use ecs::WORLD;
+pub use framebuffer::{Framebuffer, FramebufferBuilder, FramebufferSize};
+pub use gl_state::GL_STATE;
+pub use light::LIGHT_SYSTEM;
+pub use material::{Material, MATERIAL_MANAGER};
+pub use model::{Mesh, Model, MODEL_MANAGER};
+pub use postprocess::{PostProcessChain, PostProcessPass};
+pub use shader::{Shader, UniformValue, SHADER_MANAGER};
+pub use skybox::Skybox;
+pub use texture::{texture::Texture, TEXTURE_MANAGER};
+pub use vfs::{ScanOptions, VFS};
 
 /// 全局对象包装器
 /// 它利用 Mutex 的内部可变性实现全局对象的简洁访问
@@ -0,0 +1,23 @@
+#version 330 core
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoord;
+
+out vec4 FragColor;
+
+#include <lights>
+
+uniform sampler2D diffuseMap;
+uniform sampler2D specularMap;
+uniform float shininess;
+uniform vec3 viewPos;
+
+void main(){
+    vec4 albedo = texture(diffuseMap, TexCoord);
+    vec3 specular = texture(specularMap, TexCoord).rgb;
+    vec3 normal = normalize(Normal);
+    vec3 viewDir = normalize(viewPos - FragPos);
+    vec3 color = computeLighting(FragPos, normal, viewDir, albedo.rgb, specular, shininess);
+    FragColor = vec4(color, albedo.a);
+}
@@ -0,0 +1,72 @@
+// 由 LIGHT_SYSTEM 每帧上传的光源数据，绑定点与数组长度必须与 light/mod.rs 一致
+#define MAX_DIRECTIONAL_LIGHTS 4
+#define MAX_POINT_LIGHTS 32
+#define MAX_SPOT_LIGHTS 16
+
+struct DirectionalLight {
+    vec4 direction;
+    vec4 color;
+};
+
+struct PointLight {
+    vec4 position;
+    vec4 color;
+    // 常数项、一次项、二次项衰减系数
+    vec4 attenuation;
+};
+
+struct SpotLight {
+    vec4 position;
+    vec4 direction;
+    vec4 color;
+    vec4 attenuation;
+    // 内、外锥角的余弦值
+    vec4 cutoff;
+};
+
+layout (std140) uniform Lights {
+    // 方向光、点光源与聚光灯的数量
+    ivec4 lightCounts;
+    vec4 ambientLight;
+    DirectionalLight directionalLights[MAX_DIRECTIONAL_LIGHTS];
+    PointLight pointLights[MAX_POINT_LIGHTS];
+    SpotLight spotLights[MAX_SPOT_LIGHTS];
+};
+
+float attenuate(vec4 attenuation, float dist){
+    return 1.0 / (attenuation.x + attenuation.y * dist + attenuation.z * dist * dist);
+}
+
+// 单个光源的 Blinn-Phong 漫反射与镜面反射
+vec3 blinnPhong(vec3 lightDir, vec3 radiance, vec3 normal, vec3 viewDir, vec3 diffuse, vec3 specular, float shininess){
+    float diff = max(dot(normal, lightDir), 0.0);
+    vec3 halfway = normalize(lightDir + viewDir);
+    float spec = diff > 0.0 ? pow(max(dot(normal, halfway), 0.0), shininess) : 0.0;
+    return radiance * (diff * diffuse + spec * specular);
+}
+
+// 累加环境光与全部光源的光照
+vec3 computeLighting(vec3 fragPos, vec3 normal, vec3 viewDir, vec3 diffuse, vec3 specular, float shininess){
+    vec3 result = ambientLight.rgb * diffuse;
+    for (int i = 0; i < lightCounts.x; ++i) {
+        vec3 lightDir = -directionalLights[i].direction.xyz;
+        result += blinnPhong(lightDir, directionalLights[i].color.rgb, normal, viewDir, diffuse, specular, shininess);
+    }
+    for (int i = 0; i < lightCounts.y; ++i) {
+        vec3 toLight = pointLights[i].position.xyz - fragPos;
+        float dist = length(toLight);
+        vec3 radiance = pointLights[i].color.rgb * attenuate(pointLights[i].attenuation, dist);
+        result += blinnPhong(toLight / dist, radiance, normal, viewDir, diffuse, specular, shininess);
+    }
+    for (int i = 0; i < lightCounts.z; ++i) {
+        vec3 toLight = spotLights[i].position.xyz - fragPos;
+        float dist = length(toLight);
+        vec3 lightDir = toLight / dist;
+        float theta = dot(lightDir, -spotLights[i].direction.xyz);
+        vec4 cutoff = spotLights[i].cutoff;
+        float cone = clamp((theta - cutoff.y) / max(cutoff.x - cutoff.y, 0.0001), 0.0, 1.0);
+        vec3 radiance = spotLights[i].color.rgb * attenuate(spotLights[i].attenuation, dist) * cone;
+        result += blinnPhong(lightDir, radiance, normal, viewDir, diffuse, specular, shininess);
+    }
+    return result;
+}
@@ -0,0 +1,743 @@
+use std::collections::BTreeMap;
+
+use gl::types::*;
+use json::{object, JsonValue};
+use lazy_static::lazy_static;
+use mats::{Mat4, Vec3};
+
+use crate::{
+    gl_utils,
+    json_utils::{array_field, f32_field},
+    warn, RustCraftWrapper, Shader, CAMERA_SYSTEM, SHADER_MANAGER, TEXTURE_MANAGER,
+};
+
+const LIGHTS_GLSL: &str = include_str!("lights.glsl");
+const BLINN_PHONG_VERT: &str = include_str!("blinn_phong.vert");
+const BLINN_PHONG_FRAG: &str = include_str!("blinn_phong.frag");
+
+/// 着色器中光源 uniform 块的名称
+pub const LIGHT_BLOCK: &str = "Lights";
+/// 光源 uniform 块的绑定点
+pub const LIGHT_BLOCK_BINDING: u32 = 0;
+/// 方向光的最大数量
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+/// 点光源的最大数量
+pub const MAX_POINT_LIGHTS: usize = 32;
+/// 聚光灯的最大数量
+pub const MAX_SPOT_LIGHTS: usize = 16;
+/// 点光源与聚光灯截止距离的上限，不随距离衰减的光源以此作为影响范围
+pub const MAX_LIGHT_DISTANCE: f32 = 10000.0;
+/// 参考 Blinn-Phong 着色器在 `SHADER_MANAGER` 中的名称
+pub const BLINN_PHONG_SHADER: &str = "light/blinn_phong";
+
+/// 各类光源在 std140 布局下占用的 `vec4` 数量
+const DIRECTIONAL_VEC4S: usize = 2;
+const POINT_VEC4S: usize = 3;
+const SPOT_VEC4S: usize = 5;
+/// uniform 块的总长度(以 `f32` 计)：数量、环境光与三个光源数组
+const BLOCK_FLOATS: usize = 4
+    * (2 + MAX_DIRECTIONAL_LIGHTS * DIRECTIONAL_VEC4S
+        + MAX_POINT_LIGHTS * POINT_VEC4S
+        + MAX_SPOT_LIGHTS * SPOT_VEC4S);
+
+/// 光源 uniform 块及光照函数的 GLSL 源码
+///
+/// 自定义着色器在 `#version` 之后插入此源码即可调用
+/// `computeLighting(fragPos, normal, viewDir, diffuse, specular, shininess)`；
+/// 着色器源码中的 `#include <lights>` 会在编译前被自动替换为此源码
+pub fn lights_glsl() -> &'static str {
+    LIGHTS_GLSL
+}
+
+/// 距离衰减系数，衰减后的强度为 `1 / (constant + linear * d + quadratic * d²)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Default for Attenuation {
+    /// 有效范围约为 50 个单位的衰减
+    fn default() -> Self {
+        Self::from_range(50.0)
+    }
+}
+
+impl Attenuation {
+    /// 按有效范围估算衰减系数，在 `range` 处强度约衰减至 1%
+    pub fn from_range(range: f32) -> Self {
+        let range = range.max(0.0001);
+        Self {
+            constant: 1.0,
+            linear: 4.5 / range,
+            quadratic: 75.0 / (range * range),
+        }
+    }
+
+    /// 指定距离处的衰减因子
+    pub fn at(&self, distance: f32) -> f32 {
+        1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+    }
+
+    /// 亮度为 `brightness` 的光源衰减至不可见(低于 5/256)的距离
+    ///
+    /// # 注解 Note
+    ///
+    /// 结果不超过 `MAX_LIGHT_DISTANCE`，线性与二次项均为 0 的光源不随距离衰减，
+    /// 截止距离即为该上限
+    pub fn cutoff_distance(&self, brightness: f32) -> f32 {
+        let target = brightness * 256.0 / 5.0 - self.constant;
+        let distance = if target <= 0.0 {
+            0.0
+        } else if self.quadratic > 0.0 {
+            let (a, b) = (self.quadratic, self.linear);
+            (-b + (b * b + 4.0 * a * target).sqrt()) / (2.0 * a)
+        } else if self.linear > 0.0 {
+            target / self.linear
+        } else {
+            MAX_LIGHT_DISTANCE
+        };
+        distance.min(MAX_LIGHT_DISTANCE)
+    }
+}
+
+/// 方向光，例如日光
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// 光线的传播方向，无需归一化
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: [f32; 3], color: [f32; 3]) -> Self {
+        Self {
+            direction,
+            color,
+            intensity: 1.0,
+        }
+    }
+}
+
+/// 点光源
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub attenuation: Attenuation,
+}
+
+impl PointLight {
+    pub fn new(position: [f32; 3], color: [f32; 3]) -> Self {
+        Self {
+            position,
+            color,
+            intensity: 1.0,
+            attenuation: Attenuation::default(),
+        }
+    }
+
+    /// 设置有效范围，参见 `Attenuation::from_range`
+    pub fn range(mut self, range: f32) -> Self {
+        self.attenuation = Attenuation::from_range(range);
+        self
+    }
+}
+
+/// 聚光灯，例如手电筒
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    pub position: [f32; 3],
+    /// 光锥的朝向，无需归一化
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub attenuation: Attenuation,
+    /// 内锥半角(角度)，内锥内强度不衰减
+    pub inner_angle: f32,
+    /// 外锥半角(角度)，内外锥之间强度平滑衰减至 0
+    pub outer_angle: f32,
+}
+
+impl SpotLight {
+    pub fn new(position: [f32; 3], direction: [f32; 3], color: [f32; 3]) -> Self {
+        Self {
+            position,
+            direction,
+            color,
+            intensity: 1.0,
+            attenuation: Attenuation::default(),
+            inner_angle: 12.5,
+            outer_angle: 17.5,
+        }
+    }
+
+    /// 设置内外锥半角(角度)
+    pub fn cone(mut self, inner_angle: f32, outer_angle: f32) -> Self {
+        self.inner_angle = inner_angle;
+        self.outer_angle = outer_angle;
+        self
+    }
+
+    /// 设置有效范围，参见 `Attenuation::from_range`
+    pub fn range(mut self, range: f32) -> Self {
+        self.attenuation = Attenuation::from_range(range);
+        self
+    }
+}
+
+/// 光源
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    Directional(DirectionalLight),
+    Point(PointLight),
+    Spot(SpotLight),
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Self {
+        Light::Directional(light)
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Light::Point(light)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Light::Spot(light)
+    }
+}
+
+impl Light {
+    /// 转换为 JSON 对象，格式参见 `from_json`
+    pub fn to_json(&self) -> JsonValue {
+        let attenuation = |a: &Attenuation| vec![a.constant, a.linear, a.quadratic];
+        match self {
+            Light::Directional(light) => object! {
+                type: "directional",
+                direction: light.direction.to_vec(),
+                color: light.color.to_vec(),
+                intensity: light.intensity,
+            },
+            Light::Point(light) => object! {
+                type: "point",
+                position: light.position.to_vec(),
+                color: light.color.to_vec(),
+                intensity: light.intensity,
+                attenuation: attenuation(&light.attenuation),
+            },
+            Light::Spot(light) => object! {
+                type: "spot",
+                position: light.position.to_vec(),
+                direction: light.direction.to_vec(),
+                color: light.color.to_vec(),
+                intensity: light.intensity,
+                attenuation: attenuation(&light.attenuation),
+                inner_angle: light.inner_angle,
+                outer_angle: light.outer_angle,
+            },
+        }
+    }
+
+    /// 从 JSON 对象解析光源
+    ///
+    /// # 注解 Note
+    ///
+    /// 格式：
+    /// ```json
+    /// { "type": "spot", "position": [0.0, 2.0, 0.0], "direction": [0.0, -1.0, 0.0],
+    ///   "color": [1.0, 1.0, 1.0], "intensity": 1.0, "range": 20.0,
+    ///   "inner_angle": 12.5, "outer_angle": 17.5 }
+    /// ```
+    /// `type` 为 `directional`、`point` 或 `spot`；衰减可写作 `"range": r`
+    /// 或 `"attenuation": [constant, linear, quadratic]`；缺省的字段取构造函数的默认值
+    pub fn from_json(json: &JsonValue) -> Result<Self, String> {
+        if !json.is_object() {
+            return Err("JSON 不是对象".to_string());
+        }
+        let color = array_field(json, "color")?.unwrap_or([1.0; 3]);
+        let position = array_field(json, "position")?.unwrap_or([0.0; 3]);
+        let direction = array_field(json, "direction")?.unwrap_or([0.0, -1.0, 0.0]);
+        let mut light: Light = match json["type"].as_str() {
+            Some("directional") => DirectionalLight::new(direction, color).into(),
+            Some("point") => PointLight::new(position, color).into(),
+            Some("spot") => SpotLight::new(position, direction, color).into(),
+            Some(kind) => return Err(format!("未知的光源类型 {}", kind)),
+            None => return Err("JSON 中缺少 type 字段".to_string()),
+        };
+        let attenuation = match (&json["range"], &json["attenuation"]) {
+            (JsonValue::Null, JsonValue::Null) => None,
+            (range, JsonValue::Null) => Some(Attenuation::from_range(
+                range
+                    .as_f32()
+                    .ok_or_else(|| "range 字段不是数值".to_string())?,
+            )),
+            _ => {
+                array_field(json, "attenuation")?.map(|[constant, linear, quadratic]| Attenuation {
+                    constant,
+                    linear,
+                    quadratic,
+                })
+            }
+        };
+        let intensity = f32_field(json, "intensity")?;
+        match &mut light {
+            Light::Directional(light) => {
+                light.intensity = intensity.unwrap_or(light.intensity);
+            }
+            Light::Point(light) => {
+                light.intensity = intensity.unwrap_or(light.intensity);
+                light.attenuation = attenuation.unwrap_or(light.attenuation);
+            }
+            Light::Spot(light) => {
+                light.intensity = intensity.unwrap_or(light.intensity);
+                light.attenuation = attenuation.unwrap_or(light.attenuation);
+                light.inner_angle = f32_field(json, "inner_angle")?.unwrap_or(light.inner_angle);
+                light.outer_angle = f32_field(json, "outer_angle")?.unwrap_or(light.outer_angle);
+            }
+        }
+        Ok(light)
+    }
+}
+
+fn normalize([x, y, z]: [f32; 3]) -> [f32; 3] {
+    let len = (x * x + y * y + z * z).sqrt();
+    if len > 0.0 {
+        [x / len, y / len, z / len]
+    } else {
+        [0.0, -1.0, 0.0]
+    }
+}
+
+fn scaled(color: [f32; 3], intensity: f32) -> [f32; 3] {
+    color.map(|c| c * intensity)
+}
+
+/// 编译参考 Blinn-Phong 着色器，并以 `BLINN_PHONG_SHADER` 为名登记于 `SHADER_MANAGER`
+///
+/// 顶点属性依次为位置(`vec3`)、法线(`vec3`)与纹理坐标(`vec2`)，即模型描述 `3f;3f;2f`
+pub(crate) fn load_shaders() {
+    SHADER_MANAGER.add_from_source(BLINN_PHONG_SHADER, BLINN_PHONG_VERT, BLINN_PHONG_FRAG);
+}
+
+/// 由视图矩阵求摄像机在世界空间中的位置
+pub fn camera_position(view: Mat4<f32>) -> [f32; 3] {
+    // 视图矩阵为 [R | t]，摄像机位置为 -Rᵀt
+    let mut pos = [0.0; 3];
+    for (j, p) in pos.iter_mut().enumerate() {
+        *p = -(0..3).map(|i| view[i][j] * view[i][3]).sum::<f32>();
+    }
+    pos
+}
+
+/// 光源管理系统
+///
+/// 光源按名称保存，每帧由渲染线程上传到绑定点为 `LIGHT_BLOCK_BINDING` 的 uniform 块；
+/// 声明了 `Lights` uniform 块的着色器在链接后自动绑定到该绑定点
+pub struct LightSystem {
+    lights: BTreeMap<String, Light>,
+    ambient: [f32; 3],
+    /// uniform 缓冲对象，首次上传时创建
+    ubo: GLuint,
+    /// 光源自上次上传后是否发生变化
+    dirty: bool,
+    /// 是否已经输出过光源数量超出上限的警告
+    overflow_warned: bool,
+}
+
+impl LightSystem {
+    fn new() -> Self {
+        Self {
+            lights: BTreeMap::new(),
+            ambient: [0.1, 0.1, 0.1],
+            ubo: 0,
+            dirty: true,
+            overflow_warned: false,
+        }
+    }
+
+    /// 按类型分组的光源，顺序与 uniform 块中的数组一致，超出上限的光源按名称顺序被忽略
+    ///
+    /// # 返回值 Returns
+    /// 方向光、点光源、聚光灯，以及是否有光源被忽略
+    fn grouped(
+        &self,
+    ) -> (
+        Vec<&DirectionalLight>,
+        Vec<&PointLight>,
+        Vec<&SpotLight>,
+        bool,
+    ) {
+        let mut directional = vec![];
+        let mut point = vec![];
+        let mut spot = vec![];
+        for light in self.lights.values() {
+            match light {
+                Light::Directional(l) => directional.push(l),
+                Light::Point(l) => point.push(l),
+                Light::Spot(l) => spot.push(l),
+            }
+        }
+        let overflow = directional.len() > MAX_DIRECTIONAL_LIGHTS
+            || point.len() > MAX_POINT_LIGHTS
+            || spot.len() > MAX_SPOT_LIGHTS;
+        directional.truncate(MAX_DIRECTIONAL_LIGHTS);
+        point.truncate(MAX_POINT_LIGHTS);
+        spot.truncate(MAX_SPOT_LIGHTS);
+        (directional, point, spot, overflow)
+    }
+
+    /// 按 std140 布局打包光源数据，超出上限的光源按名称顺序被忽略
+    ///
+    /// # 返回值 Returns
+    /// 打包后的数据，以及是否有光源被忽略
+    fn pack(&self) -> (Vec<f32>, bool) {
+        let (directional, point, spot, overflow) = self.grouped();
+
+        let mut data = vec![0.0; BLOCK_FLOATS];
+        // ivec4 以位模式写入
+        data[0] = f32::from_bits(directional.len() as u32);
+        data[1] = f32::from_bits(point.len() as u32);
+        data[2] = f32::from_bits(spot.len() as u32);
+        data[4..7].copy_from_slice(&self.ambient);
+
+        let mut offset = 8;
+        let put = |data: &mut [f32], offset: usize, v: [f32; 3], w: f32| {
+            data[offset..offset + 3].copy_from_slice(&v);
+            data[offset + 3] = w;
+        };
+        for (i, l) in directional.iter().enumerate() {
+            let base = offset + i * DIRECTIONAL_VEC4S * 4;
+            put(&mut data, base, normalize(l.direction), 0.0);
+            put(&mut data, base + 4, scaled(l.color, l.intensity), 0.0);
+        }
+        offset += MAX_DIRECTIONAL_LIGHTS * DIRECTIONAL_VEC4S * 4;
+        for (i, l) in point.iter().enumerate() {
+            let base = offset + i * POINT_VEC4S * 4;
+            let a = l.attenuation;
+            put(&mut data, base, l.position, 1.0);
+            put(&mut data, base + 4, scaled(l.color, l.intensity), 0.0);
+            put(
+                &mut data,
+                base + 8,
+                [a.constant, a.linear, a.quadratic],
+                0.0,
+            );
+        }
+        offset += MAX_POINT_LIGHTS * POINT_VEC4S * 4;
+        for (i, l) in spot.iter().enumerate() {
+            let base = offset + i * SPOT_VEC4S * 4;
+            let a = l.attenuation;
+            let inner = l.inner_angle.to_radians().cos();
+            let outer = l.outer_angle.max(l.inner_angle).to_radians().cos();
+            put(&mut data, base, l.position, 1.0);
+            put(&mut data, base + 4, normalize(l.direction), 0.0);
+            put(&mut data, base + 8, scaled(l.color, l.intensity), 0.0);
+            put(
+                &mut data,
+                base + 12,
+                [a.constant, a.linear, a.quadratic],
+                0.0,
+            );
+            put(&mut data, base + 16, [inner, outer, 0.0], 0.0);
+        }
+        (data, overflow)
+    }
+
+    /// 将光源数据上传到 uniform 缓冲并绑定到 `LIGHT_BLOCK_BINDING`
+    fn upload(&mut self) {
+        if self.ubo == 0 {
+            self.ubo = unsafe { gl_utils::create_uniform_buffer(BLOCK_FLOATS) };
+            self.dirty = true;
+        }
+        if self.dirty {
+            let (data, overflow) = self.pack();
+            if overflow && !self.overflow_warned {
+                warn!(
+                    "LightSystem",
+                    "光源数量超出上限(方向光 {}、点光源 {}、聚光灯 {}), 超出的光源将被忽略",
+                    MAX_DIRECTIONAL_LIGHTS,
+                    MAX_POINT_LIGHTS,
+                    MAX_SPOT_LIGHTS
+                );
+            }
+            self.overflow_warned = overflow;
+            unsafe {
+                gl_utils::update_uniform_buffer(self.ubo, &data);
+            }
+            self.dirty = false;
+        }
+        unsafe {
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, LIGHT_BLOCK_BINDING, self.ubo);
+        }
+    }
+}
+
+lazy_static! {
+    pub static ref LIGHT_SYSTEM: RustCraftWrapper<LightSystem> =
+        RustCraftWrapper::new(LightSystem::new());
+}
+
+impl RustCraftWrapper<LightSystem> {
+    /// 添加光源
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 光源名称，已被占用时输出警告且不做修改
+    /// * `light` - 方向光、点光源或聚光灯
+    ///
+    /// # 示例 Examples
+    /// ```
+    /// use rustcraft::light::{PointLight, LIGHT_SYSTEM};
+    ///
+    /// LIGHT_SYSTEM.add("lamp", PointLight::new([0.0, 2.0, 0.0], [1.0, 0.9, 0.8]).range(20.0));
+    /// LIGHT_SYSTEM.update("lamp", |light| {
+    ///     if let rustcraft::light::Light::Point(lamp) = light {
+    ///         lamp.intensity = 2.0;
+    ///     }
+    /// });
+    /// ```
+    pub fn add<T: Into<Light>>(&self, name: &str, light: T) {
+        let light = light.into();
+        self.apply(|sys| {
+            if sys.lights.contains_key(name) {
+                warn!("LightSystem", "名称 {} 已经被占用", name);
+            } else {
+                sys.lights.insert(name.to_string(), light);
+                sys.dirty = true;
+            }
+        });
+    }
+
+    /// 设置光源，已存在同名光源时覆盖
+    pub fn set<T: Into<Light>>(&self, name: &str, light: T) {
+        let light = light.into();
+        self.apply(|sys| {
+            sys.lights.insert(name.to_string(), light);
+            sys.dirty = true;
+        });
+    }
+
+    /// 修改指定名称的光源
+    pub fn update<F: FnOnce(&mut Light)>(&self, name: &str, f: F) {
+        self.apply(|sys| match sys.lights.get_mut(name) {
+            Some(light) => {
+                f(light);
+                sys.dirty = true;
+            }
+            None => {
+                warn!("LightSystem", "没有名为 {} 的光源", name);
+            }
+        });
+    }
+
+    /// 移除指定名称的光源
+    pub fn remove(&self, name: &str) -> Option<Light> {
+        let mut ret = None;
+        self.apply(|sys| {
+            ret = sys.lights.remove(name);
+            sys.dirty |= ret.is_some();
+        });
+        ret
+    }
+
+    /// 获取指定名称的光源
+    pub fn get(&self, name: &str) -> Option<Light> {
+        let mut ret = None;
+        self.apply(|sys| ret = sys.lights.get(name).copied());
+        ret
+    }
+
+    /// 全部光源的名称，按名称排序
+    pub fn names(&self) -> Vec<String> {
+        let mut ret = vec![];
+        self.apply(|sys| ret = sys.lights.keys().cloned().collect());
+        ret
+    }
+
+    /// 已上传的点光源与聚光灯，下标与 uniform 块中 `pointLights`、`spotLights` 的下标一致
+    pub(crate) fn local_lights(&self) -> (Vec<PointLight>, Vec<SpotLight>) {
+        let mut ret = (vec![], vec![]);
+        self.apply(|sys| {
+            let (_, point, spot, _) = sys.grouped();
+            ret = (
+                point.into_iter().copied().collect(),
+                spot.into_iter().copied().collect(),
+            );
+        });
+        ret
+    }
+
+    /// 环境光颜色
+    pub fn ambient(&self) -> [f32; 3] {
+        let mut ret = [0.0; 3];
+        self.apply(|sys| ret = sys.ambient);
+        ret
+    }
+
+    /// 设置环境光颜色
+    pub fn set_ambient(&self, ambient: [f32; 3]) {
+        self.apply(|sys| {
+            sys.ambient = ambient;
+            sys.dirty = true;
+        });
+    }
+
+    /// 上传光源数据
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，渲染线程每帧在渲染回调前自动调用
+    pub fn upload(&self) {
+        self.apply(|sys| sys.upload());
+    }
+
+    /// 启用参考 Blinn-Phong 着色器并绑定漫反射与镜面反射贴图
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 漫反射贴图绑定到纹理单元 0，镜面反射贴图绑定到纹理单元 1，
+    /// 摄像机位置取自 `CAMERA_SYSTEM` 的视图矩阵；`model`、`view`、`projection`
+    /// 需由调用者设置
+    ///
+    /// # 参数 Parameters
+    /// * `diffuse` - `TEXTURE_MANAGER` 中的漫反射贴图名称
+    /// * `specular` - `TEXTURE_MANAGER` 中的镜面反射贴图名称
+    /// * `shininess` - 高光指数
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// let shader = LIGHT_SYSTEM.use_blinn_phong("container2", "container2_specular", 32.0).unwrap();
+    /// shader.set_uniform("model", model);
+    /// shader.set_uniform("view", view);
+    /// shader.set_uniform("projection", proj);
+    /// MODEL_MANAGER.draw_model("lit_cube");
+    /// ```
+    pub fn use_blinn_phong(&self, diffuse: &str, specular: &str, shininess: f32) -> Option<Shader> {
+        let shader = SHADER_MANAGER.get(BLINN_PHONG_SHADER)?;
+        shader.use_program();
+        TEXTURE_MANAGER.bind(diffuse, 0);
+        TEXTURE_MANAGER.bind(specular, 1);
+        shader.set_uniform("diffuseMap", 0);
+        shader.set_uniform("specularMap", 1);
+        shader.set_uniform("shininess", shininess);
+        let view_pos = camera_position(CAMERA_SYSTEM.view_matrix());
+        shader.set_uniform("viewPos", Vec3::from(view_pos));
+        Some(shader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mats::{rotate3, tranlate3};
+
+    #[test]
+    fn test_attenuation() {
+        let a = Attenuation::from_range(20.0);
+        assert_eq!(a.at(0.0), 1.0);
+        assert!(a.at(20.0) < 0.02);
+        assert!(a.at(5.0) > a.at(10.0));
+        let d = a.cutoff_distance(2.0);
+        assert!((2.0 * a.at(d) - 5.0 / 256.0).abs() < 1e-5);
+        assert_eq!(a.cutoff_distance(0.0), 0.0);
+
+        let constant = Attenuation {
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.0,
+        };
+        assert_eq!(constant.cutoff_distance(1.0), MAX_LIGHT_DISTANCE);
+        assert_eq!(constant.cutoff_distance(0.01), 0.0);
+    }
+
+    #[test]
+    fn test_json() {
+        // 取二进制下精确的衰减系数，避免数值经十进制往返后产生误差
+        let attenuation = Attenuation {
+            constant: 1.0,
+            linear: 0.5,
+            quadratic: 0.25,
+        };
+        let mut lamp = PointLight::new([1.0, 2.0, 3.0], [0.5; 3]);
+        lamp.attenuation = attenuation;
+        let mut torch = SpotLight::new([0.0; 3], [0.0, 0.0, -1.0], [1.0; 3]).cone(10.0, 20.0);
+        torch.attenuation = attenuation;
+        let lights: [Light; 3] = [
+            DirectionalLight::new([0.0, -1.0, 0.5], [1.0, 0.75, 0.5]).into(),
+            lamp.into(),
+            torch.into(),
+        ];
+        for light in lights {
+            assert_eq!(Light::from_json(&light.to_json()), Ok(light));
+        }
+        let json = json::parse(r#"{ "type": "point", "position": [0, 1, 0], "range": 20 }"#);
+        let expected = PointLight::new([0.0, 1.0, 0.0], [1.0; 3]).range(20.0);
+        assert_eq!(Light::from_json(&json.unwrap()), Ok(expected.into()));
+        assert!(Light::from_json(&json::parse(r#"{ "type": "area" }"#).unwrap()).is_err());
+        let json = json::parse(r#"{ "type": "point", "color": [1, 1] }"#).unwrap();
+        assert!(Light::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_pack() {
+        let mut sys = LightSystem::new();
+        sys.lights.insert(
+            "sun".to_string(),
+            DirectionalLight::new([0.0, -2.0, 0.0], [1.0, 1.0, 1.0]).into(),
+        );
+        let mut lamp = PointLight::new([1.0, 2.0, 3.0], [1.0, 0.5, 0.0]);
+        lamp.intensity = 2.0;
+        sys.lights.insert("lamp".to_string(), lamp.into());
+        sys.lights.insert(
+            "torch".to_string(),
+            SpotLight::new([0.0; 3], [0.0, 0.0, -1.0], [1.0; 3])
+                .cone(60.0, 60.0)
+                .into(),
+        );
+        let (data, overflow) = sys.pack();
+        assert!(!overflow);
+        assert_eq!(data.len(), BLOCK_FLOATS);
+        assert_eq!(data[0].to_bits(), 1);
+        assert_eq!(data[1].to_bits(), 1);
+        assert_eq!(data[2].to_bits(), 1);
+        assert_eq!(data[4..7], [0.1, 0.1, 0.1]);
+        // 方向光已归一化
+        assert_eq!(data[8..11], [0.0, -1.0, 0.0]);
+        let point = 8 + MAX_DIRECTIONAL_LIGHTS * DIRECTIONAL_VEC4S * 4;
+        assert_eq!(data[point..point + 4], [1.0, 2.0, 3.0, 1.0]);
+        assert_eq!(data[point + 4..point + 7], [2.0, 1.0, 0.0]);
+        let spot = point + MAX_POINT_LIGHTS * POINT_VEC4S * 4;
+        assert!((data[spot + 16] - 0.5).abs() < 1e-6);
+        assert_eq!(spot + MAX_SPOT_LIGHTS * SPOT_VEC4S * 4, BLOCK_FLOATS);
+
+        for i in 0..MAX_DIRECTIONAL_LIGHTS {
+            sys.lights.insert(
+                format!("extra{}", i),
+                DirectionalLight::new([1.0, 0.0, 0.0], [1.0; 3]).into(),
+            );
+        }
+        let (data, overflow) = sys.pack();
+        assert!(overflow);
+        assert_eq!(data[0].to_bits(), MAX_DIRECTIONAL_LIGHTS as u32);
+    }
+
+    #[test]
+    fn test_camera_position() {
+        let eye = [1.0, 2.0, 3.0];
+        let view =
+            rotate3(0.7, Vec3::from([0.0, 1.0, 0.0])) * tranlate3(Vec3::from([-1.0, -2.0, -3.0]));
+        let pos = camera_position(view);
+        for i in 0..3 {
+            assert!((pos[i] - eye[i]).abs() < 1e-5);
+        }
+    }
+}
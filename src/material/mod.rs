@@ -0,0 +1,530 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use json::{object, JsonValue};
+use lazy_static::lazy_static;
+use mats::Mat4;
+
+use crate::{
+    debug, vfs::VFS, warn, Handle, RustCraftWrapper, Shader, UniformValue, ASSETS, SHADER_MANAGER,
+    TEXTURE_MANAGER,
+};
+
+/// 单个材质可绑定的纹理数量上限，与 OpenGL 3.3 保证的片段着色器纹理单元数一致
+pub const MAX_MATERIAL_TEXTURES: usize = 16;
+
+/// 材质
+///
+/// 描述一次绘制所需的着色器、纹理与 uniform 参数；纹理按添加顺序依次绑定到纹理单元
+/// 0, 1, 2 ...，并自动将对应的采样器 uniform 设置为其纹理单元
+///
+/// 材质的副本共享同一份数据，复制的开销很小，修改时才复制数据
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    shader: Arc<str>,
+    textures: Arc<Vec<(String, String)>>,
+    uniforms: Arc<Vec<(String, UniformValue)>>,
+    transparent: bool,
+}
+
+impl Material {
+    /// 创建使用指定着色器的空材质
+    ///
+    /// # 参数 Parameters
+    /// * `shader` - `SHADER_MANAGER` 中的着色器名称
+    ///
+    /// # 示例 Examples
+    /// ```
+    /// use rustcraft::material::Material;
+    ///
+    /// let material = Material::new("light/blinn_phong")
+    ///     .texture("diffuseMap", "container2")
+    ///     .texture("specularMap", "container2_specular")
+    ///     .uniform("shininess", 32.0);
+    /// assert_eq!(material.texture_unit("specularMap"), Some(1));
+    /// ```
+    pub fn new(shader: &str) -> Self {
+        Self {
+            shader: shader.into(),
+            textures: Arc::new(vec![]),
+            uniforms: Arc::new(vec![]),
+            transparent: false,
+        }
+    }
+
+    /// 将纹理绑定到指定的采样器
+    ///
+    /// # 参数 Parameters
+    /// * `sampler` - 着色器中的采样器 uniform 名称
+    /// * `texture` - `TEXTURE_MANAGER` 中的纹理名称
+    pub fn texture(mut self, sampler: &str, texture: &str) -> Self {
+        self.set_texture(sampler, texture);
+        self
+    }
+
+    /// 设置 uniform 参数
+    pub fn uniform<T: Into<UniformValue>>(mut self, name: &str, value: T) -> Self {
+        self.set_uniform(name, value);
+        self
+    }
+
+    /// 设置材质是否透明，透明材质在延迟渲染中以前向渲染绘制
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// 将纹理绑定到指定的采样器，采样器已存在时替换纹理并保留其纹理单元
+    ///
+    /// # 注解 Note
+    ///
+    /// 纹理数量首次超出 `MAX_MATERIAL_TEXTURES` 时输出警告，超出的纹理不会被绑定
+    pub fn set_texture(&mut self, sampler: &str, texture: &str) {
+        let textures = Arc::make_mut(&mut self.textures);
+        match textures.iter_mut().find(|(s, _)| s == sampler) {
+            Some((_, t)) => *t = texture.to_string(),
+            None => {
+                textures.push((sampler.to_string(), texture.to_string()));
+                if textures.len() == MAX_MATERIAL_TEXTURES + 1 {
+                    warn!(
+                        "Material",
+                        "材质纹理数量超出上限 {}, 超出的纹理将被忽略", MAX_MATERIAL_TEXTURES
+                    );
+                }
+            }
+        }
+    }
+
+    /// 设置 uniform 参数，已存在时替换
+    pub fn set_uniform<T: Into<UniformValue>>(&mut self, name: &str, value: T) {
+        let value = value.into();
+        let uniforms = Arc::make_mut(&mut self.uniforms);
+        match uniforms.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => uniforms.push((name.to_string(), value)),
+        }
+    }
+
+    /// 材质是否透明
+    pub fn is_transparent(&self) -> bool {
+        self.transparent
+    }
+
+    /// 着色器名称
+    pub fn shader(&self) -> &str {
+        &self.shader
+    }
+
+    /// 采样器与纹理名称，按纹理单元顺序排列
+    pub fn textures(&self) -> &[(String, String)] {
+        &self.textures
+    }
+
+    /// uniform 参数
+    pub fn uniforms(&self) -> &[(String, UniformValue)] {
+        &self.uniforms
+    }
+
+    /// 获取 uniform 参数
+    pub fn get_uniform(&self, name: &str) -> Option<UniformValue> {
+        self.uniforms
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| *v)
+    }
+
+    /// 采样器被分配到的纹理单元
+    pub fn texture_unit(&self, sampler: &str) -> Option<u32> {
+        self.textures
+            .iter()
+            .position(|(s, _)| s == sampler)
+            .map(|unit| unit as u32)
+    }
+
+    /// 启用着色器，绑定纹理并设置全部 uniform 参数
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 超出 `MAX_MATERIAL_TEXTURES` 的纹理将被忽略；变换矩阵等逐次绘制的参数
+    /// 可在返回的着色器上继续设置
+    ///
+    /// # 返回值 Returns
+    /// 材质使用的着色器，着色器不存在时返回 `None`
+    pub fn apply(&self) -> Option<Shader> {
+        let shader = SHADER_MANAGER.get(&self.shader)?;
+        shader.use_program();
+        for (unit, (sampler, texture)) in
+            self.textures.iter().take(MAX_MATERIAL_TEXTURES).enumerate()
+        {
+            TEXTURE_MANAGER.bind(texture, unit as u32);
+            shader.set_uniform(sampler, unit as i32);
+        }
+        for (name, value) in self.uniforms.iter() {
+            shader.set_uniform(name, *value);
+        }
+        Some(shader)
+    }
+
+    /// 从 JSON 对象解析材质
+    ///
+    /// # 注解 Note
+    ///
+    /// 格式：
+    /// ```json
+    /// {
+    ///     "shader": "light/blinn_phong",
+    ///     "textures": { "diffuseMap": "container2", "specularMap": "container2_specular" },
+    ///     "uniforms": { "shininess": 32.0, "tint": [1.0, 0.9, 0.8], "mode": { "int": 1 } },
+    ///     "transparent": false
+    /// }
+    /// ```
+    /// 纹理单元按 `textures` 中的书写顺序分配；uniform 的数值为 `float`，
+    /// 长度为 2、3、4 的数组为向量，长度为 16 的数组为按行排列的 4x4 矩阵，
+    /// `{ "int": n }` 为 `int`
+    pub fn from_json(json: &JsonValue) -> Result<Self, String> {
+        if !json.is_object() {
+            return Err("JSON 不是对象".to_string());
+        }
+        let shader = match json["shader"].as_str() {
+            Some(shader) => shader,
+            None => return Err("JSON 中缺少 shader 字段".to_string()),
+        };
+        let mut material = Material::new(shader);
+        match &json["transparent"] {
+            JsonValue::Null => {}
+            value => {
+                material.transparent = value
+                    .as_bool()
+                    .ok_or_else(|| "transparent 字段不是布尔值".to_string())?;
+            }
+        }
+        if !json["textures"].is_null() {
+            if !json["textures"].is_object() {
+                return Err("textures 字段不是对象".to_string());
+            }
+            for (sampler, texture) in json["textures"].entries() {
+                match texture.as_str() {
+                    Some(texture) => material.set_texture(sampler, texture),
+                    None => return Err(format!("纹理 {} 不是字符串", sampler)),
+                }
+            }
+        }
+        if !json["uniforms"].is_null() {
+            if !json["uniforms"].is_object() {
+                return Err("uniforms 字段不是对象".to_string());
+            }
+            for (name, value) in json["uniforms"].entries() {
+                let value =
+                    parse_uniform(value).ok_or_else(|| format!("uniform {} 不是有效的值", name))?;
+                material.set_uniform(name, value);
+            }
+        }
+        Ok(material)
+    }
+
+    /// 转换为 JSON 对象，格式参见 `from_json`
+    pub fn to_json(&self) -> JsonValue {
+        let mut textures = JsonValue::new_object();
+        for (sampler, texture) in self.textures.iter() {
+            textures[sampler.as_str()] = texture.as_str().into();
+        }
+        let mut uniforms = JsonValue::new_object();
+        for (name, value) in self.uniforms.iter() {
+            uniforms[name.as_str()] = uniform_to_json(value);
+        }
+        object! {
+            shader: self.shader(),
+            textures: textures,
+            uniforms: uniforms,
+            transparent: self.transparent,
+        }
+    }
+}
+
+/// 将 uniform 值转换为 `parse_uniform` 可解析的 JSON 值
+fn uniform_to_json(value: &UniformValue) -> JsonValue {
+    match value {
+        UniformValue::Int(v) => object! { int: *v },
+        UniformValue::Float(v) => (*v).into(),
+        UniformValue::Vec2(v) => vec![*v.x(), *v.y()].into(),
+        UniformValue::Vec3(v) => vec![*v.x(), *v.y(), *v.z()].into(),
+        UniformValue::Vec4(v) => vec![*v.x(), *v.y(), *v.z(), *v.w()].into(),
+        UniformValue::Mat4(m) => (0..16).map(|i| m[i / 4][i % 4]).collect::<Vec<_>>().into(),
+    }
+}
+
+/// 解析 JSON 中的 uniform 值
+fn parse_uniform(json: &JsonValue) -> Option<UniformValue> {
+    if let Some(v) = json.as_f32() {
+        return Some(UniformValue::Float(v));
+    }
+    if json.is_object() {
+        return json["int"].as_i32().map(UniformValue::Int);
+    }
+    if !json.is_array() {
+        return None;
+    }
+    let values = json
+        .members()
+        .map(|v| v.as_f32())
+        .collect::<Option<Vec<_>>>()?;
+    match values.len() {
+        2 => Some([values[0], values[1]].into()),
+        3 => Some([values[0], values[1], values[2]].into()),
+        4 => Some([values[0], values[1], values[2], values[3]].into()),
+        16 => {
+            let mut rows = [[0.0; 4]; 4];
+            for (i, row) in rows.iter_mut().enumerate() {
+                row.copy_from_slice(&values[i * 4..i * 4 + 4]);
+            }
+            Some(Mat4::from(rows).into())
+        }
+        _ => None,
+    }
+}
+
+/// 材质管理器
+///
+/// 材质存储于资源系统 `ASSETS` 中，并依赖其引用的着色器与纹理，管理器仅持有其句柄
+pub struct MaterialManager {
+    materials: HashMap<String, Handle<Material>>,
+}
+
+impl MaterialManager {
+    fn new() -> Self {
+        Self {
+            materials: HashMap::new(),
+        }
+    }
+}
+
+lazy_static! {
+    /// 材质管理器
+    pub static ref MATERIAL_MANAGER: RustCraftWrapper<MaterialManager> =
+        RustCraftWrapper::new(MaterialManager::new());
+    static ref NOT_FOUND_MATERIAL: RustCraftWrapper<HashSet<String>> =
+        RustCraftWrapper::new(HashSet::new());
+}
+
+impl RustCraftWrapper<MaterialManager> {
+    /// 以指定名称登记材质，同名材质将被替换
+    ///
+    /// # 注解 Note
+    ///
+    /// 材质依赖登记时已存在的着色器与纹理，材质存在期间这些资源不会被卸载
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 材质名称
+    /// * `material` - 材质
+    ///
+    /// # 返回值 Returns
+    /// 材质的资源句柄
+    pub fn add(&self, name: &str, material: Material) -> Handle<Material> {
+        let mut dependencies = vec![];
+        if let Some(shader) = SHADER_MANAGER.handle(material.shader()) {
+            dependencies.push(shader.untyped());
+        }
+        for (_, texture) in material.textures() {
+            if let Some(texture) = TEXTURE_MANAGER.handle(texture) {
+                dependencies.push(texture.untyped());
+            }
+        }
+        let handle = ASSETS.insert(name, material);
+        for dependency in dependencies {
+            ASSETS.add_dependency(&handle, dependency);
+        }
+        self.apply(|mm| {
+            mm.materials.insert(name.to_string(), handle.clone());
+        });
+        NOT_FOUND_MATERIAL.apply(|set| {
+            set.remove(name);
+        });
+        handle
+    }
+
+    /// 从 JSON 文件中载入材质
+    ///
+    /// # 注解 Note
+    ///
+    /// 格式参见 `Material::from_json`，材质名称为 `name` 字段，缺省时为文件名(不含扩展名)；
+    /// 引用的着色器与纹理应当先于材质载入
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 材质文件路径
+    pub fn load_from_file(&self, path: &str) {
+        debug!("RCW<MaterialManager>", "尝试载入材质 {}", path);
+        let result = VFS.read_to_string(path).and_then(|text| {
+            let json = json::parse(&text).map_err(|e| format!("JSON 解析错误: {}", e))?;
+            let material = Material::from_json(&json)?;
+            Ok((json["name"].as_str().map(str::to_string), material))
+        });
+        match result {
+            Ok((name, material)) => {
+                let name = name.unwrap_or_else(|| {
+                    let file = path.rsplit('/').next().unwrap_or(path);
+                    file.split('.').next().unwrap_or(file).to_string()
+                });
+                self.add(&name, material);
+            }
+            Err(e) => {
+                warn!("RCW<MaterialManager>", "载入材质 {} 失败: {}", path, e);
+            }
+        }
+    }
+
+    /// 获取指定名称材质的资源句柄
+    pub fn handle(&self, name: &str) -> Option<Handle<Material>> {
+        let mut ret = None;
+        self.apply(|mm| ret = mm.materials.get(name).cloned());
+        ret
+    }
+
+    /// 卸载指定名称的材质
+    ///
+    /// # 注解 Note
+    ///
+    /// 管理器释放其持有的句柄，材质在其他句柄与依赖也释放后由 `ASSETS.collect_garbage` 卸载
+    ///
+    /// # 返回值 Returns
+    /// 管理器中存在该名称时返回 `true`
+    pub fn unload(&self, name: &str) -> bool {
+        let mut ret = false;
+        self.apply(|mm| ret = mm.materials.remove(name).is_some());
+        ret
+    }
+
+    /// 获取指定名称材质的副本，副本与管理器中的材质共享数据
+    pub fn get(&self, name: &str) -> Option<Material> {
+        let handle = self.handle(name)?;
+        ASSETS.with(&handle, |material: &Material| material.clone())
+    }
+
+    /// 修改指定名称的材质
+    ///
+    /// # 注解 Note
+    ///
+    /// 修改期间资源系统处于锁定状态，不应在函数内访问其他管理器
+    pub fn update<F: FnOnce(&mut Material)>(&self, name: &str, f: F) {
+        match self.handle(name) {
+            Some(handle) => {
+                ASSETS.with_mut(&handle, f);
+            }
+            None => {
+                warn!("RCW<MaterialManager>", "没有名为 {} 的材质", name);
+            }
+        }
+    }
+
+    /// 应用指定名称的材质，参见 `Material::apply`
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 返回值 Returns
+    /// 材质使用的着色器，材质或着色器不存在时返回 `None`
+    pub fn apply_material(&self, name: &str) -> Option<Shader> {
+        match self.get(name) {
+            Some(material) => material.apply(),
+            None => {
+                NOT_FOUND_MATERIAL.apply(|set| {
+                    if set.insert(name.to_string()) {
+                        warn!("RCW<MaterialManager>", "找不到材质 {}", name);
+                    }
+                });
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let mut material = Material::new("shader")
+            .texture("a", "tex_a")
+            .texture("b", "tex_b")
+            .uniform("shininess", 8.0);
+        material.set_texture("a", "tex_c");
+        material.set_uniform("shininess", 16.0);
+        assert_eq!(material.texture_unit("a"), Some(0));
+        assert_eq!(material.texture_unit("b"), Some(1));
+        assert_eq!(material.textures()[0].1, "tex_c");
+        assert_eq!(
+            material.get_uniform("shininess"),
+            Some(UniformValue::Float(16.0))
+        );
+
+        // 副本共享数据，修改时才复制
+        let mut copy = material.clone();
+        assert!(Arc::ptr_eq(&copy.textures, &material.textures));
+        copy.set_uniform("shininess", 4.0);
+        assert!(Arc::ptr_eq(&copy.textures, &material.textures));
+        assert!(!Arc::ptr_eq(&copy.uniforms, &material.uniforms));
+        assert_eq!(
+            material.get_uniform("shininess"),
+            Some(UniformValue::Float(16.0))
+        );
+    }
+
+    #[test]
+    fn test_from_json() {
+        let json = json::parse(
+            r#"{
+                "shader": "light/blinn_phong",
+                "textures": { "specularMap": "spec", "diffuseMap": "diff" },
+                "transparent": true,
+                "uniforms": {
+                    "shininess": 32,
+                    "tint": [1.0, 0.5, 0.25],
+                    "mode": { "int": 2 },
+                    "offset": [1, 0, 0, 3, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]
+                }
+            }"#,
+        )
+        .unwrap();
+        let material = Material::from_json(&json).unwrap();
+        assert_eq!(material.shader(), "light/blinn_phong");
+        assert!(material.is_transparent());
+        assert_eq!(material.texture_unit("specularMap"), Some(0));
+        assert_eq!(material.texture_unit("diffuseMap"), Some(1));
+        assert_eq!(
+            material.get_uniform("shininess"),
+            Some(UniformValue::Float(32.0))
+        );
+        assert_eq!(material.get_uniform("tint"), Some([1.0, 0.5, 0.25].into()));
+        assert_eq!(material.get_uniform("mode"), Some(UniformValue::Int(2)));
+        match material.get_uniform("offset") {
+            Some(UniformValue::Mat4(m)) => assert_eq!(m[0][3], 3.0),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert_eq!(Material::from_json(&material.to_json()), Ok(material));
+
+        let json =
+            json::parse(r#"{ "shader": "s", "uniforms": { "bad": [1, 2, 3, 4, 5] } }"#).unwrap();
+        assert!(Material::from_json(&json).is_err());
+        assert!(Material::from_json(&json::parse("{}").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_unload() {
+        let handle = MATERIAL_MANAGER.add("test/unload", Material::new("shader"));
+        assert!(MATERIAL_MANAGER.unload("test/unload"));
+        assert!(!MATERIAL_MANAGER.unload("test/unload"));
+        assert!(MATERIAL_MANAGER.handle("test/unload").is_none());
+        // 用户仍持有句柄，材质不会被卸载
+        ASSETS.collect_garbage();
+        assert!(ASSETS.with(&handle, |_| ()).is_some());
+        drop(handle);
+        ASSETS.collect_garbage();
+        assert!(ASSETS.find::<Material>("test/unload").is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::gl_utils;
+use crate::{gl_utils, GL_STATE};
 use gl::types::*;
 use json::JsonValue;
 
@@ -28,8 +28,8 @@ impl ArrayModel {
 
 impl Model for ArrayModel {
     fn draw(&self) {
+        GL_STATE.bind_vertex_array(self.vao);
         unsafe {
-            gl::BindVertexArray(self.vao);
             gl::DrawArrays(gl::TRIANGLES, 0, (self.vertices.len() / 3) as i32);
         }
     }
@@ -37,8 +37,8 @@ impl Model for ArrayModel {
 
 impl Drop for ArrayModel {
     fn drop(&mut self) {
+        GL_STATE.delete_vertex_array(self.vao);
         unsafe {
-            gl::DeleteVertexArrays(1, &self.vao);
             gl::DeleteBuffers(1, &self.vbo);
         }
     }
@@ -1,4 +1,4 @@
-use crate::gl_utils;
+use crate::{gl_utils, GL_STATE};
 use gl::types::*;
 use json::JsonValue;
 
@@ -34,8 +34,8 @@ impl ElementModel {
 
 impl Model for ElementModel {
     fn draw(&self) {
+        GL_STATE.bind_vertex_array(self.vao);
         unsafe {
-            gl::BindVertexArray(self.vao);
             gl::DrawElements(
                 gl::TRIANGLES,
                 self.indices.len() as i32,
@@ -48,8 +48,8 @@ impl Model for ElementModel {
 
 impl Drop for ElementModel {
     fn drop(&mut self) {
+        GL_STATE.delete_vertex_array(self.vao);
         unsafe {
-            gl::DeleteVertexArrays(1, &self.vao);
             gl::DeleteBuffers(1, &self.vbo);
             gl::DeleteBuffers(1, &self.ebo);
         }
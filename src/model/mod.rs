@@ -12,29 +12,72 @@ pub trait Model {
     fn draw(&self);
 }
 
+/// 网格资源
+///
+/// 对模型的包装，由资源系统 `ASSETS` 统一管理
+pub struct Mesh {
+    model: Box<dyn Model + Send + 'static>,
+}
+
+impl Mesh {
+    pub fn new(model: Box<dyn Model + Send + 'static>) -> Self {
+        Self { model }
+    }
+
+    /// 渲染网格
+    pub fn draw(&self) {
+        self.model.draw();
+    }
+}
+
+/// 模型管理器
+///
+/// 模型数据存储于资源系统 `ASSETS` 中，管理器仅持有其句柄
 pub struct ModelManager {
-    models: HashMap<String, Box<dyn Model + Send + 'static>>,
+    models: HashMap<String, Handle<Mesh>>,
+    /// 模型的默认材质名称
+    materials: HashMap<String, String>,
 }
 
 impl ModelManager {
     pub fn new() -> Self {
         Self {
             models: HashMap::new(),
+            materials: HashMap::new(),
         }
     }
 
     pub fn add(&mut self, name: &str, model: Box<dyn Model + Send + 'static>) {
-        self.models.insert(String::from(name), model);
+        let handle = ASSETS.insert(name, Mesh::new(model));
+        self.models.insert(String::from(name), handle);
     }
 
-    pub fn get(&self, name: &str) -> Option<&Box<dyn Model + Send + 'static>> {
+    /// 获取指定名称模型的资源句柄
+    pub fn get(&self, name: &str) -> Option<&Handle<Mesh>> {
         self.models.get(name)
     }
+
+    /// 设置模型的默认材质，`None` 表示不使用材质
+    pub fn set_material(&mut self, name: &str, material: Option<&str>) {
+        match material {
+            Some(material) => {
+                self.materials
+                    .insert(name.to_string(), material.to_string());
+            }
+            None => {
+                self.materials.remove(name);
+            }
+        }
+    }
 }
 
 use lazy_static::lazy_static;
 
-use crate::{debug, warn, RustCraftWrapper};
+use crate::{
+    assets::loader::{UploadTask, ASSET_LOADER},
+    debug, gl_utils, warn, Handle, RustCraftWrapper, UniformValue, ASSETS, GL_STATE,
+    MATERIAL_MANAGER,
+};
 
 lazy_static! {
     /// 模型管理器
@@ -93,6 +136,8 @@ impl RustCraftWrapper<ModelManager> {
     /// ```
     /// 其中： vertices 为顶点数据，indices 为索引数据，description 为顶点数据描述
     ///
+    /// 两种格式均可包含可选的 `"material": "..."` 字段，指定模型的默认材质，参见 `set_material`
+    ///
     /// 顶点数据结果描述的格式为： `[<num><type>;...]`
     /// - num: 值个数
     /// - type: 值类型
@@ -117,14 +162,19 @@ impl RustCraftWrapper<ModelManager> {
         };
         match ext {
             "json" => {
-                let (name, model) = match model_file::load_from_json(path) {
-                    Ok(result) => result,
+                let source = match model_file::parse_json(path) {
+                    Ok(source) => source,
                     Err(err) => {
                         warn!("RCW<ModelManager>", "载入模型 {} 失败: {}", path, err);
                         return;
                     }
                 };
+                let material = source.material().map(str::to_string);
+                let (name, model) = source.build();
                 self.add_model(&name, model);
+                if let Some(material) = material {
+                    self.set_material(&name, Some(&material));
+                }
             }
             _ => {
                 warn!(
@@ -136,6 +186,138 @@ impl RustCraftWrapper<ModelManager> {
         }
     }
 
+    /// 在后台线程中解析模型文件，并在渲染线程中分批上传
+    ///
+    /// # 参数 Parameters
+    ///
+    /// - `path`: 模型文件路径
+    ///
+    /// # 注解 Note
+    ///
+    /// 文件格式与 `load_from_file` 一致，加载进度可通过 `ASSET_LOADER.progress()` 查询
+    pub fn load_from_file_async(&self, path: &str) {
+        debug!("RCW<ModelManager>", "尝试异步载入模型 {}", path);
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("json") => {}
+            Some(ext) => {
+                warn!(
+                    "RCW<ModelManager>",
+                    "不支持的文件类型: {}, 文件: {}", ext, path
+                );
+                return;
+            }
+            None => {
+                warn!("RCW<ModelManager>", "无法确定文件类型: {}", path);
+                return;
+            }
+        }
+        let path = path.to_string();
+        ASSET_LOADER.submit(move || -> UploadTask {
+            match model_file::parse_json(&path) {
+                Ok(source) => Box::new(move || {
+                    let material = source.material().map(str::to_string);
+                    let (name, model) = source.build();
+                    MODEL_MANAGER.add_model(&name, model);
+                    if let Some(material) = material {
+                        MODEL_MANAGER.set_material(&name, Some(&material));
+                    }
+                }),
+                Err(err) => Box::new(move || {
+                    warn!("RCW<ModelManager>", "载入模型 {} 失败: {}", path, err);
+                }),
+            }
+        });
+    }
+
+    /// 获取指定名称模型的资源句柄
+    ///
+    /// # 参数 Parameters
+    ///
+    /// - `name`: 模型名称
+    pub fn handle(&self, name: &str) -> Option<Handle<Mesh>> {
+        let mut ret = None;
+        self.apply(|manager| ret = manager.get(name).cloned());
+        ret
+    }
+
+    /// 卸载指定名称的模型
+    ///
+    /// # 注解 Note
+    ///
+    /// 管理器释放其持有的句柄，模型在其他句柄与依赖也释放后由 `ASSETS.collect_garbage` 卸载
+    ///
+    /// # 返回值 Returns
+    /// 管理器中存在该名称时返回 `true`
+    ///
+    /// 模型的默认材质设置一并移除
+    pub fn unload(&self, name: &str) -> bool {
+        let mut ret = false;
+        self.apply(|manager| {
+            ret = manager.models.remove(name).is_some();
+            manager.materials.remove(name);
+        });
+        ret
+    }
+
+    /// 设置模型的默认材质
+    ///
+    /// # 参数 Parameters
+    ///
+    /// - `name`: 模型名称
+    /// - `material`: `MATERIAL_MANAGER` 中的材质名称，`None` 表示不使用材质
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// MATERIAL_MANAGER.load_from_file("material/crate.json");
+    /// MODEL_MANAGER.set_material("cube", Some("crate"));
+    /// MODEL_MANAGER.draw_model_with("cube", &[("model", model.into())]);
+    /// ```
+    pub fn set_material(&self, name: &str, material: Option<&str>) {
+        self.apply(|manager| manager.set_material(name, material));
+    }
+
+    /// 获取模型的默认材质名称
+    pub fn material(&self, name: &str) -> Option<String> {
+        let mut ret = None;
+        self.apply(|manager| ret = manager.materials.get(name).cloned());
+        ret
+    }
+
+    /// 查找模型的资源句柄与默认材质，找不到时输出一次警告
+    fn find(&self, name: &str) -> Option<(Handle<Mesh>, Option<String>)> {
+        let mut found = None;
+        self.apply(|manager| {
+            found = manager
+                .get(name)
+                .map(|handle| (handle.clone(), manager.materials.get(name).cloned()));
+        });
+        if found.is_none() {
+            NOT_FOUND_MODEL.apply(|set| {
+                if set.contains(name) {
+                    return;
+                }
+                warn!("RCW<ModelManager>", "找不到模型 {}", name);
+                set.insert(String::from(name));
+            });
+        }
+        found
+    }
+
+    /// 仅渲染模型的几何体，不应用默认材质
+    ///
+    /// # 参数 Parameters
+    ///
+    /// - `name`: 模型名称
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，适用于阴影等由调用者启用着色器的绘制
+    pub fn draw_geometry(&self, name: &str) {
+        if let Some((handle, _)) = self.find(name) {
+            ASSETS.with(&handle, |mesh| mesh.draw());
+        }
+    }
+
     /// 渲染参数所指定的模型
     ///
     /// # 参数 Parameters
@@ -145,19 +327,38 @@ impl RustCraftWrapper<ModelManager> {
     /// # 注解 Note
     ///
     /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 模型设置了默认材质时，绘制前先应用该材质
     pub fn draw_model(&self, name: &str) {
-        self.apply(|manager| {
-            if let Some(model) = manager.get(name) {
-                model.draw();
-            } else {
-                NOT_FOUND_MODEL.apply(|set| {
-                    if set.contains(name) {
-                        return;
-                    }
-                    warn!("RCW<ModelManager>", "找不到模型 {}", name);
-                    set.insert(String::from(name));
-                });
+        self.draw_model_with(name, &[]);
+    }
+
+    /// 应用默认材质并设置额外的 uniform 参数后渲染模型
+    ///
+    /// # 参数 Parameters
+    ///
+    /// - `name`: 模型名称
+    /// - `uniforms`: 在材质参数之后设置的 uniform 参数，例如模型矩阵
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 模型没有默认材质时，额外的参数被设置到当前启用的着色器
+    pub fn draw_model_with(&self, name: &str, uniforms: &[(&str, UniformValue)]) {
+        let (handle, material) = match self.find(name) {
+            Some(found) => found,
+            None => return,
+        };
+        let shader = material.and_then(|material| MATERIAL_MANAGER.apply_material(&material));
+        for (uniform, value) in uniforms {
+            match &shader {
+                Some(shader) => shader.set_uniform(uniform, *value),
+                None => unsafe {
+                    let _ = gl_utils::set_uniform(GL_STATE.program(), uniform, *value);
+                },
             }
-        });
+        }
+        ASSETS.with(&handle, |mesh| mesh.draw());
     }
 }
@@ -1,8 +1,63 @@
 use super::{array_model::ArrayModel, element_model::ElementModel, Model};
+use crate::vfs::VFS;
 
-/// 从 JSON 文件中加载模型
-pub fn load_from_json(path: &str) -> Result<(String, Box<dyn Model + Send + 'static>), String> {
-    let string = match std::fs::read_to_string(path) {
+/// 已解析、尚未上传的模型数据
+pub enum ModelSource {
+    Array {
+        name: String,
+        vertices: Vec<f32>,
+        description: String,
+        material: Option<String>,
+    },
+    Element {
+        name: String,
+        vertices: Vec<f32>,
+        indices: Vec<u32>,
+        description: String,
+        material: Option<String>,
+    },
+}
+
+impl ModelSource {
+    /// 模型的默认材质名称
+    pub fn material(&self) -> Option<&str> {
+        match self {
+            ModelSource::Array { material, .. } | ModelSource::Element { material, .. } => {
+                material.as_deref()
+            }
+        }
+    }
+
+    /// 创建 OpenGL 模型对象
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn build(self) -> (String, Box<dyn Model + Send + 'static>) {
+        match self {
+            ModelSource::Array {
+                name,
+                vertices,
+                description,
+                ..
+            } => (name, Box::new(ArrayModel::new(vertices, &description))),
+            ModelSource::Element {
+                name,
+                vertices,
+                indices,
+                description,
+                ..
+            } => (
+                name,
+                Box::new(ElementModel::new(vertices, indices, &description)),
+            ),
+        }
+    }
+}
+
+/// 解析 JSON 模型文件，不调用 OpenGL
+pub fn parse_json(path: &str) -> Result<ModelSource, String> {
+    let string = match VFS.read_to_string(path) {
         Ok(string) => string,
         Err(err) => return Err(format!("读取文件时错误: {}", err)),
     };
@@ -21,17 +76,26 @@ pub fn load_from_json(path: &str) -> Result<(String, Box<dyn Model + Send + 'sta
     } else {
         return Err("JSON 中 type 字段不是有效内容".to_string());
     };
+    let material = json["material"].as_str().map(str::to_string);
     match model_type {
         "element" => {
             let (name, vertices, indices, description) = ElementModel::load_from_json(&json)?;
-            Ok((
+            Ok(ModelSource::Element {
                 name,
-                Box::new(ElementModel::new(vertices, indices, &description)),
-            ))
+                vertices,
+                indices,
+                description,
+                material,
+            })
         }
         "array" => {
             let (name, vertices, description) = ArrayModel::load_from_json(&json)?;
-            Ok((name, Box::new(ArrayModel::new(vertices, &description))))
+            Ok(ModelSource::Array {
+                name,
+                vertices,
+                description,
+                material,
+            })
         }
         _ => Err(format!("无效的模型类型格式: {}", model_type)),
     }
@@ -0,0 +1,29 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform vec2 texelSize;
+// 模糊方向，水平为 (1, 0)，垂直为 (0, 1)
+uniform vec2 direction;
+// 高斯函数的标准差(以像素计)
+uniform float sigma;
+
+void main(){
+    vec4 sum = texture(screenTexture, TexCoords);
+    if (sigma <= 0.0) {
+        FragColor = sum;
+        return;
+    }
+    int radius = min(int(ceil(sigma * 3.0)), 32);
+    float weightSum = 1.0;
+    for (int i = 1; i <= radius; ++i) {
+        float weight = exp(-float(i * i) / (2.0 * sigma * sigma));
+        vec2 offset = direction * texelSize * float(i);
+        sum += (texture(screenTexture, TexCoords + offset) + texture(screenTexture, TexCoords - offset)) * weight;
+        weightSum += 2.0 * weight;
+    }
+    FragColor = sum / weightSum;
+}
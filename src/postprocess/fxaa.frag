@@ -0,0 +1,38 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform vec2 texelSize;
+
+const float FXAA_SPAN_MAX = 8.0;
+const float FXAA_REDUCE_MUL = 1.0 / 8.0;
+const float FXAA_REDUCE_MIN = 1.0 / 128.0;
+
+void main(){
+    vec3 lumaWeight = vec3(0.299, 0.587, 0.114);
+    float lumaNW = dot(texture(screenTexture, TexCoords + vec2(-1.0, -1.0) * texelSize).rgb, lumaWeight);
+    float lumaNE = dot(texture(screenTexture, TexCoords + vec2(1.0, -1.0) * texelSize).rgb, lumaWeight);
+    float lumaSW = dot(texture(screenTexture, TexCoords + vec2(-1.0, 1.0) * texelSize).rgb, lumaWeight);
+    float lumaSE = dot(texture(screenTexture, TexCoords + vec2(1.0, 1.0) * texelSize).rgb, lumaWeight);
+    vec4 center = texture(screenTexture, TexCoords);
+    float lumaM = dot(center.rgb, lumaWeight);
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    // 沿垂直于亮度梯度的方向(即边缘方向)采样
+    vec2 dir = vec2(-((lumaNW + lumaNE) - (lumaSW + lumaSE)), (lumaNW + lumaSW) - (lumaNE + lumaSE));
+    float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * (0.25 * FXAA_REDUCE_MUL), FXAA_REDUCE_MIN);
+    float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * rcpDirMin, vec2(-FXAA_SPAN_MAX), vec2(FXAA_SPAN_MAX)) * texelSize;
+
+    vec3 rgbA = 0.5 * (texture(screenTexture, TexCoords + dir * (1.0 / 3.0 - 0.5)).rgb
+        + texture(screenTexture, TexCoords + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 rgbB = rgbA * 0.5 + 0.25 * (texture(screenTexture, TexCoords - dir * 0.5).rgb
+        + texture(screenTexture, TexCoords + dir * 0.5).rgb);
+    float lumaB = dot(rgbB, lumaWeight);
+    // 四点采样超出局部亮度范围时说明跨越了边缘，退回两点采样
+    FragColor = vec4((lumaB < lumaMin || lumaB > lumaMax) ? rgbA : rgbB, center.a);
+}
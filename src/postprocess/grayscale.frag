@@ -0,0 +1,15 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+// 灰度化程度，0.0 为原色，1.0 为完全灰度
+uniform float amount;
+
+void main(){
+    vec4 color = texture(screenTexture, TexCoords);
+    float luma = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+    FragColor = vec4(mix(color.rgb, vec3(luma), amount), color.a);
+}
@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+
+use gl::types::*;
+use lazy_static::lazy_static;
+use mats::Vec2;
+
+use crate::{
+    gl_state::Capability,
+    gl_utils,
+    texture::{DepthFormat, PixelFormat},
+    warn, App, Framebuffer, FramebufferBuilder, RustCraftWrapper, Shader, UniformValue, GL_STATE,
+    SHADER_MANAGER,
+};
+
+const VERTEX_SHADER: &str = include_str!("fullscreen.vert");
+
+lazy_static! {
+    /// 已警告过因缺少着色器而跳过的后处理阶段
+    static ref SKIPPED_PASS: RustCraftWrapper<HashSet<String>> =
+        RustCraftWrapper::new(HashSet::new());
+}
+
+/// 内置后处理着色器的名称与片段着色器源码
+pub(crate) const BUILTIN_SHADERS: [(&str, &str); 6] = [
+    ("postprocess/copy", include_str!("copy.frag")),
+    ("postprocess/grayscale", include_str!("grayscale.frag")),
+    ("postprocess/gamma", include_str!("gamma.frag")),
+    ("postprocess/fxaa", include_str!("fxaa.frag")),
+    ("postprocess/vignette", include_str!("vignette.frag")),
+    ("postprocess/blur", include_str!("blur.frag")),
+];
+
+/// 全屏三角形的顶点着色器源码，自定义后处理着色器可与之配对
+///
+/// 顶点着色器输出 `vec2 TexCoords`，片段着色器中可使用以下 uniform：
+/// + `sampler2D screenTexture` - 上一个阶段的输出(纹理单元 0)
+/// + `sampler2D sceneTexture` - 未经后处理的场景(纹理单元 1)
+/// + `vec2 texelSize` - 单个像素的纹理坐标尺寸
+pub fn fullscreen_vertex_shader() -> &'static str {
+    VERTEX_SHADER
+}
+
+/// 以全屏三角形顶点着色器编译并登记尚未登记的着色器
+///
+/// # 参数 Parameters
+/// * `shaders` - 着色器名称与片段着色器源码
+pub(crate) fn register_shaders(shaders: &[(&str, &str)]) -> Result<(), String> {
+    for (shader, frag) in shaders {
+        if SHADER_MANAGER.handle(shader).is_none() {
+            SHADER_MANAGER.add_from_source(shader, VERTEX_SHADER, frag);
+            if SHADER_MANAGER.handle(shader).is_none() {
+                return Err(format!("内置着色器 {} 加载失败", shader));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 后处理阶段，由 `SHADER_MANAGER` 中的着色器与一组 uniform 组成
+///
+/// # 示例 Examples
+/// ```ignore
+/// SHADER_MANAGER.add_from_source("sepia", postprocess::fullscreen_vertex_shader(), SEPIA_FRAG);
+/// chain.push(PostProcessPass::new("sepia", "sepia").uniform("amount", 0.8));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PostProcessPass {
+    label: String,
+    shader: String,
+    uniforms: Vec<(String, UniformValue)>,
+    enabled: bool,
+}
+
+impl PostProcessPass {
+    /// 创建后处理阶段
+    ///
+    /// # 参数 Parameters
+    /// * `label` - 阶段名称，用于在处理链中查找
+    /// * `shader` - `SHADER_MANAGER` 中的着色器名称
+    pub fn new(label: &str, shader: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            shader: shader.to_string(),
+            uniforms: vec![],
+            enabled: true,
+        }
+    }
+
+    /// 添加 uniform 变量
+    pub fn uniform<T: Into<UniformValue>>(mut self, name: &str, value: T) -> Self {
+        self.set_uniform(name, value);
+        self
+    }
+
+    /// 设置 uniform 变量，已存在同名变量时覆盖
+    pub fn set_uniform<T: Into<UniformValue>>(&mut self, name: &str, value: T) {
+        let value = value.into();
+        match self.uniforms.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => self.uniforms.push((name.to_string(), value)),
+        }
+    }
+
+    /// 查询 uniform 变量的值
+    pub fn get_uniform(&self, name: &str) -> Option<UniformValue> {
+        self.uniforms
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| *v)
+    }
+
+    /// 阶段名称
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// 是否启用
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 启用或停用此阶段，停用的阶段将被跳过
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// 灰度化
+    pub fn grayscale() -> Self {
+        Self::new("grayscale", "postprocess/grayscale").uniform("amount", 1.0)
+    }
+
+    /// 伽马校正
+    ///
+    /// # 参数 Parameters
+    /// * `gamma` - 伽马值，通常为 `2.2`
+    pub fn gamma(gamma: f32) -> Self {
+        Self::new("gamma", "postprocess/gamma").uniform("gamma", gamma)
+    }
+
+    /// 快速近似抗锯齿(FXAA)
+    pub fn fxaa() -> Self {
+        Self::new("fxaa", "postprocess/fxaa")
+    }
+
+    /// 暗角
+    ///
+    /// # 参数 Parameters
+    /// * `strength` - 暗角强度，`0.0` 为无暗角，`1.0` 为边缘全黑
+    pub fn vignette(strength: f32) -> Self {
+        Self::new("vignette", "postprocess/vignette")
+            .uniform("strength", strength)
+            .uniform("radius", 0.75)
+            .uniform("softness", 0.45)
+    }
+
+    /// 单方向的高斯模糊，通常水平与垂直方向各使用一次，参见 `PostProcessChain::push_blur`
+    ///
+    /// # 参数 Parameters
+    /// * `label` - 阶段名称
+    /// * `direction` - 模糊方向，水平为 `[1.0, 0.0]`，垂直为 `[0.0, 1.0]`
+    /// * `sigma` - 高斯函数的标准差(以像素计)，采样半径为其 3 倍且不超过 32 像素
+    pub fn blur(label: &str, direction: [f32; 2], sigma: f32) -> Self {
+        Self::new(label, "postprocess/blur")
+            .uniform("direction", direction)
+            .uniform("sigma", sigma)
+    }
+}
+
+/// 后处理链
+///
+/// 场景首先渲染到离屏帧缓冲，随后依次执行各个后处理阶段，
+/// 阶段之间在两个帧缓冲间交替读写，最后一个阶段直接输出到默认帧缓冲
+///
+/// # 示例 Examples
+/// ```ignore
+/// // 初始化
+/// let mut chain = PostProcessChain::new("post", PixelFormat::Rgba8, 4)?;
+/// chain.push(PostProcessPass::fxaa());
+/// chain.push(PostProcessPass::vignette(0.4));
+/// // 渲染循环
+/// chain.begin();
+/// draw_scene();
+/// chain.end();
+/// ```
+pub struct PostProcessChain {
+    scene: Framebuffer,
+    targets: [Framebuffer; 2],
+    passes: Vec<PostProcessPass>,
+    /// 绘制全屏三角形使用的空顶点数组对象
+    vao: GLuint,
+}
+
+impl PostProcessChain {
+    /// 创建后处理链，并登记内置后处理着色器
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 帧缓冲的附件纹理以 `名称.scene.color0`、`名称.ping.color0`、`名称.pong.color0`
+    /// 的形式登记于 `TEXTURE_MANAGER`
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 后处理链名称
+    /// * `format` - 离屏帧缓冲的颜色格式，高动态范围渲染时使用浮点格式
+    /// * `samples` - 场景帧缓冲的多重采样数，`0` 表示不启用
+    pub fn new(name: &str, format: PixelFormat, samples: u32) -> Result<Self, String> {
+        register_shaders(&BUILTIN_SHADERS)?;
+        let scene = FramebufferBuilder::new(&format!("{}.scene", name))
+            .color(format)
+            .depth(DepthFormat::Depth24Stencil8)
+            .samples(samples)
+            .build()?;
+        let target = |suffix: &str| {
+            FramebufferBuilder::new(&format!("{}.{}", name, suffix))
+                .color(format)
+                .build()
+        };
+        let targets = [target("ping")?, target("pong")?];
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        Ok(Self {
+            scene,
+            targets,
+            passes: vec![],
+            vao,
+        })
+    }
+
+    /// 在末尾添加后处理阶段
+    pub fn push(&mut self, pass: PostProcessPass) {
+        self.passes.push(pass);
+    }
+
+    /// 在末尾添加水平与垂直两个方向的高斯模糊，名称分别为 `blur_h` 与 `blur_v`
+    pub fn push_blur(&mut self, sigma: f32) {
+        self.push(PostProcessPass::blur("blur_h", [1.0, 0.0], sigma));
+        self.push(PostProcessPass::blur("blur_v", [0.0, 1.0], sigma));
+    }
+
+    /// 在指定位置插入后处理阶段
+    pub fn insert(&mut self, index: usize, pass: PostProcessPass) {
+        let index = index.min(self.passes.len());
+        self.passes.insert(index, pass);
+    }
+
+    /// 移除指定名称的后处理阶段
+    pub fn remove(&mut self, label: &str) -> Option<PostProcessPass> {
+        let index = self.passes.iter().position(|p| p.label == label)?;
+        Some(self.passes.remove(index))
+    }
+
+    /// 移除全部后处理阶段
+    pub fn clear(&mut self) {
+        self.passes.clear();
+    }
+
+    /// 获取指定名称的后处理阶段，可用于修改 uniform 或启用状态
+    pub fn pass_mut(&mut self, label: &str) -> Option<&mut PostProcessPass> {
+        self.passes.iter_mut().find(|p| p.label == label)
+    }
+
+    /// 按执行顺序排列的后处理阶段
+    pub fn passes(&self) -> &[PostProcessPass] {
+        &self.passes
+    }
+
+    /// 场景帧缓冲
+    pub fn scene(&self) -> &Framebuffer {
+        &self.scene
+    }
+
+    /// 开始渲染场景，此后的绘制将输出到离屏帧缓冲
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，不会清空帧缓冲
+    pub fn begin(&mut self) {
+        self.scene.bind();
+    }
+
+    /// 结束渲染场景，依次执行各个后处理阶段并输出到默认帧缓冲
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 后处理期间关闭深度测试，结束后恢复；没有启用的阶段时直接复制场景。
+    /// 着色器不存在的阶段将被跳过，不占用中间帧缓冲，每个阶段只警告一次
+    pub fn end(&mut self) {
+        self.scene.unbind();
+        let copy = PostProcessPass::new("copy", "postprocess/copy");
+        let resolve = |pass: &PostProcessPass| -> Option<Shader> {
+            let shader = SHADER_MANAGER.get(&pass.shader);
+            if shader.is_none() {
+                SKIPPED_PASS.apply(|set| {
+                    if set.insert(pass.label.clone()) {
+                        warn!(
+                            "PostProcessChain::end()",
+                            "后处理阶段 {} 的着色器 {} 不存在, 已跳过", pass.label, pass.shader
+                        );
+                    }
+                });
+            }
+            shader
+        };
+        let mut passes = self
+            .passes
+            .iter()
+            .filter(|p| p.enabled)
+            .filter_map(|p| resolve(p).map(|shader| (p, shader)))
+            .collect::<Vec<_>>();
+        if passes.is_empty() {
+            passes.extend(resolve(&copy).map(|shader| (&copy, shader)));
+        }
+        let depth_test = GL_STATE.is_enabled(Capability::DepthTest);
+        GL_STATE.disable(Capability::DepthTest);
+        GL_STATE.bind_vertex_array(self.vao);
+        self.scene.bind_color(0, 1);
+        let count = passes.len();
+        for (i, (pass, shader)) in passes.into_iter().enumerate() {
+            // 第一个阶段读取场景，此后读取上一个阶段写入的帧缓冲
+            match i {
+                0 => self.scene.bind_color(0, 0),
+                _ => self.targets[(i - 1) % 2].bind_color(0, 0),
+            }
+            let size = if i + 1 == count {
+                let (w, h) = App::window_size();
+                unsafe {
+                    gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+                }
+                GL_STATE.set_viewport([0, 0, w, h]);
+                (w.max(1) as u32, h.max(1) as u32)
+            } else {
+                let output = &mut self.targets[i % 2];
+                output.bind();
+                output.size()
+            };
+            shader.use_program();
+            shader.set_uniform("screenTexture", 0);
+            // 未被着色器使用的 uniform 会在编译时被移除，此处不输出警告
+            unsafe {
+                let texel = Vec2::from([1.0 / size.0 as f32, 1.0 / size.1 as f32]);
+                let _ = gl_utils::set_uniform(shader.id(), "sceneTexture", 1);
+                let _ = gl_utils::set_uniform(shader.id(), "texelSize", texel);
+            }
+            for (name, value) in pass.uniforms.iter() {
+                shader.set_uniform(name, *value);
+            }
+            unsafe {
+                gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            }
+        }
+        GL_STATE.bind_vertex_array(0);
+        GL_STATE.set_enabled(Capability::DepthTest, depth_test);
+    }
+}
+
+impl Drop for PostProcessChain {
+    fn drop(&mut self) {
+        GL_STATE.delete_vertex_array(self.vao);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pass_uniforms() {
+        let mut pass = PostProcessPass::vignette(0.4);
+        assert_eq!(pass.get_uniform("strength"), Some(UniformValue::Float(0.4)));
+        pass.set_uniform("strength", 0.8);
+        assert_eq!(pass.get_uniform("strength"), Some(UniformValue::Float(0.8)));
+        assert_eq!(pass.uniforms.len(), 3);
+        assert_eq!(pass.get_uniform("missing"), None);
+
+        let blur = PostProcessPass::blur("blur_h", [1.0, 0.0], 2.0);
+        assert_eq!(
+            blur.get_uniform("direction"),
+            Some(UniformValue::Vec2(Vec2::from([1.0, 0.0])))
+        );
+    }
+}
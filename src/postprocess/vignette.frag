@@ -0,0 +1,20 @@
+#version 330 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+// 暗角强度，0.0 为无暗角
+uniform float strength;
+// 暗角开始的位置(到屏幕中心的距离)
+uniform float radius;
+// 暗角的过渡宽度
+uniform float softness;
+
+void main(){
+    vec4 color = texture(screenTexture, TexCoords);
+    float dist = distance(TexCoords, vec2(0.5));
+    float vignette = smoothstep(radius, radius - softness, dist);
+    FragColor = vec4(color.rgb * mix(1.0, vignette, strength), color.a);
+}
@@ -0,0 +1,388 @@
+use std::collections::{HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use mats::Mat4;
+
+use crate::{
+    gl_state::{BlendFunc, Capability},
+    light::camera_position,
+    material::MAX_MATERIAL_TEXTURES,
+    warn, Material, RustCraftWrapper, Shader, GL_STATE, MATERIAL_MANAGER, MODEL_MANAGER,
+    SHADER_MANAGER, TEXTURE_MANAGER,
+};
+
+lazy_static! {
+    static ref MISSING_RESOURCE: RustCraftWrapper<HashSet<String>> =
+        RustCraftWrapper::new(HashSet::new());
+}
+
+/// 对缺失的材质或着色器只警告一次
+fn warn_missing(kind: &str, name: &str) {
+    MISSING_RESOURCE.apply(|set| {
+        if set.insert(format!("{} {}", kind, name)) {
+            warn!("RenderQueue", "找不到{} {}，跳过使用它的绘制", kind, name);
+        }
+    });
+}
+
+/// 渲染队列中的一次绘制
+///
+/// # 示例 Examples
+/// ```
+/// use mats::Mat4;
+/// use rustcraft::render_queue::RenderItem;
+///
+/// let item = RenderItem::new("cube", Mat4::I()).material("wood").sort_key(1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderItem {
+    /// `MODEL_MANAGER` 中的模型名称
+    pub model: String,
+    /// `MATERIAL_MANAGER` 中的材质名称，`None` 表示使用模型的默认材质
+    pub material: Option<String>,
+    /// 模型矩阵
+    pub transform: Mat4<f32>,
+    /// 排序键，键较小的条目先绘制，优先于按状态或深度的排序
+    pub sort_key: i32,
+}
+
+impl RenderItem {
+    /// 以模型的默认材质绘制模型，排序键为 0
+    pub fn new(model: &str, transform: Mat4<f32>) -> Self {
+        Self {
+            model: model.to_string(),
+            material: None,
+            transform,
+            sort_key: 0,
+        }
+    }
+
+    /// 设置材质
+    pub fn material(mut self, material: &str) -> Self {
+        self.material = Some(material.to_string());
+        self
+    }
+
+    /// 设置排序键
+    pub fn sort_key(mut self, sort_key: i32) -> Self {
+        self.sort_key = sort_key;
+        self
+    }
+}
+
+/// 一次提交的统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// 绘制调用次数
+    pub draws: usize,
+    /// 着色器切换次数
+    pub shader_binds: usize,
+    /// 材质切换次数
+    pub material_binds: usize,
+    /// 纹理绑定次数
+    pub texture_binds: usize,
+    /// 不透明条目数
+    pub opaque: usize,
+    /// 透明条目数
+    pub transparent: usize,
+    /// 因材质或着色器缺失而跳过的条目数
+    pub dropped: usize,
+}
+
+/// 排序所需的条目状态
+#[derive(Debug, Clone, PartialEq)]
+struct SortEntry {
+    index: usize,
+    sort_key: i32,
+    transparent: bool,
+    shader: String,
+    texture: String,
+    material: String,
+    /// 到摄像机距离的平方
+    depth: f32,
+}
+
+/// 排序：不透明条目在前，按着色器、首个纹理与材质分组；透明条目在后，由远及近
+fn sort_entries(entries: &mut [SortEntry]) {
+    entries.sort_by(|a, b| {
+        a.transparent
+            .cmp(&b.transparent)
+            .then(a.sort_key.cmp(&b.sort_key))
+            .then_with(|| {
+                if a.transparent {
+                    b.depth.total_cmp(&a.depth)
+                } else {
+                    (&a.shader, &a.texture, &a.material).cmp(&(&b.shader, &b.texture, &b.material))
+                }
+            })
+            .then(a.index.cmp(&b.index))
+    });
+}
+
+/// 渲染队列
+///
+/// 一帧内提交的绘制在 `flush` 时统一排序并提交：不透明条目按着色器、纹理与材质排序以减少状态切换，
+/// 透明条目按到摄像机的距离由远及近绘制；着色器的 `model`、`view` 与 `projection` 参数由队列设置
+///
+/// # 示例 Examples
+/// ```ignore
+/// let mut queue = RenderQueue::new();
+/// for (i, position) in positions.iter().enumerate() {
+///     queue.submit(RenderItem::new("cube", tranlate3(*position)).material("wood"));
+/// }
+/// queue.submit(RenderItem::new("window", model).material("glass"));
+/// let stats = queue.flush(view, projection);
+/// debug!("render", "{} 次绘制, {} 次着色器切换", stats.draws, stats.shader_binds);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RenderQueue {
+    items: Vec<RenderItem>,
+    stats: RenderStats,
+}
+
+impl RenderQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 提交绘制
+    pub fn submit(&mut self, item: RenderItem) {
+        self.items.push(item);
+    }
+
+    /// 队列中的条目数
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// 队列是否为空
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// 丢弃全部条目
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// 上一次 `flush` 的统计
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// 排序并绘制全部条目，之后清空队列
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 没有材质的条目使用当前启用的着色器并最先绘制；绘制透明条目时启用混合并禁止写入深度，
+    /// 结束后恢复原有状态
+    ///
+    /// 材质或着色器缺失的条目被跳过并计入 `dropped`，每个缺失项只警告一次
+    ///
+    /// # 参数 Parameters
+    /// * `view` - 摄像机的视图矩阵
+    /// * `projection` - 摄像机的投影矩阵
+    ///
+    /// # 返回值 Returns
+    /// 本次提交的统计
+    pub fn flush(&mut self, view: Mat4<f32>, projection: Mat4<f32>) -> RenderStats {
+        let items = std::mem::take(&mut self.items);
+        let eye = camera_position(view);
+        let mut materials: HashMap<String, Option<Material>> = HashMap::new();
+        let mut resolved = Vec::with_capacity(items.len());
+        let mut entries = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let name = item
+                .material
+                .clone()
+                .or_else(|| MODEL_MANAGER.material(&item.model));
+            let material = name.as_ref().and_then(|name| {
+                materials
+                    .entry(name.clone())
+                    .or_insert_with(|| MATERIAL_MANAGER.get(name))
+                    .clone()
+            });
+            let depth = (0..3)
+                .map(|i| (item.transform[i][3] - eye[i]).powi(2))
+                .sum::<f32>();
+            entries.push(SortEntry {
+                index,
+                sort_key: item.sort_key,
+                transparent: material.as_ref().is_some_and(Material::is_transparent),
+                shader: material
+                    .as_ref()
+                    .map(|m| m.shader().to_string())
+                    .unwrap_or_default(),
+                texture: material
+                    .as_ref()
+                    .and_then(|m| m.textures().first())
+                    .map(|(_, texture)| texture.clone())
+                    .unwrap_or_default(),
+                material: name.clone().unwrap_or_default(),
+                depth,
+            });
+            resolved.push(name);
+        }
+        sort_entries(&mut entries);
+
+        let mut stats = RenderStats::default();
+        let mut shaders: HashMap<String, Option<Shader>> = HashMap::new();
+        let mut program: Option<String> = None;
+        let mut current: Option<&str> = None;
+        let mut shader: Option<Shader> = None;
+        let mut units: Vec<Option<&str>> = vec![None; MAX_MATERIAL_TEXTURES];
+        let mut saved = None;
+        for entry in &entries {
+            let item = &items[entry.index];
+            if entry.transparent && saved.is_none() {
+                saved = Some(GL_STATE.pipeline());
+                GL_STATE.enable(Capability::Blend);
+                GL_STATE.set_blend_func(BlendFunc::ALPHA);
+                GL_STATE.set_depth_mask(false);
+            }
+            match &resolved[entry.index] {
+                Some(name) => {
+                    let material = match materials.get(name).and_then(Option::as_ref) {
+                        Some(material) => material,
+                        None => {
+                            warn_missing("材质", name);
+                            stats.dropped += 1;
+                            continue;
+                        }
+                    };
+                    if current != Some(name) {
+                        if program.as_deref() != Some(material.shader()) {
+                            shader = shaders
+                                .entry(material.shader().to_string())
+                                .or_insert_with(|| SHADER_MANAGER.get(material.shader()))
+                                .clone();
+                            if let Some(shader) = &shader {
+                                shader.use_program();
+                                shader.set_uniform("view", view);
+                                shader.set_uniform("projection", projection);
+                                stats.shader_binds += 1;
+                            }
+                            program = Some(material.shader().to_string());
+                        }
+                        if let Some(shader) = &shader {
+                            let textures = material.textures().iter().take(MAX_MATERIAL_TEXTURES);
+                            for (unit, (sampler, texture)) in textures.enumerate() {
+                                if units[unit] != Some(texture.as_str()) {
+                                    TEXTURE_MANAGER.bind(texture, unit as u32);
+                                    units[unit] = Some(texture.as_str());
+                                    stats.texture_binds += 1;
+                                }
+                                shader.set_uniform(sampler, unit as i32);
+                            }
+                            for (uniform, value) in material.uniforms() {
+                                shader.set_uniform(uniform, *value);
+                            }
+                            stats.material_binds += 1;
+                        }
+                        current = Some(name);
+                    }
+                    match &shader {
+                        Some(shader) => shader.set_uniform("model", item.transform),
+                        None => {
+                            warn_missing("着色器", material.shader());
+                            stats.dropped += 1;
+                            continue;
+                        }
+                    }
+                    MODEL_MANAGER.draw_geometry(&item.model);
+                }
+                // 没有材质时沿用当前启用的着色器
+                None => MODEL_MANAGER.draw_model_with(
+                    &item.model,
+                    &[
+                        ("model", item.transform.into()),
+                        ("view", view.into()),
+                        ("projection", projection.into()),
+                    ],
+                ),
+            }
+            stats.draws += 1;
+            if entry.transparent {
+                stats.transparent += 1;
+            } else {
+                stats.opaque += 1;
+            }
+        }
+        if let Some(pipeline) = saved {
+            GL_STATE.set_pipeline(&pipeline);
+        }
+        self.stats = stats;
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        index: usize,
+        transparent: bool,
+        shader: &str,
+        texture: &str,
+        depth: f32,
+    ) -> SortEntry {
+        SortEntry {
+            index,
+            sort_key: 0,
+            transparent,
+            shader: shader.to_string(),
+            texture: texture.to_string(),
+            material: format!("{}/{}", shader, texture),
+            depth,
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut entries = vec![
+            entry(0, true, "glass", "a", 1.0),
+            entry(1, false, "phong", "b", 5.0),
+            entry(2, false, "basic", "a", 1.0),
+            entry(3, true, "glass", "a", 9.0),
+            entry(4, false, "phong", "a", 2.0),
+            entry(5, false, "basic", "a", 3.0),
+            entry(6, false, "", "", 0.0),
+        ];
+        sort_entries(&mut entries);
+        let order = entries.iter().map(|e| e.index).collect::<Vec<_>>();
+        assert_eq!(order, vec![6, 2, 5, 4, 1, 3, 0]);
+
+        // 排序键优先于状态与深度
+        entries[0].sort_key = 1;
+        entries[6].sort_key = -1;
+        sort_entries(&mut entries);
+        let order = entries.iter().map(|e| e.index).collect::<Vec<_>>();
+        assert_eq!(order, vec![2, 5, 4, 1, 6, 0, 3]);
+    }
+
+    #[test]
+    fn test_item() {
+        let mut queue = RenderQueue::new();
+        assert!(queue.is_empty());
+        let item = RenderItem::new("cube", Mat4::I())
+            .material("wood")
+            .sort_key(2);
+        assert_eq!(item.material.as_deref(), Some("wood"));
+        assert_eq!(item.sort_key, 2);
+        queue.submit(item);
+        assert_eq!(queue.len(), 1);
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.stats(), RenderStats::default());
+
+        // 缺失材质的条目被跳过并计入统计
+        queue.submit(RenderItem::new("cube", Mat4::I()).material("render_queue_test_missing"));
+        let stats = queue.flush(Mat4::I(), Mat4::I());
+        assert_eq!(stats.dropped, 1);
+        assert_eq!(stats.draws, 0);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,570 @@
+use mats::Mat4;
+
+use crate::{
+    light::Light,
+    render_queue::{RenderItem, RenderQueue, RenderStats},
+    warn, LIGHT_SYSTEM,
+};
+
+mod serialize;
+mod transform;
+pub use serialize::SceneAssets;
+pub use transform::{Quat, Transform};
+
+/// 场景节点的标识
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// 节点附加的对象
+#[derive(Debug, Clone, PartialEq)]
+pub enum Attachment {
+    /// 摄像机，视图矩阵为节点世界变换(忽略缩放)的逆，朝向局部 -z 方向
+    Camera,
+    /// `LIGHT_SYSTEM` 中指定名称的光源，位置与方向随节点更新，方向为局部 -z 方向
+    Light(String),
+}
+
+/// 场景节点
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::scene::{Node, Transform};
+///
+/// let node = Node::new("crate")
+///     .transform(Transform::default().translation([0.0, 0.0, -1.0]))
+///     .model("cube")
+///     .material("wood");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Node {
+    name: String,
+    transform: Transform,
+    model: Option<String>,
+    material: Option<String>,
+    attachment: Option<Attachment>,
+    visible: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Mat4<f32>,
+    dirty: bool,
+}
+
+impl Node {
+    /// 创建空节点
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 节点名称，可重复
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            transform: Transform::default(),
+            model: None,
+            material: None,
+            attachment: None,
+            visible: true,
+            parent: None,
+            children: vec![],
+            world: Mat4::I(),
+            dirty: true,
+        }
+    }
+
+    /// 设置局部变换
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// 设置 `MODEL_MANAGER` 中的模型
+    pub fn model(mut self, model: &str) -> Self {
+        self.model = Some(model.to_string());
+        self
+    }
+
+    /// 设置 `MATERIAL_MANAGER` 中的材质，未设置时使用模型的默认材质
+    pub fn material(mut self, material: &str) -> Self {
+        self.material = Some(material.to_string());
+        self
+    }
+
+    /// 附加摄像机
+    pub fn camera(mut self) -> Self {
+        self.attachment = Some(Attachment::Camera);
+        self
+    }
+
+    /// 附加 `LIGHT_SYSTEM` 中的光源
+    pub fn light(mut self, light: &str) -> Self {
+        self.attachment = Some(Attachment::Light(light.to_string()));
+        self
+    }
+
+    /// 设置是否可见，不可见节点及其子节点均不被渲染
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// 节点名称
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 局部变换
+    pub fn local_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    /// 模型名称
+    pub fn model_name(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// 材质名称
+    pub fn material_name(&self) -> Option<&str> {
+        self.material.as_deref()
+    }
+
+    /// 附加的对象
+    pub fn attachment(&self) -> Option<&Attachment> {
+        self.attachment.as_ref()
+    }
+
+    /// 是否可见
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// 父节点
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// 子节点
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// 最近一次 `Scene::update` 计算的世界变换矩阵
+    pub fn world_matrix(&self) -> Mat4<f32> {
+        self.world
+    }
+}
+
+/// 由世界变换矩阵求视图矩阵，忽略缩放
+pub(crate) fn view_from_world(world: Mat4<f32>) -> Mat4<f32> {
+    let mut axes = [[0.0f32; 3]; 3];
+    for (j, axis) in axes.iter_mut().enumerate() {
+        let len = (0..3).map(|i| world[i][j].powi(2)).sum::<f32>().sqrt();
+        for (i, c) in axis.iter_mut().enumerate() {
+            *c = if len > 0.0 { world[i][j] / len } else { 0.0 };
+        }
+    }
+    // 视图矩阵为 [Rᵀ | -Rᵀt]
+    let mut view = Mat4::I();
+    for (i, axis) in axes.iter().enumerate() {
+        view[i][..3].copy_from_slice(axis);
+        view[i][3] = -(0..3).map(|j| axis[j] * world[j][3]).sum::<f32>();
+    }
+    view
+}
+
+/// 按世界变换矩阵放置光源：位置取平移，方向取局部 -z 方向
+pub(crate) fn place_light(light: &mut Light, world: Mat4<f32>) {
+    let position = [world[0][3], world[1][3], world[2][3]];
+    let forward = [-world[0][2], -world[1][2], -world[2][2]];
+    match light {
+        Light::Directional(light) => light.direction = forward,
+        Light::Point(light) => light.position = position,
+        Light::Spot(light) => {
+            light.position = position;
+            light.direction = forward;
+        }
+    }
+}
+
+/// 场景图
+///
+/// 节点以树状组织，世界变换为父节点的世界变换乘以自身的局部变换；
+/// 修改局部变换只标记该节点，在 `update` 时沿树向下传播
+///
+/// # 示例 Examples
+/// ```ignore
+/// let mut scene = Scene::new();
+/// let table = scene.add(Node::new("table").model("table"), None);
+/// let lamp = scene.add(
+///     Node::new("lamp")
+///         .transform(Transform::default().translation([0.0, 1.0, 0.0]))
+///         .model("lamp")
+///         .light("lamp"),
+///     Some(table),
+/// );
+/// // 渲染循环
+/// scene.update_transform(table, |t| t.rotation = Quat::from_axis_angle([0.0, 1.0, 0.0], App::time()));
+/// scene.render(view, projection);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    nodes: Vec<Option<Node>>,
+    roots: Vec<NodeId>,
+    active_camera: Option<NodeId>,
+    assets: SceneAssets,
+}
+
+impl Scene {
+    /// 创建空场景
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加节点
+    ///
+    /// # 参数 Parameters
+    /// * `node` - 节点
+    /// * `parent` - 父节点，`None` 或不存在时作为根节点
+    ///
+    /// # 注解 Note
+    ///
+    /// 第一个附加摄像机的节点成为活动摄像机
+    ///
+    /// # 返回值 Returns
+    /// 新节点的标识
+    pub fn add(&mut self, mut node: Node, parent: Option<NodeId>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        let parent = parent.filter(|&parent| self.node(parent).is_some());
+        node.parent = parent;
+        node.children.clear();
+        node.dirty = true;
+        if node.attachment == Some(Attachment::Camera) && self.active_camera.is_none() {
+            self.active_camera = Some(id);
+        }
+        self.nodes.push(Some(node));
+        match parent {
+            Some(parent) => self.node_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    /// 移除节点及其所有子孙节点
+    ///
+    /// # 返回值 Returns
+    /// 被移除的节点，不存在时返回 `None`
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        let node = self.nodes.get_mut(id.0)?.take()?;
+        match node.parent.and_then(|parent| self.node_mut(parent)) {
+            Some(parent) => parent.children.retain(|&child| child != id),
+            None => self.roots.retain(|&root| root != id),
+        }
+        let mut stack = node.children.clone();
+        while let Some(child) = stack.pop() {
+            if let Some(child) = self.nodes.get_mut(child.0).and_then(Option::take) {
+                stack.extend(child.children);
+            }
+        }
+        if self
+            .active_camera
+            .is_some_and(|camera| self.node(camera).is_none())
+        {
+            self.active_camera = None;
+        }
+        Some(node)
+    }
+
+    /// 获取节点
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id.0).and_then(Option::as_ref)
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(id.0).and_then(Option::as_mut)
+    }
+
+    /// 按名称查找第一个节点
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.ids().find(|&id| self.node(id).unwrap().name == name)
+    }
+
+    /// 所有节点的标识，按添加顺序排列
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_some())
+            .map(|(i, _)| NodeId(i))
+    }
+
+    /// 根节点
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// 设置节点的局部变换
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+        self.update_transform(id, |t| *t = transform);
+    }
+
+    /// 修改节点的局部变换
+    pub fn update_transform<F: FnOnce(&mut Transform)>(&mut self, id: NodeId, f: F) {
+        match self.node_mut(id) {
+            Some(node) => {
+                f(&mut node.transform);
+                node.dirty = true;
+            }
+            None => {
+                warn!("Scene", "节点 {:?} 不存在", id);
+            }
+        }
+    }
+
+    /// 设置节点的模型
+    pub fn set_model(&mut self, id: NodeId, model: Option<&str>) {
+        if let Some(node) = self.node_mut(id) {
+            node.model = model.map(|model| model.to_string());
+        }
+    }
+
+    /// 设置节点的材质
+    pub fn set_material(&mut self, id: NodeId, material: Option<&str>) {
+        if let Some(node) = self.node_mut(id) {
+            node.material = material.map(|material| material.to_string());
+        }
+    }
+
+    /// 设置节点附加的对象
+    pub fn set_attachment(&mut self, id: NodeId, attachment: Option<Attachment>) {
+        if let Some(node) = self.node_mut(id) {
+            node.attachment = attachment;
+            node.dirty = true;
+        }
+    }
+
+    /// 设置节点是否可见
+    pub fn set_visible(&mut self, id: NodeId, visible: bool) {
+        if let Some(node) = self.node_mut(id) {
+            node.visible = visible;
+        }
+    }
+
+    /// 修改节点的父节点，保持局部变换不变
+    ///
+    /// # 参数 Parameters
+    /// * `id` - 节点
+    /// * `parent` - 新的父节点，`None` 表示作为根节点
+    ///
+    /// # 返回值 Returns
+    /// 节点不存在或新的父节点是其自身或子孙节点时返回错误
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<(), String> {
+        let old = match self.node(id) {
+            Some(node) => node.parent,
+            None => return Err(format!("节点 {:?} 不存在", id)),
+        };
+        if let Some(parent) = parent {
+            if self.node(parent).is_none() {
+                return Err(format!("节点 {:?} 不存在", parent));
+            }
+            let mut ancestor = Some(parent);
+            while let Some(current) = ancestor {
+                if current == id {
+                    return Err(format!("节点 {:?} 不能成为自身子孙节点的子节点", id));
+                }
+                ancestor = self.node(current).unwrap().parent;
+            }
+        }
+        match old.and_then(|old| self.node_mut(old)) {
+            Some(old) => old.children.retain(|&child| child != id),
+            None => self.roots.retain(|&root| root != id),
+        }
+        match parent {
+            Some(parent) => self.node_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+        let node = self.node_mut(id).unwrap();
+        node.parent = parent;
+        node.dirty = true;
+        Ok(())
+    }
+
+    /// 设置活动摄像机，节点必须附加了摄像机
+    pub fn set_active_camera(&mut self, id: NodeId) {
+        match self.node(id).and_then(|node| node.attachment.as_ref()) {
+            Some(Attachment::Camera) => self.active_camera = Some(id),
+            _ => {
+                warn!("Scene", "节点 {:?} 没有附加摄像机", id);
+            }
+        }
+    }
+
+    /// 活动摄像机节点
+    pub fn active_camera(&self) -> Option<NodeId> {
+        self.active_camera
+    }
+
+    /// 活动摄像机的视图矩阵，基于最近一次 `update` 的结果
+    pub fn view_matrix(&self) -> Option<Mat4<f32>> {
+        self.active_camera
+            .and_then(|id| self.node(id))
+            .map(|node| view_from_world(node.world))
+    }
+
+    /// 节点的世界变换矩阵，基于最近一次 `update` 的结果
+    pub fn world_matrix(&self, id: NodeId) -> Option<Mat4<f32>> {
+        self.node(id).map(|node| node.world)
+    }
+
+    /// 重新计算已修改节点及其子孙节点的世界变换矩阵，并同步附加光源的位置与方向
+    pub fn update(&mut self) {
+        let mut stack = self
+            .roots
+            .iter()
+            .map(|&root| (root, Mat4::I(), false))
+            .collect::<Vec<_>>();
+        while let Some((id, parent_world, parent_changed)) = stack.pop() {
+            let node = match self.node_mut(id) {
+                Some(node) => node,
+                None => continue,
+            };
+            let changed = parent_changed || node.dirty;
+            if changed {
+                node.world = parent_world * node.transform.matrix();
+                node.dirty = false;
+                if let Some(Attachment::Light(light)) = &node.attachment {
+                    let world = node.world;
+                    LIGHT_SYSTEM.update(light, |light| place_light(light, world));
+                }
+            }
+            let world = node.world;
+            stack.extend(node.children.iter().map(|&child| (child, world, changed)));
+        }
+    }
+
+    /// 更新世界变换后将所有可见节点的模型提交到渲染队列，按深度优先顺序提交
+    ///
+    /// # 参数 Parameters
+    /// * `queue` - 渲染队列
+    pub fn submit(&mut self, queue: &mut RenderQueue) {
+        self.update();
+        let mut stack = self.roots.iter().rev().copied().collect::<Vec<_>>();
+        while let Some(id) = stack.pop() {
+            let node = match self.node(id) {
+                Some(node) if node.visible => node,
+                _ => continue,
+            };
+            if let Some(model) = &node.model {
+                let mut item = RenderItem::new(model, node.world);
+                if let Some(material) = &node.material {
+                    item = item.material(material);
+                }
+                queue.submit(item);
+            }
+            stack.extend(node.children.iter().rev());
+        }
+    }
+
+    /// 更新世界变换后绘制所有可见节点的模型
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 节点设置了材质时应用该材质，否则使用模型的默认材质；绘制经由 `RenderQueue` 排序，
+    /// 着色器的 `model`、`view` 与 `projection` 参数由队列设置
+    ///
+    /// # 参数 Parameters
+    /// * `view` - 摄像机的视图矩阵，可取自 `view_matrix`
+    /// * `projection` - 摄像机的投影矩阵
+    ///
+    /// # 返回值 Returns
+    /// 本次绘制的统计
+    pub fn render(&mut self, view: Mat4<f32>, projection: Mat4<f32>) -> RenderStats {
+        let mut queue = RenderQueue::new();
+        self.submit(&mut queue);
+        queue.flush(view, projection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(m: Mat4<f32>) -> [f32; 3] {
+        [m[0][3], m[1][3], m[2][3]]
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let mut scene = Scene::new();
+        let root =
+            scene.add(
+                Node::new("root").transform(
+                    Transform::default().translation([1.0, 0.0, 0.0]).rotation(
+                        Quat::from_axis_angle([0.0, 1.0, 0.0], std::f32::consts::FRAC_PI_2),
+                    ),
+                ),
+                None,
+            );
+        let child = scene.add(
+            Node::new("child").transform(Transform::default().translation([0.0, 0.0, 2.0])),
+            Some(root),
+        );
+        scene.update();
+        let p = translation(scene.world_matrix(child).unwrap());
+        assert!((p[0] - 3.0).abs() < 1e-5 && p[1].abs() < 1e-5 && p[2].abs() < 1e-5);
+
+        // 修改父节点后子节点随之更新
+        scene.update_transform(root, |t| t.translation = [0.0, 5.0, 0.0]);
+        scene.update();
+        let p = translation(scene.world_matrix(child).unwrap());
+        assert!((p[0] - 2.0).abs() < 1e-5 && (p[1] - 5.0).abs() < 1e-5);
+        assert_eq!(scene.find("child"), Some(child));
+    }
+
+    #[test]
+    fn test_set_parent_and_remove() {
+        let mut scene = Scene::new();
+        let a = scene.add(Node::new("a"), None);
+        let b = scene.add(Node::new("b"), Some(a));
+        let c = scene.add(Node::new("c").model("cube"), Some(b));
+        assert!(scene.set_parent(a, Some(c)).is_err());
+        assert!(scene.set_parent(c, None).is_ok());
+        assert_eq!(scene.roots(), &[a, c]);
+        assert!(scene.node(b).unwrap().children().is_empty());
+
+        scene.set_parent(c, Some(b)).unwrap();
+        scene.set_visible(b, false);
+        let mut queue = RenderQueue::new();
+        scene.submit(&mut queue);
+        assert!(queue.is_empty());
+        scene.set_visible(b, true);
+        scene.submit(&mut queue);
+        assert_eq!(queue.len(), 1);
+
+        assert!(scene.remove(a).is_some());
+        assert!(scene.node(c).is_none());
+        assert!(scene.roots().is_empty());
+        assert_eq!(scene.ids().count(), 0);
+    }
+
+    #[test]
+    fn test_camera_view() {
+        let mut scene = Scene::new();
+        let camera = scene.add(
+            Node::new("camera")
+                .transform(
+                    Transform::default()
+                        .translation([1.0, 2.0, 3.0])
+                        .rotation(Quat::from_axis_angle([0.0, 1.0, 0.0], 0.7))
+                        .scale([2.0, 2.0, 2.0]),
+                )
+                .camera(),
+            None,
+        );
+        assert_eq!(scene.active_camera(), Some(camera));
+        scene.update();
+        let view = scene.view_matrix().unwrap();
+        let position = crate::light::camera_position(view);
+        for (p, e) in position.iter().zip([1.0, 2.0, 3.0]) {
+            assert!((p - e).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::BTreeSet;
+
+use json::{object, JsonValue};
+
+use super::{Attachment, Node, NodeId, Quat, Scene, Transform};
+use crate::{
+    debug,
+    json_utils::{array_field, bool_field, str_field},
+    light::Light,
+    vfs::VFS,
+    Material, LIGHT_SYSTEM, MATERIAL_MANAGER, MODEL_MANAGER, SHADER_MANAGER, TEXTURE_MANAGER,
+};
+
+/// 场景引用的资源来源，载入场景时先于节点依次载入
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SceneAssets {
+    /// 以 `SHADER_MANAGER.load_from` 载入的目录
+    pub shaders: Vec<String>,
+    /// 以 `TEXTURE_MANAGER.load_from` 载入的目录
+    pub textures: Vec<String>,
+    /// 以 `MODEL_MANAGER.load_from_file` 载入的模型文件
+    pub models: Vec<String>,
+    /// 以 `MATERIAL_MANAGER.load_from_file` 载入的材质文件
+    pub materials: Vec<String>,
+}
+
+impl SceneAssets {
+    fn to_json(&self) -> JsonValue {
+        object! {
+            shaders: self.shaders.clone(),
+            textures: self.textures.clone(),
+            models: self.models.clone(),
+            materials: self.materials.clone(),
+        }
+    }
+
+    fn from_json(json: &JsonValue) -> Result<Self, String> {
+        if json.is_null() {
+            return Ok(Self::default());
+        }
+        if !json.is_object() {
+            return Err("assets 字段不是对象".to_string());
+        }
+        let list = |field: &str| -> Result<Vec<String>, String> {
+            let value = &json[field];
+            if value.is_null() {
+                return Ok(vec![]);
+            }
+            value
+                .members()
+                .map(|path| path.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .filter(|_| value.is_array())
+                .ok_or_else(|| format!("assets.{} 字段不是字符串数组", field))
+        };
+        Ok(Self {
+            shaders: list("shaders")?,
+            textures: list("textures")?,
+            models: list("models")?,
+            materials: list("materials")?,
+        })
+    }
+
+    /// 通过各资源管理器载入全部资源
+    fn load(&self) {
+        for dir in &self.shaders {
+            SHADER_MANAGER.load_from(dir);
+        }
+        for dir in &self.textures {
+            TEXTURE_MANAGER.load_from(dir);
+        }
+        // 材质可能被模型引用，先于模型载入
+        for path in &self.materials {
+            MATERIAL_MANAGER.load_from_file(path);
+        }
+        for path in &self.models {
+            MODEL_MANAGER.load_from_file(path);
+        }
+    }
+}
+
+/// 解析单个节点，不含子节点
+fn parse_node(json: &JsonValue) -> Result<(Node, bool), String> {
+    if !json.is_object() {
+        return Err("节点不是对象".to_string());
+    }
+    let mut node = Node::new(str_field(json, "name")?.unwrap_or_default());
+    let node_err = |e: String| format!("节点 {}: {}", node.name, e);
+    let mut transform = Transform::default();
+    if let Some(translation) = array_field(json, "translation").map_err(node_err)? {
+        transform.translation = translation;
+    }
+    if let Some([x, y, z, w]) = array_field(json, "rotation").map_err(node_err)? {
+        transform.rotation = Quat { x, y, z, w }.normalize();
+    }
+    if let Some(scale) = array_field(json, "scale").map_err(node_err)? {
+        transform.scale = scale;
+    }
+    let model = str_field(json, "model").map_err(node_err)?;
+    let material = str_field(json, "material").map_err(node_err)?;
+    let light = str_field(json, "light").map_err(node_err)?;
+    let camera = bool_field(json, "camera")
+        .map_err(node_err)?
+        .unwrap_or(false);
+    let active = bool_field(json, "active_camera")
+        .map_err(node_err)?
+        .unwrap_or(false);
+    let visible = bool_field(json, "visible")
+        .map_err(node_err)?
+        .unwrap_or(true);
+    if camera && light.is_some() {
+        return Err(node_err("不能同时附加摄像机与光源".to_string()));
+    }
+    if active && !camera {
+        return Err(node_err("活动摄像机节点缺少 camera 字段".to_string()));
+    }
+    node.transform = transform;
+    node.model = model.map(str::to_string);
+    node.material = material.map(str::to_string);
+    node.visible = visible;
+    node.attachment = match light {
+        Some(light) => Some(Attachment::Light(light.to_string())),
+        None if camera => Some(Attachment::Camera),
+        None => None,
+    };
+    Ok((node, active))
+}
+
+/// 按书写顺序解析节点数组及其子节点
+fn parse_nodes(
+    scene: &mut Scene,
+    nodes: &JsonValue,
+    parent: Option<NodeId>,
+    active_camera: &mut Option<NodeId>,
+) -> Result<(), String> {
+    if nodes.is_null() {
+        return Ok(());
+    }
+    if !nodes.is_array() {
+        return Err("nodes 与 children 字段必须是数组".to_string());
+    }
+    for json in nodes.members() {
+        let (node, active) = parse_node(json)?;
+        let id = scene.add(node, parent);
+        if active {
+            if active_camera.is_some() {
+                return Err("存在多个活动摄像机节点".to_string());
+            }
+            *active_camera = Some(id);
+        }
+        parse_nodes(scene, &json["children"], Some(id), active_camera)?;
+    }
+    Ok(())
+}
+
+impl Scene {
+    /// 场景引用的资源来源
+    pub fn assets(&self) -> &SceneAssets {
+        &self.assets
+    }
+
+    /// 设置场景引用的资源来源，随场景一同保存
+    pub fn set_assets(&mut self, assets: SceneAssets) {
+        self.assets = assets;
+    }
+
+    /// 从 JSON 对象解析场景结构，不载入资源
+    ///
+    /// # 注解 Note
+    ///
+    /// 格式：
+    /// ```json
+    /// {
+    ///     "assets": {
+    ///         "shaders": ["shader/"], "textures": ["texture/"],
+    ///         "models": ["model/cube.json"], "materials": ["material/wood.json"]
+    ///     },
+    ///     "materials": { "glass": { "shader": "light/blinn_phong", "transparent": true } },
+    ///     "lights": { "sun": { "type": "directional", "direction": [0.0, -1.0, -0.5] } },
+    ///     "ambient": [0.1, 0.1, 0.1],
+    ///     "nodes": [{
+    ///         "name": "table", "translation": [0.0, 0.0, -2.0],
+    ///         "rotation": [0.0, 0.0, 0.0, 1.0], "scale": [1.0, 1.0, 1.0],
+    ///         "model": "table", "material": "wood", "visible": true,
+    ///         "children": [{ "name": "eye", "camera": true, "active_camera": true }]
+    ///     }]
+    /// }
+    /// ```
+    /// `rotation` 为四元数 `[x, y, z, w]`；节点的 `light` 字段为光源名称；
+    /// `materials`、`lights` 与 `ambient` 由 `load` 登记到对应的管理器，此函数忽略
+    pub fn from_json(json: &JsonValue) -> Result<Self, String> {
+        if !json.is_object() {
+            return Err("JSON 不是对象".to_string());
+        }
+        let mut scene = Scene::new();
+        scene.assets = SceneAssets::from_json(&json["assets"])?;
+        let mut active_camera = None;
+        parse_nodes(&mut scene, &json["nodes"], None, &mut active_camera)?;
+        if active_camera.is_some() {
+            scene.active_camera = active_camera;
+        }
+        Ok(scene)
+    }
+
+    /// 转换为 JSON 对象，格式参见 `from_json`
+    ///
+    /// # 注解 Note
+    ///
+    /// 节点引用的材质与光源取自 `MATERIAL_MANAGER` 与 `LIGHT_SYSTEM` 并内联保存，
+    /// 模型、着色器与纹理只保存名称，由 `assets` 中的来源载入
+    ///
+    /// 仅支持场景图，`WORLD` 中的实体与组件不会被保存
+    pub fn to_json(&self) -> JsonValue {
+        let mut materials = JsonValue::new_object();
+        let mut lights = JsonValue::new_object();
+        for id in self.ids() {
+            let node = self.node(id).unwrap();
+            if let Some(name) = &node.material {
+                if let Some(material) = MATERIAL_MANAGER.get(name) {
+                    materials[name.as_str()] = material.to_json();
+                }
+            }
+            if let Some(Attachment::Light(name)) = &node.attachment {
+                if let Some(light) = LIGHT_SYSTEM.get(name) {
+                    lights[name.as_str()] = light.to_json();
+                }
+            }
+        }
+        object! {
+            assets: self.assets.to_json(),
+            materials: materials,
+            lights: lights,
+            ambient: LIGHT_SYSTEM.ambient().to_vec(),
+            nodes: self.nodes_to_json(&self.roots),
+        }
+    }
+
+    fn nodes_to_json(&self, ids: &[NodeId]) -> JsonValue {
+        let mut nodes = JsonValue::new_array();
+        for &id in ids {
+            let node = match self.node(id) {
+                Some(node) => node,
+                None => continue,
+            };
+            let Transform {
+                translation,
+                rotation,
+                scale,
+            } = node.transform;
+            let mut json = object! {
+                name: node.name.as_str(),
+                translation: translation.to_vec(),
+                rotation: vec![rotation.x, rotation.y, rotation.z, rotation.w],
+                scale: scale.to_vec(),
+                visible: node.visible,
+            };
+            if let Some(model) = &node.model {
+                json["model"] = model.as_str().into();
+            }
+            if let Some(material) = &node.material {
+                json["material"] = material.as_str().into();
+            }
+            match &node.attachment {
+                Some(Attachment::Camera) => {
+                    json["camera"] = true.into();
+                    if self.active_camera == Some(id) {
+                        json["active_camera"] = true.into();
+                    }
+                }
+                Some(Attachment::Light(light)) => json["light"] = light.as_str().into(),
+                None => {}
+            }
+            if !node.children.is_empty() {
+                json["children"] = self.nodes_to_json(&node.children);
+            }
+            let _ = nodes.push(json);
+        }
+        nodes
+    }
+
+    /// 将场景保存为 JSON 文件
+    ///
+    /// # 注解 Note
+    ///
+    /// 只保存场景图，`WORLD` 中的实体需由使用者自行保存
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 文件系统中的路径
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_json().pretty(4))
+            .map_err(|e| format!("无法保存场景 {}: {}", path, e))
+    }
+
+    /// 载入场景文件及其引用的资源
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 依次载入 `assets` 中的资源，将 `materials` 与 `lights` 登记到 `MATERIAL_MANAGER` 与
+    /// `LIGHT_SYSTEM`(覆盖同名项)，设置环境光，最后以 `validate` 检查引用
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 虚拟文件系统中的场景文件路径，格式参见 `from_json`
+    ///
+    /// # 返回值 Returns
+    /// 文件无法解析或存在缺失的引用时返回错误
+    pub fn load(path: &str) -> Result<Self, String> {
+        debug!("Scene", "尝试载入场景 {}", path);
+        let text = VFS.read_to_string(path)?;
+        let json = json::parse(&text).map_err(|e| format!("JSON 解析错误: {}", e))?;
+        let scene = Scene::from_json(&json)?;
+        let mut materials = vec![];
+        if !json["materials"].is_null() && !json["materials"].is_object() {
+            return Err("materials 字段不是对象".to_string());
+        }
+        for (name, json) in json["materials"].entries() {
+            let material =
+                Material::from_json(json).map_err(|e| format!("材质 {}: {}", name, e))?;
+            materials.push((name, material));
+        }
+        let mut lights = vec![];
+        if !json["lights"].is_null() && !json["lights"].is_object() {
+            return Err("lights 字段不是对象".to_string());
+        }
+        for (name, json) in json["lights"].entries() {
+            let light = Light::from_json(json).map_err(|e| format!("光源 {}: {}", name, e))?;
+            lights.push((name, light));
+        }
+        let ambient = array_field(&json, "ambient")?;
+
+        scene.assets.load();
+        for (name, material) in materials {
+            MATERIAL_MANAGER.add(name, material);
+        }
+        for (name, light) in lights {
+            LIGHT_SYSTEM.set(name, light);
+        }
+        if let Some(ambient) = ambient {
+            LIGHT_SYSTEM.set_ambient(ambient);
+        }
+        scene.validate()?;
+        Ok(scene)
+    }
+
+    /// 检查节点引用的模型、材质与光源，以及材质引用的着色器与纹理是否存在
+    ///
+    /// # 返回值 Returns
+    /// 存在缺失的引用时返回列出全部缺失项的错误，重复的缺失项只列出一次
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = BTreeSet::new();
+        let mut materials = vec![];
+        for id in self.ids() {
+            let node = self.node(id).unwrap();
+            if let Some(model) = &node.model {
+                if MODEL_MANAGER.handle(model).is_none() {
+                    errors.insert(format!("节点 {} 引用的模型 {} 不存在", node.name, model));
+                } else if let Some(material) = MODEL_MANAGER.material(model) {
+                    if node.material.is_none() {
+                        materials.push((format!("模型 {}", model), material));
+                    }
+                }
+            }
+            if let Some(material) = &node.material {
+                materials.push((format!("节点 {}", node.name), material.clone()));
+            }
+            if let Some(Attachment::Light(light)) = &node.attachment {
+                if LIGHT_SYSTEM.get(light).is_none() {
+                    errors.insert(format!("节点 {} 引用的光源 {} 不存在", node.name, light));
+                }
+            }
+        }
+        for (owner, name) in materials {
+            let material = match MATERIAL_MANAGER.get(&name) {
+                Some(material) => material,
+                None => {
+                    errors.insert(format!("{} 引用的材质 {} 不存在", owner, name));
+                    continue;
+                }
+            };
+            if SHADER_MANAGER.handle(material.shader()).is_none() {
+                errors.insert(format!(
+                    "材质 {} 引用的着色器 {} 不存在",
+                    name,
+                    material.shader()
+                ));
+            }
+            for (_, texture) in material.textures() {
+                if TEXTURE_MANAGER.handle(texture).is_none() {
+                    errors.insert(format!("材质 {} 引用的纹理 {} 不存在", name, texture));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.into_iter().collect::<Vec<_>>().join("; "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"{
+        "assets": { "models": ["model/table.json"] },
+        "nodes": [{
+            "name": "table",
+            "translation": [0, 0, -2],
+            "scale": [2, 2, 2],
+            "model": "scene_test_table",
+            "children": [
+                { "name": "eye", "camera": true },
+                { "name": "top", "camera": true, "active_camera": true, "visible": false }
+            ]
+        }, {
+            "name": "lamp", "light": "scene_test_lamp"
+        }]
+    }"#;
+
+    #[test]
+    fn test_from_json() {
+        let scene = Scene::from_json(&json::parse(SCENE).unwrap()).unwrap();
+        assert_eq!(scene.assets().models, vec!["model/table.json"]);
+        let table = scene.find("table").unwrap();
+        let top = scene.find("top").unwrap();
+        assert_eq!(scene.node(top).unwrap().parent(), Some(table));
+        assert!(!scene.node(top).unwrap().is_visible());
+        assert_eq!(scene.active_camera(), Some(top));
+        assert_eq!(
+            scene.node(table).unwrap().local_transform().scale,
+            [2.0, 2.0, 2.0]
+        );
+        let lamp = scene.find("lamp").unwrap();
+        assert_eq!(
+            scene.node(lamp).unwrap().attachment(),
+            Some(&Attachment::Light("scene_test_lamp".to_string()))
+        );
+
+        // 保存后再解析得到相同的结构
+        let again = Scene::from_json(&scene.to_json()).unwrap();
+        assert_eq!(again.ids().count(), scene.ids().count());
+        assert_eq!(again.active_camera(), scene.active_camera());
+        assert_eq!(again.assets(), scene.assets());
+        for id in scene.ids() {
+            let (a, b) = (scene.node(id).unwrap(), again.node(id).unwrap());
+            assert_eq!(a.name(), b.name());
+            assert_eq!(a.parent(), b.parent());
+            assert_eq!(a.local_transform(), b.local_transform());
+            assert_eq!(a.attachment(), b.attachment());
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let parse = |text: &str| Scene::from_json(&json::parse(text).unwrap());
+        assert!(parse(r#"{ "nodes": [{ "name": "a", "rotation": [0, 0, 1] }] }"#).is_err());
+        assert!(parse(r#"{ "nodes": [{ "name": "a", "active_camera": true }] }"#).is_err());
+        assert!(parse(r#"{ "nodes": [{ "camera": true, "light": "l" }] }"#).is_err());
+        assert!(parse(r#"{ "assets": { "models": "a.json" } }"#).is_err());
+
+        let scene = Scene::from_json(&json::parse(SCENE).unwrap()).unwrap();
+        let err = scene.validate().unwrap_err();
+        assert!(err.contains("scene_test_table"));
+        assert!(err.contains("scene_test_lamp"));
+    }
+}
@@ -0,0 +1,290 @@
+use std::ops::Mul;
+
+use mats::Mat4;
+
+/// 单位四元数表示的旋转
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::scene::Quat;
+///
+/// let q = Quat::from_axis_angle([0.0, 1.0, 0.0], std::f32::consts::FRAC_PI_2);
+/// let v = q.rotate([1.0, 0.0, 0.0]);
+/// assert!((v[2] + 1.0).abs() < 1e-5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Quat {
+    /// 不旋转
+    pub const IDENTITY: Quat = Quat {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        w: 1.0,
+    };
+
+    /// 绕轴旋转
+    ///
+    /// # 参数 Parameters
+    /// * `axis` - 旋转轴，无需归一化
+    /// * `angle` - 旋转角(弧度)，沿轴方向看去为逆时针
+    pub fn from_axis_angle(axis: [f32; 3], angle: f32) -> Self {
+        let len = axis.iter().map(|c| c * c).sum::<f32>().sqrt();
+        if len == 0.0 {
+            return Self::IDENTITY;
+        }
+        let (s, c) = (angle * 0.5).sin_cos();
+        let [x, y, z] = axis.map(|v| v / len * s);
+        Self { x, y, z, w: c }
+    }
+
+    /// 依次绕 z、x、y 轴旋转，即 `偏航 * 俯仰 * 翻滚`
+    ///
+    /// # 参数 Parameters
+    /// * `pitch` - 绕 x 轴的旋转角(弧度)
+    /// * `yaw` - 绕 y 轴的旋转角(弧度)
+    /// * `roll` - 绕 z 轴的旋转角(弧度)
+    pub fn from_euler(pitch: f32, yaw: f32, roll: f32) -> Self {
+        Self::from_axis_angle([0.0, 1.0, 0.0], yaw)
+            * Self::from_axis_angle([1.0, 0.0, 0.0], pitch)
+            * Self::from_axis_angle([0.0, 0.0, 1.0], roll)
+    }
+
+    /// 归一化，长度为零时返回 `IDENTITY`
+    pub fn normalize(self) -> Self {
+        let len = self.dot(self).sqrt();
+        if len == 0.0 {
+            return Self::IDENTITY;
+        }
+        Self {
+            x: self.x / len,
+            y: self.y / len,
+            z: self.z / len,
+            w: self.w / len,
+        }
+    }
+
+    /// 共轭，对单位四元数即逆旋转
+    pub fn conjugate(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: self.w,
+        }
+    }
+
+    fn dot(self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    /// 旋转向量
+    pub fn rotate(self, v: [f32; 3]) -> [f32; 3] {
+        let p = Quat {
+            x: v[0],
+            y: v[1],
+            z: v[2],
+            w: 0.0,
+        };
+        let r = self * p * self.conjugate();
+        [r.x, r.y, r.z]
+    }
+
+    /// 球面线性插值
+    ///
+    /// # 参数 Parameters
+    /// * `other` - 目标旋转
+    /// * `t` - 插值系数，0 为自身，1 为目标
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        let mut cos = self.dot(other);
+        // 取最短路径
+        let other = if cos < 0.0 {
+            cos = -cos;
+            Quat {
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+                w: -other.w,
+            }
+        } else {
+            other
+        };
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Quat {
+            x: self.x * a + other.x * b,
+            y: self.y * a + other.y * b,
+            z: self.z * a + other.z * b,
+            w: self.w * a + other.w * b,
+        }
+        .normalize()
+    }
+
+    /// 旋转矩阵
+    pub fn matrix(self) -> Mat4<f32> {
+        let Quat { x, y, z, w } = self.normalize();
+        Mat4::from([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+impl Mul for Quat {
+    type Output = Quat;
+
+    /// 组合旋转，`a * b` 先应用 `b` 再应用 `a`
+    fn mul(self, rhs: Quat) -> Quat {
+        Quat {
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        }
+    }
+}
+
+/// 相对父节点的局部变换，依次应用缩放、旋转与平移
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::scene::{Quat, Transform};
+///
+/// let transform = Transform::default()
+///     .translation([0.0, 1.0, 0.0])
+///     .rotation(Quat::from_axis_angle([0.0, 1.0, 0.0], 0.5))
+///     .scale([2.0, 2.0, 2.0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: [f32; 3],
+    pub rotation: Quat,
+    pub scale: [f32; 3],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation: Quat::IDENTITY,
+            scale: [1.0; 3],
+        }
+    }
+}
+
+impl Transform {
+    /// 设置平移
+    pub fn translation(mut self, translation: [f32; 3]) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    /// 设置旋转
+    pub fn rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// 设置缩放
+    pub fn scale(mut self, scale: [f32; 3]) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// 变换矩阵，即 `平移 * 旋转 * 缩放`
+    pub fn matrix(&self) -> Mat4<f32> {
+        let mut m = self.rotation.matrix();
+        for i in 0..3 {
+            for j in 0..3 {
+                m[i][j] *= self.scale[j];
+            }
+            m[i][3] = self.translation[i];
+        }
+        m
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mats::{rotate3, scale3, tranlate3, Vec3};
+
+    fn assert_mat_eq(a: Mat4<f32>, b: Mat4<f32>) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a[i][j] - b[i][j]).abs() < 1e-5, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_quat_matrix() {
+        let axis = [1.0, 2.0, -0.5];
+        let q = Quat::from_axis_angle(axis, 0.8);
+        assert_mat_eq(q.matrix(), rotate3(0.8, Vec3::from(axis)));
+        let v = q.rotate([0.3, -1.0, 2.0]);
+        let m = q.matrix();
+        for (i, c) in v.iter().enumerate() {
+            let expected = m[i][0] * 0.3 - m[i][1] + m[i][2] * 2.0;
+            assert!((c - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_quat_slerp() {
+        let a = Quat::from_axis_angle([0.0, 1.0, 0.0], 0.0);
+        let b = Quat::from_axis_angle([0.0, 1.0, 0.0], 1.0);
+        let mid = a.slerp(b, 0.5);
+        assert_mat_eq(
+            mid.matrix(),
+            Quat::from_axis_angle([0.0, 1.0, 0.0], 0.5).matrix(),
+        );
+        assert_eq!(a.slerp(b, 0.0), a);
+    }
+
+    #[test]
+    fn test_transform_matrix() {
+        let transform = Transform::default()
+            .translation([1.0, 2.0, 3.0])
+            .rotation(Quat::from_axis_angle([0.0, 0.0, 1.0], 0.3))
+            .scale([2.0, 3.0, 4.0]);
+        let expected = tranlate3(Vec3::from([1.0, 2.0, 3.0]))
+            * rotate3(0.3, Vec3::from([0.0, 0.0, 1.0]))
+            * scale3(Vec3::from([2.0, 3.0, 4.0]));
+        assert_mat_eq(transform.matrix(), expected);
+    }
+}
@@ -1,12 +1,36 @@
-use crate::{debug, gl_utils, info, warn, RustCraftWrapper};
-use gl::types::*;
-use shader::Shader;
+use crate::{
+    debug, gl_utils, info, light, shadow,
+    vfs::{ScanOptions, VFS},
+    warn, Handle, RustCraftWrapper, ASSETS,
+};
+pub use shader::Shader;
 use std::collections::{HashMap, HashSet};
+pub use uniform::UniformValue;
 
 mod shader;
+mod uniform;
 
+/// 将源码中的 `#include <名称>` 替换为内置的着色器库
+///
+/// + `lights` - 光源 uniform 块与光照函数，参见 `light::lights_glsl`
+/// + `shadows` - 阴影贴图参数与阴影函数，参见 `shadow::shadows_glsl`
+pub(crate) fn expand_includes(source: &str) -> String {
+    let includes = [
+        ("lights", light::lights_glsl()),
+        ("shadows", shadow::shadows_glsl()),
+    ];
+    let mut source = source.to_string();
+    for (name, code) in includes {
+        source = source.replace(&format!("#include <{}>", name), code);
+    }
+    source
+}
+
+/// 着色器管理器
+///
+/// 着色器程序存储于资源系统 `ASSETS` 中，管理器仅持有其句柄
 pub struct ShaderManager {
-    programs: HashMap<String, GLuint>,
+    programs: HashMap<String, Handle<Shader>>,
 }
 
 impl ShaderManager {
@@ -20,12 +44,13 @@ impl ShaderManager {
     ///
     /// # 参数 Parameters
     /// * `path` - 目录路径
+    /// * `options` - 扫描选项
     ///
     /// # 注解 Note
     /// 此函数会遍历指定目录下的所有文件，并尝试编译其中的顶点着色器和片段着色器
     /// 只有成功编译且成功配对的着色器才会被保留，否则会输出警告信息
     /// 配对的顶点着色器和片段着色器必须具有相同的文件名
-    fn load_from(&mut self, path: &str) {
+    fn load_from(&mut self, path: &str, options: &ScanOptions) {
         debug!("ShaderManager", "正在从 {} 加载着色器", path);
         // 顶点着色器文件扩展名集合
         let vert_ext = {
@@ -40,64 +65,55 @@ impl ShaderManager {
             set.insert("fs");
             set
         };
-        use std::fs::*;
         // 遍历指定目录
-        let dir = match read_dir(path) {
-            Ok(dir) => dir,
-            Err(e) => {
+        let entries = VFS.scan(path, options);
+        if entries.is_empty() {
+            warn!("ShaderManager", "目录 {} 中没有任何匹配的文件", path);
+            return;
+        }
+        let mut vert_codes = HashMap::new();
+        let mut frag_codes = HashMap::new();
+        let mut sources: HashMap<(String, bool), String> = HashMap::new();
+        for entry in entries {
+            debug!("ShaderManager", "检索到文件: {}", entry.path);
+            // 标记着色器
+            let is_vert = if vert_ext.contains(entry.ext.as_str()) {
+                true
+            } else if frag_ext.contains(entry.ext.as_str()) {
+                false
+            } else {
+                info!("ShaderManager", "忽略未知文件: {}", entry.path);
+                continue;
+            };
+            let key = (entry.name.clone(), is_vert);
+            if let Some(first) = sources.get(&key) {
                 warn!(
                     "ShaderManager",
-                    "无法读取指定目录: {}, 由于 \"{}\"", path, e
+                    "着色器名称冲突: {} 同时对应 {} 与 {}, 已忽略后者",
+                    entry.name,
+                    first,
+                    entry.path
                 );
-                return;
+                continue;
             }
-        };
-        let mut vert_codes = HashMap::new();
-        let mut frag_codes = HashMap::new();
-        for entry in dir {
-            let entry = match entry {
-                Ok(entry) => entry,
-                Err(e) => {
-                    warn!("ShaderManager", "遍历项目出现错误，由于 \"{}\"", e);
-                    continue;
-                }
-            };
-            let path = entry.path();
             // 读取文件内容
-            let content = match read_to_string(path.clone()) {
+            let content = match VFS.read_to_string(&entry.path) {
                 Ok(content) => content,
                 Err(e) => {
                     warn!(
                         "ShaderManager",
-                        "无法读取文件: {}, 由于 \"{}\"",
-                        path.display(),
-                        e
+                        "无法读取文件: {}, 由于 \"{}\"", entry.path, e
                     );
                     continue;
                 }
             };
-            debug!("ShaderManager", "检索到文件: {}", path.display());
-            // 文件扩展名
-            let ext: &str = if let Some(ext) = path.extension() {
-                ext.to_str().unwrap()
-            } else {
-                info!("ShaderManager", "忽略未知文件: {}", path.display());
-                continue;
-            };
-            // 文件本名
-            let filename = if let Some(ext) = path.file_stem() {
-                ext.to_str().unwrap()
+            sources.insert(key, entry.path.clone());
+            if is_vert {
+                debug!("ShaderManager", "顶点着色器: {}", entry.path);
+                vert_codes.insert(entry.name, content);
             } else {
-                info!("ShaderManager", "忽略未知文件: {}", path.display());
-                continue;
-            };
-            // 标记着色器
-            if vert_ext.contains(ext) {
-                debug!("ShaderManager", "顶点着色器: {}", path.display());
-                vert_codes.insert(filename.to_string(), content);
-            } else if frag_ext.contains(ext) {
-                debug!("ShaderManager", "片段着色器: {}", path.display());
-                frag_codes.insert(filename.to_string(), content);
+                debug!("ShaderManager", "片段着色器: {}", entry.path);
+                frag_codes.insert(entry.name, content);
             }
         }
         // 移除不完整的着色器
@@ -124,7 +140,9 @@ impl ShaderManager {
         let mut vert_shader = HashMap::new();
         let mut frag_shader = HashMap::new();
         for (path, code) in vert_codes {
-            let shader = match unsafe { gl_utils::complie_shader(gl::VERTEX_SHADER, &code) } {
+            let shader = match unsafe {
+                gl_utils::complie_shader(gl::VERTEX_SHADER, &expand_includes(&code))
+            } {
                 Ok(shader) => Some(shader),
                 Err(e) => {
                     warn!(
@@ -137,7 +155,9 @@ impl ShaderManager {
             vert_shader.insert(path, shader);
         }
         for (path, code) in frag_codes {
-            let shader = match unsafe { gl_utils::complie_shader(gl::FRAGMENT_SHADER, &code) } {
+            let shader = match unsafe {
+                gl_utils::complie_shader(gl::FRAGMENT_SHADER, &expand_includes(&code))
+            } {
                 Ok(shader) => Some(shader),
                 Err(e) => {
                     warn!(
@@ -178,7 +198,8 @@ impl ShaderManager {
                     continue;
                 }
             };
-            self.programs.insert(path, program);
+            let handle = ASSETS.insert(&path, Shader::from_program(program));
+            self.programs.insert(path, handle);
         }
     }
 }
@@ -193,6 +214,28 @@ lazy_static! {
 }
 
 impl RustCraftWrapper<ShaderManager> {
+    /// 由源码编译着色器并以指定名称登记
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，适用于嵌入在程序中的着色器
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 着色器名称
+    /// * `vert` - 顶点着色器源码
+    /// * `frag` - 片段着色器源码
+    pub fn add_from_source(&self, name: &str, vert: &str, frag: &str) {
+        match Shader::from_source(vert, frag) {
+            Ok(shader) => self.apply(|sm| {
+                let handle = ASSETS.insert(name, shader);
+                sm.programs.insert(name.to_string(), handle);
+            }),
+            Err(e) => {
+                warn!("RCW<ShaderManager>", "着色器\"{}\"加载失败: {}", name, e);
+            }
+        }
+    }
+
     /// 加载指定目录下的着色器
     ///
     /// # 注解 Note
@@ -207,7 +250,25 @@ impl RustCraftWrapper<ShaderManager> {
     /// * `path` - 目录路径
     pub fn load_from(&self, path: &str) {
         self.apply(|manager| {
-            manager.load_from(path);
+            manager.load_from(path, &ScanOptions::default());
+        });
+    }
+
+    /// 按扫描选项加载指定目录下的着色器
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// + 着色器名称为相对于 `path`、不含扩展名的路径，例如 `post/blur`
+    /// + 同名的多个顶点(或片段)着色器文件视为冲突，仅保留路径排序靠前的一个
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 目录路径
+    /// * `options` - 扫描选项，可指定递归扫描与通配符过滤
+    pub fn load_from_with(&self, path: &str, options: &ScanOptions) {
+        self.apply(|manager| {
+            manager.load_from(path, options);
         });
     }
 
@@ -224,8 +285,8 @@ impl RustCraftWrapper<ShaderManager> {
     pub fn get(&self, name: &str) -> Option<Shader> {
         let mut ret = None;
         self.apply(|manager| {
-            if let Some(&program) = manager.programs.get(name) {
-                ret = Some(Shader { program });
+            if let Some(handle) = manager.programs.get(name) {
+                ret = ASSETS.with(handle, |shader| shader.clone());
             } else {
                 NOT_FOUND.apply(|set| {
                     if set.contains(name) {
@@ -241,4 +302,28 @@ impl RustCraftWrapper<ShaderManager> {
         });
         ret
     }
+
+    /// 获取指定名称着色器的资源句柄
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 着色器名称
+    pub fn handle(&self, name: &str) -> Option<Handle<Shader>> {
+        let mut ret = None;
+        self.apply(|manager| ret = manager.programs.get(name).cloned());
+        ret
+    }
+
+    /// 卸载指定名称的着色器
+    ///
+    /// # 注解 Note
+    ///
+    /// 管理器释放其持有的句柄，着色器在其他句柄与依赖也释放后由 `ASSETS.collect_garbage` 卸载
+    ///
+    /// # 返回值 Returns
+    /// 管理器中存在该名称时返回 `true`
+    pub fn unload(&self, name: &str) -> bool {
+        let mut ret = false;
+        self.apply(|manager| ret = manager.programs.remove(name).is_some());
+        ret
+    }
 }
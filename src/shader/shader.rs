@@ -1,26 +1,90 @@
+use std::sync::Arc;
+
 use gl::types::*;
 
 use crate::{
     gl_utils::{self},
-    warn,
+    light::{LIGHT_BLOCK, LIGHT_BLOCK_BINDING},
+    warn, GL_STATE,
 };
 use mats::uniform::SetUniform;
 
+use super::expand_includes;
+
+/// 着色器程序对象，释放时删除 OpenGL 程序
+pub(crate) struct Program(GLuint);
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        GL_STATE.delete_program(self.0);
+    }
+}
+
+/// 着色器
+///
+/// 克隆的着色器共享同一个程序对象，程序在最后一个引用释放时删除
+#[derive(Clone)]
 pub struct Shader {
-    pub(crate) program: GLuint,
+    pub(crate) program: Arc<Program>,
 }
 
 impl Shader {
-    /// 向当前上下文应用着色器程序
-    pub fn use_program(&self) {
+    pub(crate) fn from_program(program: GLuint) -> Self {
+        // 声明了光源 uniform 块的着色器绑定到光源系统的绑定点
         unsafe {
-            gl_utils::use_program(self.program);
+            gl_utils::bind_uniform_block(program, LIGHT_BLOCK, LIGHT_BLOCK_BINDING);
         }
+        Self {
+            program: Arc::new(Program(program)),
+        }
+    }
+
+    /// 由顶点着色器与片段着色器源码编译着色器程序
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用；
+    /// 源码中的 `#include <lights>`、`#include <shadows>` 会被替换为内置的着色器库
+    ///
+    /// # 参数 Parameters
+    /// * `vert` - 顶点着色器源码
+    /// * `frag` - 片段着色器源码
+    pub fn from_source(vert: &str, frag: &str) -> Result<Self, String> {
+        unsafe {
+            let vshader = gl_utils::complie_shader(gl::VERTEX_SHADER, &expand_includes(vert))
+                .map_err(|e| format!("顶点着色器编译失败, 由于 \"{}\"", e))?;
+            let fshader =
+                match gl_utils::complie_shader(gl::FRAGMENT_SHADER, &expand_includes(frag)) {
+                    Ok(shader) => shader,
+                    Err(e) => {
+                        gl::DeleteShader(vshader);
+                        return Err(format!("片段着色器编译失败, 由于 \"{}\"", e));
+                    }
+                };
+            match gl_utils::link_program(vshader, fshader) {
+                Ok(program) => Ok(Self::from_program(program)),
+                Err(e) => {
+                    gl::DeleteShader(vshader);
+                    gl::DeleteShader(fshader);
+                    Err(format!("着色器链接失败, 由于 \"{}\"", e))
+                }
+            }
+        }
+    }
+
+    /// OpenGL 程序对象名称
+    pub(crate) fn id(&self) -> GLuint {
+        self.program.0
+    }
+
+    /// 向当前上下文应用着色器程序
+    pub fn use_program(&self) {
+        GL_STATE.use_program(self.id());
     }
 
     /// 向着色器程序中设置uniform变量
     pub fn set_uniform<T: SetUniform>(&self, name: &str, value: T) {
-        if let Err(e) = unsafe { gl_utils::set_uniform(self.program, name, value) } {
+        if let Err(e) = unsafe { gl_utils::set_uniform(self.id(), name, value) } {
             warn!("Shader", "{}", e);
         };
     }
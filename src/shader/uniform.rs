@@ -0,0 +1,79 @@
+use mats::{uniform::SetUniform, Mat4, Vec2, Vec3, Vec4};
+
+/// 可保存并在之后设置到着色器中的 uniform 值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformValue {
+    Int(i32),
+    Float(f32),
+    Vec2(Vec2<f32>),
+    Vec3(Vec3<f32>),
+    Vec4(Vec4<f32>),
+    Mat4(Mat4<f32>),
+}
+
+impl SetUniform for UniformValue {
+    fn give(&self, location: i32) {
+        match self {
+            UniformValue::Int(v) => v.give(location),
+            UniformValue::Float(v) => v.give(location),
+            UniformValue::Vec2(v) => v.give(location),
+            UniformValue::Vec3(v) => v.give(location),
+            UniformValue::Vec4(v) => v.give(location),
+            UniformValue::Mat4(v) => v.give(location),
+        }
+    }
+}
+
+impl From<i32> for UniformValue {
+    fn from(value: i32) -> Self {
+        UniformValue::Int(value)
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(value: f32) -> Self {
+        UniformValue::Float(value)
+    }
+}
+
+impl From<[f32; 2]> for UniformValue {
+    fn from(value: [f32; 2]) -> Self {
+        UniformValue::Vec2(Vec2::from(value))
+    }
+}
+
+impl From<[f32; 3]> for UniformValue {
+    fn from(value: [f32; 3]) -> Self {
+        UniformValue::Vec3(Vec3::from(value))
+    }
+}
+
+impl From<[f32; 4]> for UniformValue {
+    fn from(value: [f32; 4]) -> Self {
+        UniformValue::Vec4(Vec4::from(value))
+    }
+}
+
+impl From<Vec2<f32>> for UniformValue {
+    fn from(value: Vec2<f32>) -> Self {
+        UniformValue::Vec2(value)
+    }
+}
+
+impl From<Vec3<f32>> for UniformValue {
+    fn from(value: Vec3<f32>) -> Self {
+        UniformValue::Vec3(value)
+    }
+}
+
+impl From<Vec4<f32>> for UniformValue {
+    fn from(value: Vec4<f32>) -> Self {
+        UniformValue::Vec4(value)
+    }
+}
+
+impl From<Mat4<f32>> for UniformValue {
+    fn from(value: Mat4<f32>) -> Self {
+        UniformValue::Mat4(value)
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::HashSet;
+
+use gl::types::*;
+use lazy_static::lazy_static;
+use mats::{look_at, perspective, Mat4, Vec2, Vec3};
+
+use crate::{
+    gl_state::Capability,
+    gl_utils,
+    light::{Light, SpotLight},
+    texture::DepthFormat,
+    warn, Framebuffer, FramebufferBuilder, FramebufferSize, Handle, RustCraftWrapper, Shader,
+    Texture, GL_STATE, LIGHT_SYSTEM, SHADER_MANAGER,
+};
+
+const SHADOWS_GLSL: &str = include_str!("shadows.glsl");
+const DEPTH_VERT: &str = include_str!("depth.vert");
+const DEPTH_FRAG: &str = include_str!("depth.frag");
+
+/// 深度着色器在 `SHADER_MANAGER` 中的名称
+pub const DEPTH_SHADER: &str = "shadow/depth";
+/// 级联数量的上限，与 `shadows.glsl` 中的数组长度一致
+pub const MAX_SHADOW_CASCADES: usize = 4;
+/// 聚光灯阴影投影的近裁切面
+const SPOT_NEAR: f32 = 0.1;
+/// 方向光阴影沿光线反方向额外包含的范围(相对于级联包围球半径)，
+/// 使视锥之外、位于光源一侧的物体仍能投射阴影
+const CASTER_MARGIN: f32 = 2.0;
+
+lazy_static! {
+    /// 已警告过无法投射阴影的光源名称
+    static ref INVALID_LIGHT: RustCraftWrapper<HashSet<String>> =
+        RustCraftWrapper::new(HashSet::new());
+}
+
+/// 阴影贴图参数及 `computeShadow` 函数的 GLSL 源码
+///
+/// 着色器源码中的 `#include <shadows>` 会在编译前被自动替换为此源码
+pub fn shadows_glsl() -> &'static str {
+    SHADOWS_GLSL
+}
+
+/// 摄像机的透视投影参数，用于划分级联阴影
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projection {
+    /// 垂直视角(弧度)
+    pub fov: f32,
+    /// 纵横比
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Projection {
+    pub fn new(fov: f32, aspect: f32, near: f32, far: f32) -> Self {
+        Self {
+            fov,
+            aspect,
+            near,
+            far,
+        }
+    }
+
+    /// 透视投影矩阵
+    pub fn matrix(&self) -> Mat4<f32> {
+        perspective(self.fov, self.aspect, self.near, self.far)
+    }
+}
+
+/// 阴影参数
+///
+/// 默认值为：2048 分辨率、深度偏移 0.005、法线偏移 0.02、3x3 PCF、4 级级联、
+/// 划分系数 0.75、阴影距离 100
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::shadow::ShadowSettings;
+///
+/// let settings = ShadowSettings::default()
+///     .resolution(4096)
+///     .pcf_radius(2)
+///     .cascades(3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowSettings {
+    /// 每张阴影贴图的边长
+    pub resolution: u32,
+    /// 深度比较时的偏移，用于消除阴影失真(shadow acne)
+    pub bias: f32,
+    /// 采样位置沿法线方向的偏移
+    pub normal_bias: f32,
+    /// PCF 过滤半径，`0` 表示不过滤，`n` 表示 `(2n + 1)²` 次采样
+    pub pcf_radius: u32,
+    /// 方向光的级联数量，取值为 1 至 `MAX_SHADOW_CASCADES`；聚光灯始终只使用一级
+    pub cascades: usize,
+    /// 级联划分系数，`0` 为均匀划分，`1` 为对数划分
+    pub split_lambda: f32,
+    /// 阴影的最大距离，方向光为距摄像机的距离，聚光灯为投影的远裁切面
+    pub max_distance: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            bias: 0.005,
+            normal_bias: 0.02,
+            pcf_radius: 1,
+            cascades: 4,
+            split_lambda: 0.75,
+            max_distance: 100.0,
+        }
+    }
+}
+
+impl ShadowSettings {
+    /// 设置阴影贴图边长
+    pub fn resolution(mut self, resolution: u32) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// 设置深度偏移
+    pub fn bias(mut self, bias: f32) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    /// 设置法线偏移
+    pub fn normal_bias(mut self, normal_bias: f32) -> Self {
+        self.normal_bias = normal_bias;
+        self
+    }
+
+    /// 设置 PCF 过滤半径
+    pub fn pcf_radius(mut self, pcf_radius: u32) -> Self {
+        self.pcf_radius = pcf_radius;
+        self
+    }
+
+    /// 设置方向光的级联数量
+    pub fn cascades(mut self, cascades: usize) -> Self {
+        self.cascades = cascades;
+        self
+    }
+
+    /// 设置级联划分系数
+    pub fn split_lambda(mut self, split_lambda: f32) -> Self {
+        self.split_lambda = split_lambda;
+        self
+    }
+
+    /// 设置阴影的最大距离
+    pub fn max_distance(mut self, max_distance: f32) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+}
+
+/// 以对数划分与均匀划分的加权平均计算各级联的远端距离
+///
+/// # 参数 Parameters
+/// * `near` - 近裁切面
+/// * `far` - 阴影的最大距离
+/// * `count` - 级联数量
+/// * `lambda` - 对数划分所占的权重
+pub fn cascade_splits(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    (1..=count)
+        .map(|i| {
+            let t = i as f32 / count as f32;
+            let log = near * (far / near).powf(t);
+            let uniform = near + (far - near) * t;
+            lambda * log + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// 生成正交投影矩阵
+pub fn orthographic(
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+    near: f32,
+    far: f32,
+) -> Mat4<f32> {
+    Mat4::from([
+        [
+            2.0 / (right - left),
+            0.0,
+            0.0,
+            -(right + left) / (right - left),
+        ],
+        [
+            0.0,
+            2.0 / (top - bottom),
+            0.0,
+            -(top + bottom) / (top - bottom),
+        ],
+        [0.0, 0.0, -2.0 / (far - near), -(far + near) / (far - near)],
+        [0.0, 0.0, 0.0, 1.0],
+    ])
+}
+
+/// 摄像机视锥在 `near` 至 `far` 区间内的 8 个顶点(世界空间)
+fn frustum_corners(view: Mat4<f32>, projection: &Projection, near: f32, far: f32) -> [[f32; 3]; 8] {
+    let tan = (projection.fov / 2.0).tan();
+    let mut corners = [[0.0; 3]; 8];
+    let mut k = 0;
+    for d in [near, far] {
+        for sx in [-1.0, 1.0] {
+            for sy in [-1.0, 1.0] {
+                let v = [sx * d * tan * projection.aspect, sy * d * tan, -d];
+                // 视图矩阵为 [R | t]，世界坐标为 Rᵀ(v - t)
+                for (j, c) in corners[k].iter_mut().enumerate() {
+                    *c = (0..3).map(|i| view[i][j] * (v[i] - view[i][3])).sum();
+                }
+                k += 1;
+            }
+        }
+    }
+    corners
+}
+
+/// 与光线方向不平行的上方向
+fn up_for(direction: [f32; 3]) -> Vec3<f32> {
+    let len = direction.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if len > 0.0 && (direction[1] / len).abs() > 0.99 {
+        Vec3::from([0.0, 0.0, 1.0])
+    } else {
+        Vec3::from([0.0, 1.0, 0.0])
+    }
+}
+
+/// 包围一段视锥的方向光光照空间矩阵
+///
+/// 正交投影以视锥的包围球为范围，并将原点对齐到阴影贴图的纹素，
+/// 以避免摄像机移动时阴影边缘闪烁
+fn directional_matrix(direction: [f32; 3], corners: &[[f32; 3]; 8], resolution: u32) -> Mat4<f32> {
+    let mut center = [0.0; 3];
+    for corner in corners {
+        for i in 0..3 {
+            center[i] += corner[i] / 8.0;
+        }
+    }
+    let radius = corners
+        .iter()
+        .map(|c| {
+            (0..3)
+                .map(|i| (c[i] - center[i]).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        })
+        .fold(0.0, f32::max);
+    // 量化半径，使投影范围不随摄像机旋转而抖动
+    let radius = (radius * 16.0).ceil() / 16.0;
+    let len = direction
+        .iter()
+        .map(|v| v * v)
+        .sum::<f32>()
+        .sqrt()
+        .max(1e-6);
+    let eye = [0, 1, 2].map(|i| center[i] - direction[i] / len * radius);
+    let light_view = look_at(Vec3::from(eye), Vec3::from(center), up_for(direction));
+    let projection = orthographic(
+        -radius,
+        radius,
+        -radius,
+        radius,
+        -radius * CASTER_MARGIN,
+        radius * 2.0,
+    );
+    let mut matrix = projection * light_view;
+    // 将世界原点对齐到纹素
+    let half = resolution as f32 / 2.0;
+    for i in 0..2 {
+        let origin = matrix[i][3] * half;
+        matrix[i][3] += (origin.round() - origin) / half;
+    }
+    matrix
+}
+
+/// 聚光灯的光照空间矩阵
+fn spot_matrix(light: &SpotLight, far: f32) -> Mat4<f32> {
+    let p = light.position;
+    let d = light.direction;
+    let view = look_at(
+        Vec3::from(p),
+        Vec3::from([p[0] + d[0], p[1] + d[1], p[2] + d[2]]),
+        up_for(d),
+    );
+    let fov = (light.outer_angle.max(light.inner_angle) * 2.0).clamp(1.0, 170.0);
+    perspective(fov.to_radians(), 1.0, SPOT_NEAR, far.max(SPOT_NEAR * 2.0)) * view
+}
+
+/// 由渲染线程注册深度着色器
+fn register_shader() -> Result<(), String> {
+    if SHADER_MANAGER.handle(DEPTH_SHADER).is_none() {
+        SHADER_MANAGER.add_from_source(DEPTH_SHADER, DEPTH_VERT, DEPTH_FRAG);
+        if SHADER_MANAGER.handle(DEPTH_SHADER).is_none() {
+            return Err(format!("内置着色器 {} 加载失败", DEPTH_SHADER));
+        }
+    }
+    Ok(())
+}
+
+/// 方向光或聚光灯的阴影贴图
+///
+/// 从光源视角将场景深度渲染到深度纹理中，方向光按摄像机视锥划分为多级级联；
+/// 着色器通过 `#include <shadows>` 引入 `computeShadow` 函数，并由 `bind` 设置参数
+///
+/// # 示例 Examples
+/// ```ignore
+/// let mut shadow = ShadowMap::new("sun", &ShadowSettings::default())?;
+/// let projection = Projection::new(radian(45.0), w as f32 / h as f32, 0.1, 500.0);
+/// // 渲染循环
+/// shadow.update(CAMERA_SYSTEM.view_matrix(), &projection);
+/// shadow.render(|depth| {
+///     depth.set_uniform("model", model);
+///     MODEL_MANAGER.draw_geometry("terrain");
+/// });
+/// let shader = MATERIAL_MANAGER.apply_material("terrain").unwrap();
+/// shadow.bind(&shader, 8);
+/// MODEL_MANAGER.draw_geometry("terrain");
+/// ```
+/// 片段着色器中：
+/// ```glsl
+/// #include <lights>
+/// #include <shadows>
+/// // ...
+/// float shadow = computeShadow(FragPos, normal, directionalLights[0].direction.xyz);
+/// ```
+pub struct ShadowMap {
+    light: String,
+    settings: ShadowSettings,
+    targets: Vec<Framebuffer>,
+    /// 各级联的光照空间矩阵，为空表示尚未更新或光源无效
+    matrices: Vec<Mat4<f32>>,
+    /// 各级联在观察空间中的远端距离
+    splits: Vec<f32>,
+    camera_view: Mat4<f32>,
+}
+
+impl ShadowMap {
+    /// 为 `LIGHT_SYSTEM` 中的光源创建阴影贴图
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `light` - 方向光或聚光灯的名称，深度纹理以 `shadow/名称/级联序号.depth` 登记于 `TEXTURE_MANAGER`
+    /// * `settings` - 阴影参数
+    pub fn new(light: &str, settings: &ShadowSettings) -> Result<Self, String> {
+        register_shader()?;
+        let mut shadow = Self {
+            light: light.to_string(),
+            settings: *settings,
+            targets: vec![],
+            matrices: vec![],
+            splits: vec![],
+            camera_view: Mat4::I(),
+        };
+        shadow.build()?;
+        Ok(shadow)
+    }
+
+    fn build(&mut self) -> Result<(), String> {
+        let settings = &mut self.settings;
+        settings.cascades = settings.cascades.clamp(1, MAX_SHADOW_CASCADES);
+        settings.resolution = settings.resolution.max(1);
+        let resolution = settings.resolution;
+        self.targets = (0..settings.cascades)
+            .map(|i| {
+                FramebufferBuilder::new(&format!("shadow/{}/{}", self.light, i))
+                    .size(FramebufferSize::Fixed(resolution, resolution))
+                    .depth_texture(DepthFormat::Depth32F)
+                    .build()
+            })
+            .collect::<Result<_, _>>()?;
+        self.matrices.clear();
+        Ok(())
+    }
+
+    /// 当前的阴影参数
+    pub fn settings(&self) -> &ShadowSettings {
+        &self.settings
+    }
+
+    /// 修改阴影参数，分辨率或级联数量变化时重新创建阴影贴图
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn set_settings(&mut self, settings: &ShadowSettings) -> Result<(), String> {
+        let rebuild = settings.resolution != self.settings.resolution
+            || settings.cascades.clamp(1, MAX_SHADOW_CASCADES) != self.targets.len();
+        self.settings = *settings;
+        if rebuild {
+            self.build()?;
+        }
+        Ok(())
+    }
+
+    /// 光源名称
+    pub fn light(&self) -> &str {
+        &self.light
+    }
+
+    /// 按光源与摄像机的当前状态更新光照空间矩阵
+    ///
+    /// # 参数 Parameters
+    /// * `view` - 摄像机的视图矩阵
+    /// * `projection` - 摄像机的投影参数，方向光按此划分级联
+    ///
+    /// # 返回值 Returns
+    /// 光源不存在或不是方向光、聚光灯时返回 `false`，此时 `computeShadow` 始终返回 0；
+    /// 每个光源名称只警告一次，光源恢复有效后再次失效时重新警告
+    pub fn update(&mut self, view: Mat4<f32>, projection: &Projection) -> bool {
+        self.camera_view = view;
+        self.matrices.clear();
+        self.splits.clear();
+        match LIGHT_SYSTEM.get(&self.light) {
+            Some(Light::Directional(light)) => {
+                let far = projection.far.min(self.settings.max_distance);
+                let splits = cascade_splits(
+                    projection.near,
+                    far,
+                    self.targets.len(),
+                    self.settings.split_lambda,
+                );
+                let mut near = projection.near;
+                for &split in &splits {
+                    let corners = frustum_corners(view, projection, near, split);
+                    self.matrices.push(directional_matrix(
+                        light.direction,
+                        &corners,
+                        self.settings.resolution,
+                    ));
+                    near = split;
+                }
+                self.splits = splits;
+            }
+            Some(Light::Spot(light)) => {
+                self.matrices
+                    .push(spot_matrix(&light, self.settings.max_distance));
+                self.splits.push(f32::MAX);
+            }
+            Some(Light::Point(_)) => {
+                INVALID_LIGHT.apply(|set| {
+                    if set.insert(self.light.clone()) {
+                        warn!("ShadowMap", "点光源 {} 不支持阴影贴图", self.light);
+                    }
+                });
+                return false;
+            }
+            None => {
+                INVALID_LIGHT.apply(|set| {
+                    if set.insert(self.light.clone()) {
+                        warn!("ShadowMap", "没有名为 {} 的光源", self.light);
+                    }
+                });
+                return false;
+            }
+        }
+        INVALID_LIGHT.apply(|set| {
+            set.remove(&self.light);
+        });
+        true
+    }
+
+    /// 级联数量，尚未更新或光源无效时为 0
+    pub fn cascade_count(&self) -> usize {
+        self.matrices.len()
+    }
+
+    /// 指定级联的光照空间矩阵
+    pub fn light_space_matrix(&self, cascade: usize) -> Option<Mat4<f32>> {
+        self.matrices.get(cascade).copied()
+    }
+
+    /// 指定级联的远端距离(观察空间)
+    pub fn split(&self, cascade: usize) -> Option<f32> {
+        self.splits.get(cascade).copied()
+    }
+
+    /// 指定级联的深度纹理
+    pub fn depth(&self, cascade: usize) -> Option<Handle<Texture>> {
+        self.targets.get(cascade).and_then(|target| target.depth())
+    }
+
+    /// 从光源视角渲染场景深度
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 每个级联调用一次 `draw`，参数为已设置 `lightSpaceMatrix` 的深度着色器，
+    /// 绘制时需设置 `model` 并只绘制几何体(例如 `MODEL_MANAGER.draw_geometry`)；
+    /// 结束后恢复原有的帧缓冲、视口与深度测试等管线状态
+    ///
+    /// # 参数 Parameters
+    /// * `draw` - 绘制投射阴影的物体
+    pub fn render<F: FnMut(&Shader)>(&mut self, mut draw: F) {
+        let shader = match SHADER_MANAGER.get(DEPTH_SHADER) {
+            Some(shader) => shader,
+            None => return,
+        };
+        let mut framebuffer = 0;
+        unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut framebuffer);
+        }
+        let pipeline = GL_STATE.pipeline();
+        GL_STATE.enable(Capability::DepthTest);
+        GL_STATE.set_depth_mask(true);
+        shader.use_program();
+        for (target, matrix) in self.targets.iter_mut().zip(&self.matrices) {
+            target.bind();
+            unsafe {
+                gl::Clear(gl::DEPTH_BUFFER_BIT);
+            }
+            shader.set_uniform("lightSpaceMatrix", *matrix);
+            draw(&shader);
+        }
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as GLuint);
+        }
+        GL_STATE.set_pipeline(&pipeline);
+    }
+
+    /// 绑定阴影贴图并设置 `shadows.glsl` 中的全部参数
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，着色器需已启用
+    ///
+    /// # 参数 Parameters
+    /// * `shader` - 引入了 `#include <shadows>` 的着色器
+    /// * `first_unit` - 第一张阴影贴图使用的纹理单元，共占用 `MAX_SHADOW_CASCADES` 个单元
+    pub fn bind(&self, shader: &Shader, first_unit: u32) {
+        let program = shader.id();
+        let texel = 1.0 / self.settings.resolution as f32;
+        unsafe {
+            for i in 0..MAX_SHADOW_CASCADES {
+                let unit = first_unit + i as u32;
+                // 未使用的采样器也需要指向独立的纹理单元
+                let target = self.targets.get(i).or(self.targets.last());
+                if let Some(target) = target {
+                    target.bind_depth(unit);
+                }
+                let _ = gl_utils::set_uniform(program, &format!("shadowMaps[{}]", i), unit as i32);
+                if let Some(matrix) = self.matrices.get(i) {
+                    let _ = gl_utils::set_uniform(
+                        program,
+                        &format!("lightSpaceMatrices[{}]", i),
+                        *matrix,
+                    );
+                    let _ = gl_utils::set_uniform(
+                        program,
+                        &format!("cascadeSplits[{}]", i),
+                        self.splits[i],
+                    );
+                }
+            }
+            let _ = gl_utils::set_uniform(program, "cascadeCount", self.matrices.len() as i32);
+            let _ = gl_utils::set_uniform(program, "shadowCameraView", self.camera_view);
+            let _ = gl_utils::set_uniform(program, "shadowBias", self.settings.bias);
+            let _ = gl_utils::set_uniform(program, "shadowNormalBias", self.settings.normal_bias);
+            let _ = gl_utils::set_uniform(program, "pcfRadius", self.settings.pcf_radius as i32);
+            let _ = gl_utils::set_uniform(program, "shadowTexelSize", Vec2::from([texel, texel]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mats::radian;
+
+    fn transform(m: Mat4<f32>, p: [f32; 3]) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        for (i, o) in out.iter_mut().enumerate() {
+            *o = m[i][0] * p[0] + m[i][1] * p[1] + m[i][2] * p[2] + m[i][3];
+        }
+        out
+    }
+
+    #[test]
+    fn test_cascade_splits() {
+        let splits = cascade_splits(0.1, 100.0, 4, 0.75);
+        assert_eq!(splits.len(), 4);
+        assert!((splits[3] - 100.0).abs() < 1e-3);
+        assert!(splits.windows(2).all(|w| w[0] < w[1]));
+        let uniform = cascade_splits(0.0001, 100.0, 4, 0.0);
+        assert!((uniform[0] - 25.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_orthographic() {
+        let m = orthographic(-2.0, 2.0, -1.0, 1.0, 1.0, 11.0);
+        assert_eq!(transform(m, [2.0, 1.0, -1.0]), [1.0, 1.0, -1.0, 1.0]);
+        assert_eq!(transform(m, [-2.0, -1.0, -11.0]), [-1.0, -1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_frustum_corners() {
+        // 摄像机位于 (0, 0, 5)，朝向 -z
+        let view = Mat4::from([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, -5.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let projection = Projection::new(radian(90.0), 2.0, 0.1, 100.0);
+        let corners = frustum_corners(view, &projection, 1.0, 10.0);
+        assert!((corners[0][0] + 2.0).abs() < 1e-5);
+        assert!((corners[0][1] + 1.0).abs() < 1e-5);
+        assert!((corners[0][2] - 4.0).abs() < 1e-5);
+        assert!((corners[7][0] - 20.0).abs() < 1e-4);
+        assert!((corners[7][2] + 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_directional_matrix_contains_frustum() {
+        let view = Mat4::I();
+        let projection = Projection::new(radian(60.0), 1.5, 0.1, 100.0);
+        let corners = frustum_corners(view, &projection, 0.1, 20.0);
+        for direction in [[0.3, -1.0, 0.2], [0.0, -1.0, 0.0], [1.0, 0.0, 0.0]] {
+            let m = directional_matrix(direction, &corners, 1024);
+            for corner in &corners {
+                let p = transform(m, *corner);
+                for v in &p[..3] {
+                    assert!(v.abs() <= 1.0 + 1e-3, "{:?} -> {:?}", corner, p);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+// 由 ShadowMap::bind 设置的阴影参数，数组长度必须与 shadow/mod.rs 一致
+#define MAX_SHADOW_CASCADES 4
+
+uniform sampler2D shadowMaps[MAX_SHADOW_CASCADES];
+uniform mat4 lightSpaceMatrices[MAX_SHADOW_CASCADES];
+// 各级联在观察空间中的远端距离
+uniform float cascadeSplits[MAX_SHADOW_CASCADES];
+uniform int cascadeCount;
+uniform mat4 shadowCameraView;
+uniform float shadowBias;
+uniform float shadowNormalBias;
+uniform int pcfRadius;
+uniform vec2 shadowTexelSize;
+
+// 采样器数组只能以常量下标访问
+float shadowDepth(int cascade, vec2 uv){
+    if(cascade == 0) return texture(shadowMaps[0], uv).r;
+    if(cascade == 1) return texture(shadowMaps[1], uv).r;
+    if(cascade == 2) return texture(shadowMaps[2], uv).r;
+    return texture(shadowMaps[3], uv).r;
+}
+
+// 返回片段处于阴影中的比例，0 为完全受光，1 为完全处于阴影中
+// lightDir 为光线的传播方向
+float computeShadow(vec3 fragPos, vec3 normal, vec3 lightDir){
+    if(cascadeCount == 0) return 0.0;
+    float viewDepth = -(shadowCameraView * vec4(fragPos, 1.0)).z;
+    int cascade = cascadeCount - 1;
+    for(int i = 0; i < cascadeCount; i++){
+        if(viewDepth < cascadeSplits[i]){
+            cascade = i;
+            break;
+        }
+    }
+    vec4 lightSpacePos = lightSpaceMatrices[cascade] * vec4(fragPos + normal * shadowNormalBias, 1.0);
+    vec3 coords = lightSpacePos.xyz / lightSpacePos.w * 0.5 + 0.5;
+    if(coords.z > 1.0 || any(lessThan(coords.xy, vec2(0.0))) || any(greaterThan(coords.xy, vec2(1.0))))
+        return 0.0;
+    // 表面越倾斜，所需的偏移越大
+    float bias = max(shadowBias * (1.0 - dot(normal, -lightDir)), shadowBias * 0.1);
+    float shadow = 0.0;
+    for(int x = -pcfRadius; x <= pcfRadius; x++){
+        for(int y = -pcfRadius; y <= pcfRadius; y++){
+            float depth = shadowDepth(cascade, coords.xy + vec2(x, y) * shadowTexelSize);
+            shadow += coords.z - bias > depth ? 1.0 : 0.0;
+        }
+    }
+    float taps = float((2 * pcfRadius + 1) * (2 * pcfRadius + 1));
+    return shadow / taps;
+}
@@ -0,0 +1,141 @@
+use gl::types::*;
+use mats::Mat4;
+
+use crate::{gl_state::DepthFunc, gl_utils, Shader, CAMERA_SYSTEM, GL_STATE, TEXTURE_MANAGER};
+
+const VERTEX_SHADER: &str = include_str!("skybox.vert");
+const FRAGMENT_SHADER: &str = include_str!("skybox.frag");
+
+/// 单位立方体的 36 个顶点，仅含位置
+#[rustfmt::skip]
+const CUBE_VERTICES: [f32; 108] = [
+    -1.0,  1.0, -1.0,  -1.0, -1.0, -1.0,   1.0, -1.0, -1.0,
+     1.0, -1.0, -1.0,   1.0,  1.0, -1.0,  -1.0,  1.0, -1.0,
+
+    -1.0, -1.0,  1.0,  -1.0, -1.0, -1.0,  -1.0,  1.0, -1.0,
+    -1.0,  1.0, -1.0,  -1.0,  1.0,  1.0,  -1.0, -1.0,  1.0,
+
+     1.0, -1.0, -1.0,   1.0, -1.0,  1.0,   1.0,  1.0,  1.0,
+     1.0,  1.0,  1.0,   1.0,  1.0, -1.0,   1.0, -1.0, -1.0,
+
+    -1.0, -1.0,  1.0,  -1.0,  1.0,  1.0,   1.0,  1.0,  1.0,
+     1.0,  1.0,  1.0,   1.0, -1.0,  1.0,  -1.0, -1.0,  1.0,
+
+    -1.0,  1.0, -1.0,   1.0,  1.0, -1.0,   1.0,  1.0,  1.0,
+     1.0,  1.0,  1.0,  -1.0,  1.0,  1.0,  -1.0,  1.0, -1.0,
+
+    -1.0, -1.0, -1.0,  -1.0, -1.0,  1.0,   1.0, -1.0, -1.0,
+     1.0, -1.0, -1.0,  -1.0, -1.0,  1.0,   1.0, -1.0,  1.0,
+];
+
+/// 移除视图矩阵中的平移分量，仅保留旋转
+pub fn remove_translation(mut view: Mat4<f32>) -> Mat4<f32> {
+    for i in 0..3 {
+        view[i][3] = 0.0;
+    }
+    view
+}
+
+/// 天空盒
+///
+/// 使用内嵌的着色器渲染 `TEXTURE_MANAGER` 中的立方体贴图，不依赖 `shader/` 目录中的文件
+///
+/// # 示例 Examples
+/// ```ignore
+/// // 初始化
+/// TEXTURE_MANAGER.load_cubemap("sky", "texture/sky/");
+/// let skybox = Skybox::new("sky").unwrap();
+/// // 渲染循环中，在绘制完不透明物体之后
+/// skybox.draw(proj);
+/// ```
+pub struct Skybox {
+    cubemap: String,
+    shader: Shader,
+    vao: GLuint,
+    vbo: GLuint,
+}
+
+impl Skybox {
+    /// 创建天空盒
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `cubemap` - `TEXTURE_MANAGER` 中立方体贴图的名称
+    pub fn new(cubemap: &str) -> Result<Self, String> {
+        let shader = Shader::from_source(VERTEX_SHADER, FRAGMENT_SHADER)?;
+        let (vao, vbo) =
+            unsafe { gl_utils::create_array_model_context(&CUBE_VERTICES.to_vec(), "3f") };
+        Ok(Self {
+            cubemap: cubemap.to_string(),
+            shader,
+            vao,
+            vbo,
+        })
+    }
+
+    /// 更换天空盒使用的立方体贴图
+    pub fn set_cubemap(&mut self, cubemap: &str) {
+        self.cubemap = cubemap.to_string();
+    }
+
+    /// 以当前摄像机的视角渲染天空盒
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 应在绘制完不透明物体之后调用，以便深度测试剔除被遮挡的部分
+    ///
+    /// # 参数 Parameters
+    /// * `projection` - 投影矩阵
+    pub fn draw(&self, projection: Mat4<f32>) {
+        self.draw_with(CAMERA_SYSTEM.view_matrix(), projection);
+    }
+
+    /// 以指定的视图矩阵渲染天空盒，视图矩阵中的平移分量将被忽略
+    pub fn draw_with(&self, view: Mat4<f32>, projection: Mat4<f32>) {
+        self.shader.use_program();
+        self.shader.set_uniform("view", remove_translation(view));
+        self.shader.set_uniform("projection", projection);
+        self.shader.set_uniform("skybox", 0);
+        TEXTURE_MANAGER.bind(&self.cubemap, 0);
+        let depth_func = GL_STATE.depth_func();
+        GL_STATE.set_depth_func(DepthFunc::LessEqual);
+        GL_STATE.bind_vertex_array(self.vao);
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, (CUBE_VERTICES.len() / 3) as i32);
+        }
+        GL_STATE.bind_vertex_array(0);
+        GL_STATE.set_depth_func(depth_func);
+    }
+}
+
+impl Drop for Skybox {
+    fn drop(&mut self) {
+        GL_STATE.delete_vertex_array(self.vao);
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mats::{rotate3, tranlate3, Vec3};
+
+    #[test]
+    fn test_remove_translation() {
+        let rotation = rotate3(0.5, Vec3::from([0.0, 1.0, 0.0]));
+        let view = rotation * tranlate3(Vec3::from([1.0, 2.0, 3.0]));
+        let view = remove_translation(view);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((view[i][j] - rotation[i][j]).abs() < 1e-6);
+            }
+        }
+    }
+}
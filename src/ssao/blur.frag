@@ -0,0 +1,20 @@
+#version 330 core
+
+in vec2 TexCoords;
+out float FragColor;
+
+uniform sampler2D aoTexture;
+// 模糊范围与噪声纹理尺寸一致，恰好消除噪声的重复图案
+uniform int noiseSize;
+
+void main(){
+    vec2 texelSize = 1.0 / vec2(textureSize(aoTexture, 0));
+    float result = 0.0;
+    int halfSize = noiseSize / 2;
+    for(int x = -halfSize; x < noiseSize - halfSize; ++x){
+        for(int y = -halfSize; y < noiseSize - halfSize; ++y){
+            result += texture(aoTexture, TexCoords + vec2(x, y) * texelSize).r;
+        }
+    }
+    FragColor = result / float(noiseSize * noiseSize);
+}
@@ -0,0 +1,399 @@
+use gl::types::*;
+use mats::{Mat4, Vec2, Vec3};
+
+use crate::{
+    gl_state::Capability,
+    postprocess,
+    texture::{Filter, PixelFormat, TextureOptions, Wrap},
+    Framebuffer, FramebufferBuilder, FramebufferSize, Handle, Texture, ASSETS, GL_STATE,
+    SHADER_MANAGER, TEXTURE_MANAGER,
+};
+
+const SSAO_FRAG: &str = include_str!("ssao.frag");
+const BLUR_FRAG: &str = include_str!("blur.frag");
+
+const SSAO_SHADER: &str = "ssao/ssao";
+const BLUR_SHADER: &str = "ssao/blur";
+
+/// 采样核长度的上限，与 `ssao.frag` 中的数组长度一致
+pub const MAX_KERNEL_SIZE: usize = 64;
+/// 噪声纹理的边长
+const NOISE_SIZE: u32 = 4;
+
+/// 以异或移位生成的伪随机数，保证每次生成的采样核一致
+struct XorShift(u32);
+
+impl XorShift {
+    /// `[0, 1)` 区间内的随机数
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+/// 生成切线空间中朝向 +z 的半球采样核
+///
+/// 采样点位于单位半球内，并向原点集中，使近处的遮挡权重更高
+pub fn generate_kernel(size: usize) -> Vec<[f32; 3]> {
+    let mut rng = XorShift(0x9E37_79B9);
+    (0..size)
+        .map(|i| {
+            let v = [
+                rng.next() * 2.0 - 1.0,
+                rng.next() * 2.0 - 1.0,
+                rng.next().max(0.05),
+            ];
+            let len = v.iter().map(|c| c * c).sum::<f32>().sqrt();
+            let t = i as f32 / size as f32;
+            let scale = (0.1 + 0.9 * t * t) * rng.next().max(0.1) / len;
+            v.map(|c| c * scale)
+        })
+        .collect()
+}
+
+/// 生成绕切线空间 z 轴旋转采样核的噪声向量
+fn generate_noise(count: usize) -> Vec<[f32; 3]> {
+    let mut rng = XorShift(0x2545_F491);
+    (0..count)
+        .map(|_| [rng.next() * 2.0 - 1.0, rng.next() * 2.0 - 1.0, 0.0])
+        .collect()
+}
+
+/// 环境光遮蔽参数
+///
+/// 默认值为：32 个采样点、采样半径 0.5、深度偏移 0.025、强度指数 1、启用模糊、全分辨率
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::ssao::SsaoSettings;
+///
+/// let settings = SsaoSettings::default().kernel_size(16).radius(1.0).scale(0.5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsaoSettings {
+    /// 采样点数量，取值为 1 至 `MAX_KERNEL_SIZE`
+    pub kernel_size: usize,
+    /// 采样半球的半径(观察空间单位)
+    pub radius: f32,
+    /// 深度比较时的偏移，用于消除自遮挡
+    pub bias: f32,
+    /// 遮蔽结果的指数，越大遮蔽越明显
+    pub power: f32,
+    /// 是否模糊遮蔽结果以消除噪声图案
+    pub blur: bool,
+    /// 遮蔽纹理相对窗口的缩放比例
+    pub scale: f32,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self {
+            kernel_size: 32,
+            radius: 0.5,
+            bias: 0.025,
+            power: 1.0,
+            blur: true,
+            scale: 1.0,
+        }
+    }
+}
+
+impl SsaoSettings {
+    /// 设置采样点数量
+    pub fn kernel_size(mut self, kernel_size: usize) -> Self {
+        self.kernel_size = kernel_size;
+        self
+    }
+
+    /// 设置采样半径
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// 设置深度偏移
+    pub fn bias(mut self, bias: f32) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    /// 设置强度指数
+    pub fn power(mut self, power: f32) -> Self {
+        self.power = power;
+        self
+    }
+
+    /// 设置是否模糊
+    pub fn blur(mut self, blur: bool) -> Self {
+        self.blur = blur;
+        self
+    }
+
+    /// 设置遮蔽纹理相对窗口的缩放比例
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// 环境光遮蔽的输入
+#[derive(Clone, Copy)]
+pub enum SsaoInput<'a> {
+    /// 按 `DeferredRenderer` 约定的 G-buffer，使用其世界空间法线(`color1`)与位置(`color2`)
+    GBuffer(&'a Framebuffer),
+    /// 带深度纹理附件的帧缓冲(例如深度预渲染)，由深度重建位置与法线，仅支持对称透视投影
+    Depth(&'a Framebuffer),
+}
+
+/// 由渲染线程注册环境光遮蔽着色器
+fn register_shaders() -> Result<(), String> {
+    postprocess::register_shaders(&[(SSAO_SHADER, SSAO_FRAG), (BLUR_SHADER, BLUR_FRAG)])
+}
+
+/// 屏幕空间环境光遮蔽
+///
+/// 以半球采样核估计每个像素被周围几何体遮挡的程度，结果为单通道纹理，
+/// 1 表示无遮蔽；光照着色器可采样 `texture_name()` 对应的纹理调制环境光
+///
+/// # 示例 Examples
+/// ```ignore
+/// let mut deferred = DeferredRenderer::new("gbuffer")?;
+/// let mut ssao = Ssao::new("ssao", &SsaoSettings::default())?;
+/// deferred.set_ambient_occlusion(Some(&ssao.texture_name()));
+/// // 渲染循环
+/// deferred.begin_geometry();
+/// draw_scene();
+/// deferred.end_geometry();
+/// ssao.render(SsaoInput::GBuffer(deferred.gbuffer()), view, projection);
+/// deferred.lighting(view, projection);
+/// ```
+pub struct Ssao {
+    settings: SsaoSettings,
+    kernel: Vec<[f32; 3]>,
+    noise: Handle<Texture>,
+    target: Framebuffer,
+    blurred: Framebuffer,
+    /// 绘制全屏三角形使用的空顶点数组对象
+    vao: GLuint,
+}
+
+impl Ssao {
+    /// 创建环境光遮蔽
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 名称，噪声纹理与结果纹理以此为前缀登记于 `TEXTURE_MANAGER`
+    /// * `settings` - 参数
+    pub fn new(name: &str, settings: &SsaoSettings) -> Result<Self, String> {
+        register_shaders()?;
+        let mut settings = *settings;
+        settings.kernel_size = settings.kernel_size.clamp(1, MAX_KERNEL_SIZE);
+        let noise = generate_noise((NOISE_SIZE * NOISE_SIZE) as usize)
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect::<Vec<_>>();
+        let options = TextureOptions::default()
+            .filter(Filter::Nearest)
+            .wrap(Wrap::Repeat)
+            .mipmaps(false)
+            .half_float(false);
+        let noise = Texture::from_raw(
+            NOISE_SIZE,
+            NOISE_SIZE,
+            PixelFormat::Rgb32F,
+            &noise,
+            &options,
+        )?;
+        let noise = TEXTURE_MANAGER.add(&format!("{}.noise", name), noise);
+        let size = FramebufferSize::Scaled(settings.scale);
+        let target = FramebufferBuilder::new(name)
+            .size(size)
+            .color(PixelFormat::R8)
+            .build()?;
+        let blurred = FramebufferBuilder::new(&format!("{}.blur", name))
+            .size(size)
+            .color(PixelFormat::R8)
+            .build()?;
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        Ok(Self {
+            kernel: generate_kernel(settings.kernel_size),
+            settings,
+            noise,
+            target,
+            blurred,
+            vao,
+        })
+    }
+
+    /// 当前的参数
+    pub fn settings(&self) -> &SsaoSettings {
+        &self.settings
+    }
+
+    /// 修改参数，在下次 `render` 时生效
+    pub fn set_settings(&mut self, settings: &SsaoSettings) {
+        let mut settings = *settings;
+        settings.kernel_size = settings.kernel_size.clamp(1, MAX_KERNEL_SIZE);
+        if settings.kernel_size != self.settings.kernel_size {
+            self.kernel = generate_kernel(settings.kernel_size);
+        }
+        if settings.scale != self.settings.scale {
+            let size = FramebufferSize::Scaled(settings.scale);
+            self.target.set_size(size);
+            self.blurred.set_size(size);
+        }
+        self.settings = settings;
+    }
+
+    /// 结果纹理在 `TEXTURE_MANAGER` 中的名称，随是否模糊而变化
+    pub fn texture_name(&self) -> String {
+        let target = if self.settings.blur {
+            &self.blurred
+        } else {
+            &self.target
+        };
+        target.color_name(0).unwrap_or_default()
+    }
+
+    /// 结果纹理
+    pub fn texture(&self) -> Option<Handle<Texture>> {
+        if self.settings.blur {
+            self.blurred.color(0)
+        } else {
+            self.target.color(0)
+        }
+    }
+
+    /// 将结果纹理绑定到指定纹理单元
+    pub fn bind(&self, unit: u32) {
+        if self.settings.blur {
+            self.blurred.bind_color(0, unit);
+        } else {
+            self.target.bind_color(0, unit);
+        }
+    }
+
+    /// 计算环境光遮蔽
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，结束后恢复原有的帧缓冲与视口
+    ///
+    /// # 参数 Parameters
+    /// * `input` - 几何信息的来源
+    /// * `view` - 摄像机的视图矩阵
+    /// * `projection` - 摄像机的投影矩阵
+    pub fn render(&mut self, input: SsaoInput, view: Mat4<f32>, projection: Mat4<f32>) {
+        let (ssao, blur) = match (
+            SHADER_MANAGER.get(SSAO_SHADER),
+            SHADER_MANAGER.get(BLUR_SHADER),
+        ) {
+            (Some(ssao), Some(blur)) => (ssao, blur),
+            _ => return,
+        };
+        let mut framebuffer = 0;
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut framebuffer);
+        }
+        let pipeline = GL_STATE.pipeline();
+
+        self.target.bind();
+        ssao.use_program();
+        match input {
+            SsaoInput::GBuffer(gbuffer) => {
+                gbuffer.bind_color(2, 0);
+                gbuffer.bind_color(1, 1);
+                ssao.set_uniform("inputMode", 0);
+            }
+            SsaoInput::Depth(framebuffer) => {
+                framebuffer.bind_depth(2);
+                ssao.set_uniform("inputMode", 1);
+            }
+        }
+        ASSETS.with(&self.noise, |texture| texture.bind(3));
+        ssao.set_uniform("gPosition", 0);
+        ssao.set_uniform("gNormal", 1);
+        ssao.set_uniform("depthMap", 2);
+        ssao.set_uniform("noiseTexture", 3);
+        // 着色器由所有实例共享，每次都上传采样核
+        for (i, sample) in self.kernel.iter().enumerate() {
+            ssao.set_uniform(&format!("samples[{}]", i), Vec3::from(*sample));
+        }
+        let (width, height) = self.target.size();
+        let noise = NOISE_SIZE as f32;
+        ssao.set_uniform("kernelSize", self.kernel.len() as i32);
+        ssao.set_uniform("radius", self.settings.radius);
+        ssao.set_uniform("bias", self.settings.bias);
+        ssao.set_uniform("power", self.settings.power);
+        ssao.set_uniform(
+            "noiseScale",
+            Vec2::from([width as f32 / noise, height as f32 / noise]),
+        );
+        ssao.set_uniform("view", view);
+        ssao.set_uniform("projection", projection);
+        GL_STATE.disable(Capability::DepthTest);
+        GL_STATE.disable(Capability::Blend);
+        GL_STATE.bind_vertex_array(self.vao);
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+
+        if self.settings.blur {
+            self.blurred.bind();
+            blur.use_program();
+            self.target.bind_color(0, 0);
+            blur.set_uniform("aoTexture", 0);
+            blur.set_uniform("noiseSize", NOISE_SIZE as i32);
+            unsafe {
+                gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            }
+        }
+
+        GL_STATE.bind_vertex_array(0);
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as GLuint);
+        }
+        GL_STATE.set_pipeline(&pipeline);
+    }
+}
+
+impl Drop for Ssao {
+    fn drop(&mut self) {
+        GL_STATE.delete_vertex_array(self.vao);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernel() {
+        let kernel = generate_kernel(MAX_KERNEL_SIZE);
+        assert_eq!(kernel.len(), MAX_KERNEL_SIZE);
+        for sample in &kernel {
+            let len = sample.iter().map(|c| c * c).sum::<f32>().sqrt();
+            assert!(len <= 1.0 + 1e-5);
+            assert!(sample[2] > 0.0);
+        }
+        // 相同长度的采样核保持一致
+        assert_eq!(generate_kernel(16), generate_kernel(16));
+    }
+
+    #[test]
+    fn test_noise() {
+        let noise = generate_noise(16);
+        assert_eq!(noise.len(), 16);
+        assert!(noise.iter().all(|v| v[2] == 0.0));
+        assert!(noise.iter().any(|v| v[0] != noise[0][0]));
+    }
+}
@@ -0,0 +1,69 @@
+#version 330 core
+
+in vec2 TexCoords;
+out float FragColor;
+
+// 采样核长度的上限，必须与 ssao/mod.rs 一致
+#define MAX_KERNEL_SIZE 64
+
+// 0: G-buffer 输入(世界空间位置与法线)；1: 深度输入(由深度重建观察空间位置)
+uniform int inputMode;
+uniform sampler2D gPosition;
+uniform sampler2D gNormal;
+uniform sampler2D depthMap;
+uniform sampler2D noiseTexture;
+
+uniform vec3 samples[MAX_KERNEL_SIZE];
+uniform int kernelSize;
+uniform float radius;
+uniform float bias;
+uniform float power;
+uniform vec2 noiseScale;
+uniform mat4 view;
+uniform mat4 projection;
+
+// 像素处是否有几何体
+bool hasGeometry(vec2 uv){
+    if(inputMode == 0) return texture(gPosition, uv).w != 0.0;
+    return texture(depthMap, uv).r < 1.0;
+}
+
+// 像素的观察空间位置
+vec3 viewPosition(vec2 uv){
+    if(inputMode == 0) return (view * vec4(texture(gPosition, uv).xyz, 1.0)).xyz;
+    // 对称透视投影下由深度还原观察空间坐标
+    vec3 ndc = vec3(uv, texture(depthMap, uv).r) * 2.0 - 1.0;
+    float z = -projection[3][2] / (ndc.z + projection[2][2]);
+    return vec3(ndc.x * -z / projection[0][0], ndc.y * -z / projection[1][1], z);
+}
+
+void main(){
+    if(!hasGeometry(TexCoords)){
+        FragColor = 1.0;
+        return;
+    }
+    vec3 fragPos = viewPosition(TexCoords);
+    vec3 normal;
+    if(inputMode == 0){
+        normal = normalize(mat3(view) * texture(gNormal, TexCoords).xyz);
+    } else {
+        normal = normalize(cross(dFdx(fragPos), dFdy(fragPos)));
+    }
+    // 以噪声向量随机旋转采样半球，构建切线空间
+    vec3 randomVec = normalize(texture(noiseTexture, TexCoords * noiseScale).xyz);
+    vec3 tangent = normalize(randomVec - normal * dot(randomVec, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 TBN = mat3(tangent, bitangent, normal);
+
+    float occlusion = 0.0;
+    for(int i = 0; i < kernelSize; ++i){
+        vec3 samplePos = fragPos + TBN * samples[i] * radius;
+        vec4 offset = projection * vec4(samplePos, 1.0);
+        vec2 uv = offset.xy / offset.w * 0.5 + 0.5;
+        if(any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0))) || !hasGeometry(uv)) continue;
+        float sampleDepth = viewPosition(uv).z;
+        float rangeCheck = smoothstep(0.0, 1.0, radius / abs(fragPos.z - sampleDepth));
+        occlusion += (sampleDepth >= samplePos.z + bias ? 1.0 : 0.0) * rangeCheck;
+    }
+    FragColor = pow(1.0 - occlusion / float(max(kernelSize, 1)), power);
+}
@@ -0,0 +1,452 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use image::RgbaImage;
+use json::{object, JsonValue};
+
+use super::{texture::Texture, TextureOptions};
+use crate::{
+    debug,
+    vfs::{ScanOptions, VFS},
+    warn,
+};
+
+/// 图集中的像素矩形区域，不含填充
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 纹理坐标矩形，`(u0, v0)` 对应图像左上角像素，`(u1, v1)` 对应右下角像素
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvRect {
+    pub u0: f32,
+    pub v0: f32,
+    pub u1: f32,
+    pub v1: f32,
+}
+
+/// 图集布局，记录每张图像在图集中的位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasLayout {
+    pub width: u32,
+    pub height: u32,
+    /// 每张图像四周的填充像素数
+    pub padding: u32,
+    regions: BTreeMap<String, AtlasRect>,
+}
+
+impl AtlasLayout {
+    /// 将若干矩形打包为一张尺寸为 2 的幂的图集
+    ///
+    /// # 参数 Parameters
+    /// * `sizes` - 每张图像的名称、宽度与高度
+    /// * `padding` - 每张图像四周的填充像素数
+    /// * `max_size` - 图集允许的最大边长
+    ///
+    /// # 注解 Note
+    ///
+    /// 使用按高度降序排列的行式打包，图集宽度从能容纳全部面积的最小值开始倍增，
+    /// 直到所需高度不超过宽度或宽度达到不超过 `max_size` 的最大 2 的幂；宽或高为 0 的图像无法打包
+    pub fn pack(sizes: &[(String, u32, u32)], padding: u32, max_size: u32) -> Result<Self, String> {
+        if sizes.is_empty() {
+            return Err("图集中没有任何图像".to_string());
+        }
+        if let Some((name, _, _)) = sizes.iter().find(|(_, w, h)| *w == 0 || *h == 0) {
+            return Err(format!("图像 {} 的尺寸为 0", name));
+        }
+        let mut names = BTreeSet::new();
+        if let Some((name, _, _)) = sizes.iter().find(|(name, _, _)| !names.insert(name)) {
+            return Err(format!("图集中存在重名图像 {}", name));
+        }
+        let too_large = || format!("图像无法放入最大边长为 {} 的图集", max_size);
+        // 图集边长为 2 的幂，不能超过 `max_size` 以内的最大 2 的幂
+        let limit = max_size.checked_ilog2().map_or(0, |e| 1 << e);
+        let cells = sizes
+            .iter()
+            .map(|(_, w, h)| {
+                let padding = padding.checked_mul(2)?;
+                Some((w.checked_add(padding)?, h.checked_add(padding)?))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(too_large)?;
+        let mut order = (0..sizes.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            let (wa, ha) = cells[a];
+            let (wb, hb) = cells[b];
+            hb.cmp(&ha)
+                .then(wb.cmp(&wa))
+                .then(sizes[a].0.cmp(&sizes[b].0))
+        });
+        let area = cells.iter().map(|&(w, h)| w as u64 * h as u64).sum::<u64>();
+        let widest = cells.iter().map(|&(w, _)| w).max().unwrap_or(1);
+        let mut width = ((area as f64).sqrt().ceil() as u32)
+            .max(widest)
+            .checked_next_power_of_two()
+            .ok_or_else(too_large)?;
+        'width: while width <= limit {
+            let mut regions = BTreeMap::new();
+            // 以 u64 计算坐标，边长接近 `u32::MAX` 时不会溢出
+            let (mut x, mut y, mut shelf) = (0u64, 0u64, 0u64);
+            for &i in &order {
+                let (name, w, h) = &sizes[i];
+                let (cw, ch) = (cells[i].0 as u64, cells[i].1 as u64);
+                if x + cw > width as u64 {
+                    x = 0;
+                    y += shelf;
+                    shelf = 0;
+                }
+                // 高度超过宽度时以更大的宽度重试
+                if y + ch > width as u64 {
+                    match width.checked_mul(2) {
+                        Some(next) => width = next,
+                        None => break,
+                    }
+                    continue 'width;
+                }
+                let rect = AtlasRect {
+                    x: x as u32 + padding,
+                    y: y as u32 + padding,
+                    width: *w,
+                    height: *h,
+                };
+                regions.insert(name.clone(), rect);
+                x += cw;
+                shelf = shelf.max(ch);
+            }
+            return Ok(Self {
+                width,
+                height: ((y + shelf) as u32).next_power_of_two(),
+                padding,
+                regions,
+            });
+        }
+        Err(too_large())
+    }
+
+    /// 指定名称图像的像素区域
+    pub fn rect(&self, name: &str) -> Option<AtlasRect> {
+        self.regions.get(name).copied()
+    }
+
+    /// 指定名称图像的纹理坐标
+    pub fn uv(&self, name: &str) -> Option<UvRect> {
+        let rect = self.regions.get(name)?;
+        let (w, h) = (self.width as f32, self.height as f32);
+        Some(UvRect {
+            u0: rect.x as f32 / w,
+            v0: rect.y as f32 / h,
+            u1: (rect.x + rect.width) as f32 / w,
+            v1: (rect.y + rect.height) as f32 / h,
+        })
+    }
+
+    /// 图集中所有图像的名称，按名称排序
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(|name| name.as_str())
+    }
+
+    /// 导出为 JSON 布局
+    ///
+    /// # 注解 Note
+    ///
+    /// 格式如下：
+    /// ```json
+    /// {
+    ///     "width": 256, "height": 256, "padding": 1,
+    ///     "regions": { "stone": { "x": 1, "y": 1, "width": 16, "height": 16 } }
+    /// }
+    /// ```
+    pub fn to_json(&self) -> JsonValue {
+        let mut regions = JsonValue::new_object();
+        for (name, rect) in &self.regions {
+            regions[name.as_str()] = object! {
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+            };
+        }
+        object! {
+            width: self.width,
+            height: self.height,
+            padding: self.padding,
+            regions: regions,
+        }
+    }
+}
+
+/// 纹理图集构建器
+///
+/// # 示例 Examples
+/// ```ignore
+/// let atlas = TextureAtlasBuilder::new()
+///     .padding(2)
+///     .add_dir("texture/blocks/", &ScanOptions::recursive().include("**/*.png"))
+///     .build()?;
+/// let uv = atlas.uv("stone").unwrap();
+/// ```
+pub struct TextureAtlasBuilder {
+    images: Vec<(String, RgbaImage)>,
+    padding: u32,
+    max_size: u32,
+    options: TextureOptions,
+}
+
+impl Default for TextureAtlasBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextureAtlasBuilder {
+    /// 默认填充 1 像素，最大边长 4096
+    pub fn new() -> Self {
+        Self {
+            images: vec![],
+            padding: 1,
+            max_size: 4096,
+            options: TextureOptions::default(),
+        }
+    }
+
+    /// 设置每张图像四周的填充像素数，填充区域以图像边缘像素延展，避免采样时的颜色渗透
+    pub fn padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// 设置图集允许的最大边长
+    pub fn max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// 设置图集纹理的选项
+    pub fn options(mut self, options: TextureOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// 添加一张图像
+    pub fn add(mut self, name: &str, image: RgbaImage) -> Self {
+        self.images.push((name.to_string(), image));
+        self
+    }
+
+    /// 添加目录下的所有图像，图像名称规则与 `TEXTURE_MANAGER.load_from_with` 相同
+    ///
+    /// # 注解 Note
+    ///
+    /// 无法解码的文件将被跳过并输出警告信息
+    pub fn add_dir(mut self, dir: &str, options: &ScanOptions) -> Self {
+        for entry in VFS.scan(dir, options) {
+            if entry.ext == "json" {
+                continue;
+            }
+            match Texture::decode(&entry.path) {
+                Ok(img) => {
+                    debug!("TextureAtlasBuilder", "添加图像 {}", entry.path);
+                    self.images.push((entry.name, img));
+                }
+                Err(e) => {
+                    warn!("TextureAtlasBuilder", "跳过文件 {}: {}", entry.path, e);
+                }
+            }
+        }
+        self
+    }
+
+    /// 在 CPU 上完成打包与合成，返回布局与图集图像
+    pub fn compose(&self) -> Result<(AtlasLayout, RgbaImage), String> {
+        let sizes = self
+            .images
+            .iter()
+            .map(|(name, img)| (name.clone(), img.width(), img.height()))
+            .collect::<Vec<_>>();
+        let layout = AtlasLayout::pack(&sizes, self.padding, self.max_size)?;
+        let mut atlas = RgbaImage::new(layout.width, layout.height);
+        for (name, img) in &self.images {
+            let rect = layout.regions[name];
+            blit_extruded(&mut atlas, img, rect, self.padding);
+        }
+        Ok((layout, atlas))
+    }
+
+    /// 将图集图像与 JSON 布局导出到文件系统
+    ///
+    /// # 参数 Parameters
+    /// * `png_path` - 图集图像路径
+    /// * `json_path` - 布局文件路径
+    pub fn export(&self, png_path: &str, json_path: &str) -> Result<(), String> {
+        let (layout, atlas) = self.compose()?;
+        atlas
+            .save(png_path)
+            .map_err(|e| format!("无法保存图集图像 {}: {}", png_path, e))?;
+        std::fs::write(json_path, layout.to_json().pretty(4))
+            .map_err(|e| format!("无法保存图集布局 {}: {}", json_path, e))
+    }
+
+    /// 打包图像并上传为一张纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn build(&self) -> Result<TextureAtlas, String> {
+        let (layout, atlas) = self.compose()?;
+        debug!(
+            "TextureAtlasBuilder",
+            "已将 {} 张图像打包为 {}x{} 的图集",
+            self.images.len(),
+            layout.width,
+            layout.height
+        );
+        Ok(TextureAtlas {
+            texture: Texture::from_rgba_with(&atlas, &self.options),
+            layout,
+        })
+    }
+}
+
+/// 将图像复制到图集的指定区域，并以边缘像素填充四周
+fn blit_extruded(atlas: &mut RgbaImage, img: &RgbaImage, rect: AtlasRect, padding: u32) {
+    if rect.width == 0 || rect.height == 0 {
+        return;
+    }
+    let (x0, y0) = (rect.x - padding, rect.y - padding);
+    for y in 0..rect.height + padding * 2 {
+        for x in 0..rect.width + padding * 2 {
+            let sx = x.saturating_sub(padding).min(rect.width - 1);
+            let sy = y.saturating_sub(padding).min(rect.height - 1);
+            atlas.put_pixel(x0 + x, y0 + y, *img.get_pixel(sx, sy));
+        }
+    }
+}
+
+/// 纹理图集，由多张小图像打包而成的单张纹理
+pub struct TextureAtlas {
+    texture: Texture,
+    layout: AtlasLayout,
+}
+
+impl TextureAtlas {
+    pub fn layout(&self) -> &AtlasLayout {
+        &self.layout
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// 指定名称图像的纹理坐标
+    pub fn uv(&self, name: &str) -> Option<UvRect> {
+        self.layout.uv(name)
+    }
+
+    /// 将图集纹理绑定到指定 ID
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn bind(&self, id: u32) {
+        self.texture.bind(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(list: &[(&str, u32, u32)]) -> Vec<(String, u32, u32)> {
+        list.iter()
+            .map(|&(name, w, h)| (name.to_string(), w, h))
+            .collect()
+    }
+
+    #[test]
+    fn test_pack_no_overlap() {
+        let input = sizes(&[
+            ("a", 16, 16),
+            ("b", 32, 8),
+            ("c", 7, 30),
+            ("d", 16, 16),
+            ("e", 64, 3),
+        ]);
+        let layout = AtlasLayout::pack(&input, 2, 1024).unwrap();
+        assert!(layout.width.is_power_of_two() && layout.height.is_power_of_two());
+        let cells = input
+            .iter()
+            .map(|(name, _, _)| {
+                let r = layout.rect(name).unwrap();
+                (r.x - 2, r.y - 2, r.x + r.width + 2, r.y + r.height + 2)
+            })
+            .collect::<Vec<_>>();
+        for (i, a) in cells.iter().enumerate() {
+            assert!(a.2 <= layout.width && a.3 <= layout.height);
+            for b in &cells[i + 1..] {
+                assert!(a.2 <= b.0 || b.2 <= a.0 || a.3 <= b.1 || b.3 <= a.1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pack_errors() {
+        assert!(AtlasLayout::pack(&[], 0, 64).is_err());
+        assert!(AtlasLayout::pack(&sizes(&[("a", 100, 10)]), 0, 64).is_err());
+        assert!(AtlasLayout::pack(&sizes(&[("a", 8, 8), ("a", 8, 8)]), 0, 64).is_err());
+        assert!(AtlasLayout::pack(&sizes(&[("a", 8, 8), ("b", 0, 0)]), 1, 64).is_err());
+        assert!(AtlasLayout::pack(&sizes(&[("a", 8, 8)]), 0, 0).is_err());
+
+        // 最大边长不是 2 的幂时以其以内的最大 2 的幂为上限
+        let layout = AtlasLayout::pack(&sizes(&[("a", 40, 40), ("b", 40, 40)]), 0, 100);
+        assert!(layout.is_err());
+        let layout = AtlasLayout::pack(&sizes(&[("a", 30, 60), ("b", 30, 60)]), 0, 100).unwrap();
+        assert_eq!((layout.width, layout.height), (64, 64));
+
+        // 边长接近 `u32::MAX` 时不会溢出
+        let max = u32::MAX;
+        assert!(AtlasLayout::pack(&sizes(&[("a", 3 << 30, 1)]), 0, max).is_err());
+        assert!(AtlasLayout::pack(&sizes(&[("a", max, 1)]), 1, max).is_err());
+        let input = sizes(&[("a", 1 << 31, 1), ("b", 1 << 31, 1)]);
+        let layout = AtlasLayout::pack(&input, 0, max).unwrap();
+        assert_eq!((layout.width, layout.height), (1 << 31, 2));
+    }
+
+    #[test]
+    fn test_uv() {
+        let layout = AtlasLayout::pack(&sizes(&[("a", 16, 16)]), 0, 64).unwrap();
+        assert_eq!((layout.width, layout.height), (16, 16));
+        let uv = layout.uv("a").unwrap();
+        assert_eq!((uv.u0, uv.v0, uv.u1, uv.v1), (0.0, 0.0, 1.0, 1.0));
+
+        let layout = AtlasLayout::pack(&sizes(&[("a", 6, 6)]), 1, 64).unwrap();
+        let uv = layout.uv("a").unwrap();
+        assert_eq!((uv.u0, uv.v0, uv.u1, uv.v1), (0.125, 0.125, 0.875, 0.875));
+        assert!(layout.uv("b").is_none());
+    }
+
+    #[test]
+    fn test_extrusion() {
+        let mut img = RgbaImage::new(2, 2);
+        img.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        img.put_pixel(1, 1, image::Rgba([0, 0, 255, 255]));
+        let builder = TextureAtlasBuilder::new().padding(1).add("a", img);
+        let (layout, atlas) = builder.compose().unwrap();
+        assert_eq!(layout.rect("a").unwrap().x, 1);
+        assert_eq!(atlas.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(atlas.get_pixel(1, 0).0, [255, 0, 0, 255]);
+        assert_eq!(atlas.get_pixel(3, 3).0, [0, 0, 255, 255]);
+        assert_eq!(atlas.get_pixel(3, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let layout = AtlasLayout::pack(&sizes(&[("a", 4, 4), ("b", 4, 4)]), 0, 64).unwrap();
+        let json = layout.to_json();
+        assert_eq!(json["width"].as_u32(), Some(8));
+        assert_eq!(json["regions"]["b"]["width"].as_u32(), Some(4));
+        assert!(json["regions"].has_key("a"));
+    }
+}
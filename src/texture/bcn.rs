@@ -0,0 +1,331 @@
+//! BC1-BC7 块压缩格式的 CPU 解压缩
+//!
+//! 用于显卡不支持 S3TC、BPTC 等压缩格式扩展时的回退路径
+
+use super::{bptc, ImageData, PixelFormat};
+
+/// 将 RGB565 颜色扩展为 8 位每通道
+fn rgb565(c: u16) -> [u8; 3] {
+    let r = ((c >> 11) & 0x1F) as u8;
+    let g = ((c >> 5) & 0x3F) as u8;
+    let b = (c & 0x1F) as u8;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+/// 解码 BC1 颜色块
+///
+/// # 参数 Parameters
+/// * `block` - 8 字节颜色块
+/// * `punch_through` - 是否允许 `c0 <= c1` 时的 1 位透明模式，BC2、BC3 中的颜色块不允许
+pub(crate) fn decode_color_block(block: &[u8], punch_through: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (p0, p1) = (rgb565(c0), rgb565(c1));
+    let mix = |a: u8, b: u8, wa: u32, wb: u32| ((a as u32 * wa + b as u32 * wb) / (wa + wb)) as u8;
+    let mut palette = [[0u8; 4]; 4];
+    palette[0] = [p0[0], p0[1], p0[2], 255];
+    palette[1] = [p1[0], p1[1], p1[2], 255];
+    if c0 > c1 || !punch_through {
+        for i in 0..3 {
+            palette[2][i] = mix(p0[i], p1[i], 2, 1);
+            palette[3][i] = mix(p0[i], p1[i], 1, 2);
+        }
+        palette[2][3] = 255;
+        palette[3][3] = 255;
+    } else {
+        for i in 0..3 {
+            palette[2][i] = mix(p0[i], p1[i], 1, 1);
+        }
+        palette[2][3] = 255;
+        palette[3] = [0, 0, 0, 0];
+    }
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut ret = [[0u8; 4]; 16];
+    for (i, pixel) in ret.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (i * 2)) & 0x3) as usize];
+    }
+    ret
+}
+
+/// 解码 BC4 单通道块(无符号)
+pub(crate) fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+    let mut bits = 0u64;
+    for (i, byte) in block[2..8].iter().enumerate() {
+        bits |= (*byte as u64) << (i * 8);
+    }
+    let mut ret = [0u8; 16];
+    for (i, value) in ret.iter_mut().enumerate() {
+        *value = palette[((bits >> (i * 3)) & 0x7) as usize];
+    }
+    ret
+}
+
+/// 解码有符号 BC4 单通道块，结果位于 `[-1, 1]`
+fn decode_signed_alpha_block(block: &[u8]) -> [f32; 16] {
+    // -128 与 -127 均表示 -1.0
+    let a0 = (block[0] as i8).max(-127) as f32;
+    let a1 = (block[1] as i8).max(-127) as f32;
+    let mut palette = [0f32; 8];
+    palette[0] = a0;
+    palette[1] = a1;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = ((7 - i) as f32 * a0 + i as f32 * a1) / 7.0;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = ((5 - i) as f32 * a0 + i as f32 * a1) / 5.0;
+        }
+        palette[6] = -127.0;
+        palette[7] = 127.0;
+    }
+    let mut bits = 0u64;
+    for (i, byte) in block[2..8].iter().enumerate() {
+        bits |= (*byte as u64) << (i * 8);
+    }
+    let mut ret = [0f32; 16];
+    for (i, value) in ret.iter_mut().enumerate() {
+        *value = palette[((bits >> (i * 3)) & 0x7) as usize] / 127.0;
+    }
+    ret
+}
+
+/// 解码 BC2 中的 4 位显式透明度块
+fn decode_explicit_alpha_block(block: &[u8]) -> [u8; 16] {
+    let mut ret = [0u8; 16];
+    for (i, value) in ret.iter_mut().enumerate() {
+        let nibble = (block[i / 2] >> ((i % 2) * 4)) & 0xF;
+        *value = nibble * 17;
+    }
+    ret
+}
+
+/// 可在 CPU 上解压缩的块格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BcKind {
+    Bc1,
+    Bc2,
+    Bc3,
+    Bc4 { signed: bool },
+    Bc5 { signed: bool },
+    Bc6h { signed: bool },
+    Bc7,
+}
+
+/// 解压缩后的像素块
+enum Block {
+    /// 8 位 RGBA
+    Unorm([[u8; 4]; 16]),
+    /// 浮点 RGBA，按像素格式的通道数取前若干个分量
+    Float([[f32; 4]; 16]),
+}
+
+impl BcKind {
+    fn block_bytes(self) -> usize {
+        match self {
+            BcKind::Bc1 | BcKind::Bc4 { .. } => 8,
+            _ => 16,
+        }
+    }
+
+    /// 解压缩后的像素格式
+    ///
+    /// 有符号格式与 BC6H 保留为浮点数据，以保持与显卡采样结果相同的取值范围
+    fn pixel_format(self) -> PixelFormat {
+        match self {
+            BcKind::Bc4 { signed: true } => PixelFormat::R32F,
+            BcKind::Bc5 { signed: true } => PixelFormat::Rgba32F,
+            BcKind::Bc6h { .. } => PixelFormat::Rgb32F,
+            _ => PixelFormat::Rgba8,
+        }
+    }
+
+    fn decode_block(self, block: &[u8]) -> Block {
+        let ret = match self {
+            BcKind::Bc1 => decode_color_block(block, true),
+            BcKind::Bc2 => {
+                let mut colors = decode_color_block(&block[8..], false);
+                let alpha = decode_explicit_alpha_block(&block[..8]);
+                for (color, a) in colors.iter_mut().zip(alpha) {
+                    color[3] = a;
+                }
+                colors
+            }
+            BcKind::Bc3 => {
+                let mut colors = decode_color_block(&block[8..], false);
+                let alpha = decode_alpha_block(&block[..8]);
+                for (color, a) in colors.iter_mut().zip(alpha) {
+                    color[3] = a;
+                }
+                colors
+            }
+            BcKind::Bc4 { signed: false } => decode_alpha_block(block).map(|r| [r, 0, 0, 255]),
+            BcKind::Bc4 { signed: true } => {
+                return Block::Float(decode_signed_alpha_block(block).map(|r| [r, 0.0, 0.0, 1.0]))
+            }
+            BcKind::Bc5 { signed: false } => {
+                let r = decode_alpha_block(&block[..8]);
+                let g = decode_alpha_block(&block[8..]);
+                let mut ret = [[0, 0, 0, 255]; 16];
+                for i in 0..16 {
+                    ret[i][0] = r[i];
+                    ret[i][1] = g[i];
+                }
+                ret
+            }
+            BcKind::Bc5 { signed: true } => {
+                let r = decode_signed_alpha_block(&block[..8]);
+                let g = decode_signed_alpha_block(&block[8..]);
+                let mut ret = [[0.0, 0.0, 0.0, 1.0]; 16];
+                for i in 0..16 {
+                    ret[i][0] = r[i];
+                    ret[i][1] = g[i];
+                }
+                return Block::Float(ret);
+            }
+            BcKind::Bc6h { signed } => {
+                return Block::Float(
+                    bptc::decode_bc6h_block(block, signed).map(|[r, g, b]| [r, g, b, 1.0]),
+                )
+            }
+            BcKind::Bc7 => bptc::decode_bc7_block(block),
+        };
+        Block::Unorm(ret)
+    }
+}
+
+/// 将块压缩数据解压缩为未压缩图像
+///
+/// # 注解 Note
+///
+/// BC1-BC5 与 BC7 解压缩为 RGBA8，有符号的 BC4、BC5 分别解压缩为 `R32F` 与 `Rgba32F`，
+/// BC6H 解压缩为 `Rgb32F`。单通道与双通道格式未使用的通道为 0，与显卡采样结果一致
+pub(crate) fn decompress(
+    kind: BcKind,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<ImageData, String> {
+    let blocks_x = width.div_ceil(4).max(1);
+    let blocks_y = height.div_ceil(4).max(1);
+    let expected = (blocks_x * blocks_y) as usize * kind.block_bytes();
+    if data.len() < expected {
+        return Err(format!(
+            "压缩数据长度不足, 需要 {} 字节, 实际为 {} 字节",
+            expected,
+            data.len()
+        ));
+    }
+    let format = kind.pixel_format();
+    let bytes_per_pixel = format.bytes_per_pixel();
+    let mut pixels = vec![0u8; format.image_size(width, height)];
+    for (i, block) in data[..expected].chunks(kind.block_bytes()).enumerate() {
+        let (bx, by) = (i as u32 % blocks_x * 4, i as u32 / blocks_x * 4);
+        let decoded = kind.decode_block(block);
+        for j in 0..16 {
+            let (x, y) = (bx + j as u32 % 4, by + j as u32 / 4);
+            if x >= width || y >= height {
+                continue;
+            }
+            let offset = (y as usize * width as usize + x as usize) * bytes_per_pixel;
+            let pixel = &mut pixels[offset..offset + bytes_per_pixel];
+            match &decoded {
+                Block::Unorm(colors) => pixel.copy_from_slice(&colors[j]),
+                Block::Float(colors) => {
+                    for (bytes, value) in pixel.chunks_mut(4).zip(colors[j]) {
+                        bytes.copy_from_slice(&value.to_ne_bytes());
+                    }
+                }
+            }
+        }
+    }
+    ImageData::new(width, height, format, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_block() {
+        // c0 = 纯红, c1 = 纯蓝, 索引依次为 0、1、2、3
+        let block = [0x00, 0xF8, 0x1F, 0x00, 0b1110_0100, 0, 0, 0];
+        let pixels = decode_color_block(&block, true);
+        assert_eq!(pixels[0], [255, 0, 0, 255]);
+        assert_eq!(pixels[1], [0, 0, 255, 255]);
+        assert_eq!(pixels[2], [170, 0, 85, 255]);
+        assert_eq!(pixels[3], [85, 0, 170, 255]);
+
+        // c0 <= c1 时为 3 色加透明模式
+        let block = [0x1F, 0x00, 0x00, 0xF8, 0b1110_0100, 0, 0, 0];
+        let pixels = decode_color_block(&block, true);
+        assert_eq!(pixels[2], [127, 0, 127, 255]);
+        assert_eq!(pixels[3], [0, 0, 0, 0]);
+        assert_eq!(decode_color_block(&block, false)[3][3], 255);
+    }
+
+    #[test]
+    fn test_alpha_block() {
+        // 索引 0..8 依次排列
+        let bits: u64 = (0..8).map(|i| (i as u64) << (i * 3)).sum();
+        let mut block = [0u8; 8];
+        block[0] = 255;
+        block[1] = 0;
+        block[2..8].copy_from_slice(&bits.to_le_bytes()[..6]);
+        let values = decode_alpha_block(&block);
+        assert_eq!(values[..8], [255, 0, 218, 182, 145, 109, 72, 36]);
+
+        block[0] = 0;
+        block[1] = 255;
+        let values = decode_alpha_block(&block);
+        assert_eq!(values[..8], [0, 255, 51, 102, 153, 204, 0, 255]);
+    }
+
+    #[test]
+    fn test_decompress() {
+        // 6x2 的图像需要两个块，超出范围的像素被丢弃
+        let mut data = vec![];
+        data.extend_from_slice(&[0x00, 0xF8, 0x00, 0xF8, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0x1F, 0x00, 0x1F, 0x00, 0, 0, 0, 0]);
+        let img = decompress(BcKind::Bc1, 6, 2, &data).unwrap();
+        assert_eq!(img.format, PixelFormat::Rgba8);
+        assert_eq!(img.data[(6 + 3) * 4..(6 + 4) * 4], [255, 0, 0, 255]);
+        assert_eq!(img.data[4 * 4..5 * 4], [0, 0, 255, 255]);
+        assert!(decompress(BcKind::Bc3, 6, 2, &data).is_err());
+    }
+
+    #[test]
+    fn test_signed_alpha_block() {
+        // -128 视为 -127，索引 0、1、6、7 分别为两个端点与 6 值模式下的 -1.0、1.0
+        let bits: u64 = 1 << 3 | 6 << 6 | 7 << 9;
+        let mut block = [0u8; 8];
+        block[0] = 0x80;
+        block[1] = 0x7F;
+        block[2..8].copy_from_slice(&bits.to_le_bytes()[..6]);
+        let values = decode_signed_alpha_block(&block);
+        assert_eq!(values[..4], [-1.0, 1.0, -1.0, 1.0]);
+
+        let img = decompress(BcKind::Bc4 { signed: true }, 1, 1, &block).unwrap();
+        assert_eq!(img.format, PixelFormat::R32F);
+        assert_eq!(img.data, (-1.0f32).to_ne_bytes());
+    }
+}
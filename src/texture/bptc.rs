@@ -0,0 +1,941 @@
+//! BC6H 与 BC7(BPTC)块压缩格式的 CPU 解压缩
+//!
+//! 用于显卡不支持 `GL_ARB_texture_compression_bptc` 时的回退路径
+
+/// 按从低到高的顺序读取 128 位块中的位
+struct BitReader {
+    bits: u128,
+    pos: u32,
+}
+
+impl BitReader {
+    fn new(block: &[u8]) -> Self {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&block[..16]);
+        Self {
+            bits: u128::from_le_bytes(bytes),
+            pos: 0,
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        let value = (self.bits >> self.pos) as u32 & ((1u64 << count) - 1) as u32;
+        self.pos += count;
+        value
+    }
+}
+
+/// 二分区的划分方式，第 `i` 位为第 `i` 个像素所属的分区
+const PARTITIONS2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800,
+    0xFFE8, 0xFF00, 0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+    0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C, 0xAAAA, 0xF0F0, 0x5A5A, 0x33CC,
+    0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718,
+    0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+/// 三分区的划分方式，第 `2i` 与 `2i + 1` 位为第 `i` 个像素所属的分区
+const PARTITIONS3: [u32; 64] = [
+    0xAA685050, 0x6A5A5040, 0x5A5A4200, 0x5450A0A8, 0xA5A50000, 0xA0A05050, 0x5555A0A0, 0x5A5A5050,
+    0xAA550000, 0xAA555500, 0xAAAA5500, 0x90909090, 0x94949494, 0xA4A4A4A4, 0xA9A59450, 0x2A0A4250,
+    0xA5945040, 0x0A425054, 0xA5A5A500, 0x55A0A0A0, 0xA8A85454, 0x6A6A4040, 0xA4A45000, 0x1A1A0500,
+    0x0050A4A4, 0xAAA59090, 0x14696914, 0x69691400, 0xA08585A0, 0xAA821414, 0x50A4A450, 0x6A5A0200,
+    0xA9A58000, 0x5090A0A8, 0xA8A09050, 0x24242424, 0x00AA5500, 0x24924924, 0x24499224, 0x50A50A50,
+    0x500AA550, 0xAAAA4444, 0x66660000, 0xA5A0A5A0, 0x50A050A0, 0x69286928, 0x44AAAA44, 0x66666600,
+    0xAA444444, 0x54A854A8, 0x95809580, 0x96969600, 0xA85454A8, 0x80959580, 0xAA141414, 0x96960000,
+    0xAAAA1414, 0xA05050A0, 0xA0A5A5A0, 0x96000000, 0x40804080, 0xA9A8A9A8, 0xAAAAAA44, 0x2A4A5254,
+];
+
+/// 二分区中第二个分区的锚点像素
+const ANCHORS2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// 三分区中第二个分区的锚点像素
+const ANCHORS3_1: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5,
+    15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5,
+    10, 8, 13, 15, 12, 3, 3,
+];
+
+/// 三分区中第三个分区的锚点像素
+const ANCHORS3_2: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6,
+    10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+const WEIGHTS2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// 按索引位数选择插值权重
+fn weight(index_bits: u32, index: u32) -> u32 {
+    match index_bits {
+        2 => WEIGHTS2[index as usize],
+        3 => WEIGHTS3[index as usize],
+        _ => WEIGHTS4[index as usize],
+    }
+}
+
+/// 像素所属的分区
+fn subset(subsets: usize, partition: usize, pixel: usize) -> usize {
+    match subsets {
+        2 => (PARTITIONS2[partition] >> pixel) as usize & 1,
+        3 => (PARTITIONS3[partition] >> (pixel * 2)) as usize & 3,
+        _ => 0,
+    }
+}
+
+/// 像素是否为分区的锚点，锚点索引的最高位隐含为 0，少存储一位
+fn is_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+    pixel == 0
+        || match subsets {
+            2 => pixel == ANCHORS2[partition] as usize,
+            3 => pixel == ANCHORS3_1[partition] as usize || pixel == ANCHORS3_2[partition] as usize,
+            _ => false,
+        }
+}
+
+/// BC7 模式参数
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    /// 每个端点各有一个 P 位
+    endpoint_pbits: bool,
+    /// 每个分区的两个端点共享一个 P 位
+    shared_pbits: bool,
+    index_bits: u32,
+    /// 第二组索引的位数，为 0 表示颜色与透明度共用一组索引
+    index_bits2: u32,
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode {
+        subsets: 3,
+        partition_bits: 4,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 4,
+        alpha_bits: 0,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 3,
+        index_bits2: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 6,
+        alpha_bits: 0,
+        endpoint_pbits: false,
+        shared_pbits: true,
+        index_bits: 3,
+        index_bits2: 0,
+    },
+    Bc7Mode {
+        subsets: 3,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 5,
+        alpha_bits: 0,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index_bits2: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 0,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 2,
+        index_bits2: 0,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 2,
+        index_selection_bits: 1,
+        color_bits: 5,
+        alpha_bits: 6,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index_bits2: 3,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 2,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 8,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index_bits2: 2,
+    },
+    Bc7Mode {
+        subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 7,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 4,
+        index_bits2: 0,
+    },
+    Bc7Mode {
+        subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 5,
+        alpha_bits: 5,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 2,
+        index_bits2: 0,
+    },
+];
+
+/// 将 `bits` 位的端点值扩展为 8 位
+fn expand(value: u32, bits: u32) -> u32 {
+    let value = value << (8 - bits);
+    value | (value >> bits)
+}
+
+/// 解码 BC7 块
+///
+/// # 注解 Note
+///
+/// 保留的模式(首字节为 0)解码为全透明的黑色
+pub(crate) fn decode_bc7_block(block: &[u8]) -> [[u8; 4]; 16] {
+    if block[0] == 0 {
+        return [[0; 4]; 16];
+    }
+    let mut reader = BitReader::new(block);
+    let mode_index = block[0].trailing_zeros();
+    reader.read(mode_index + 1);
+    let mode = &BC7_MODES[mode_index as usize];
+    let partition = reader.read(mode.partition_bits) as usize;
+    let rotation = reader.read(mode.rotation_bits);
+    let index_selection = reader.read(mode.index_selection_bits);
+
+    let count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(count) {
+            endpoint[channel] = reader.read(mode.color_bits);
+        }
+    }
+    for endpoint in endpoints.iter_mut().take(count) {
+        endpoint[3] = reader.read(mode.alpha_bits);
+    }
+    let mut pbits = [0u32; 6];
+    if mode.endpoint_pbits {
+        for pbit in pbits.iter_mut().take(count) {
+            *pbit = reader.read(1);
+        }
+    } else if mode.shared_pbits {
+        for i in 0..mode.subsets {
+            let pbit = reader.read(1);
+            pbits[i * 2] = pbit;
+            pbits[i * 2 + 1] = pbit;
+        }
+    }
+    let pbit_bits = (mode.endpoint_pbits || mode.shared_pbits) as u32;
+    for (endpoint, pbit) in endpoints.iter_mut().zip(pbits).take(count) {
+        for value in endpoint.iter_mut().take(3) {
+            *value = expand(*value << pbit_bits | pbit, mode.color_bits + pbit_bits);
+        }
+        endpoint[3] = if mode.alpha_bits == 0 {
+            255
+        } else {
+            expand(endpoint[3] << pbit_bits | pbit, mode.alpha_bits + pbit_bits)
+        };
+    }
+
+    let mut indices = [0u32; 16];
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.subsets, partition, pixel) as u32;
+        *index = reader.read(mode.index_bits - anchor);
+    }
+    let mut indices2 = [0u32; 16];
+    if mode.index_bits2 > 0 {
+        for (pixel, index) in indices2.iter_mut().enumerate() {
+            *index = reader.read(mode.index_bits2 - (pixel == 0) as u32);
+        }
+    }
+
+    let mut ret = [[0u8; 4]; 16];
+    for (pixel, color) in ret.iter_mut().enumerate() {
+        let s = subset(mode.subsets, partition, pixel);
+        let (e0, e1) = (endpoints[s * 2], endpoints[s * 2 + 1]);
+        let (color_weight, alpha_weight) = if mode.index_bits2 == 0 {
+            let w = weight(mode.index_bits, indices[pixel]);
+            (w, w)
+        } else if index_selection == 0 {
+            (
+                weight(mode.index_bits, indices[pixel]),
+                weight(mode.index_bits2, indices2[pixel]),
+            )
+        } else {
+            (
+                weight(mode.index_bits2, indices2[pixel]),
+                weight(mode.index_bits, indices[pixel]),
+            )
+        };
+        for channel in 0..4 {
+            let w = if channel == 3 {
+                alpha_weight
+            } else {
+                color_weight
+            };
+            color[channel] = (((64 - w) * e0[channel] + w * e1[channel] + 32) >> 6) as u8;
+        }
+        match rotation {
+            1 => color.swap(0, 3),
+            2 => color.swap(1, 3),
+            3 => color.swap(2, 3),
+            _ => {}
+        }
+    }
+    ret
+}
+
+/// BC6H 端点中的字段，`W`、`X` 为第一个分区的端点，`Y`、`Z` 为第二个分区的端点
+const W: u8 = 0;
+const X: u8 = 1;
+const Y: u8 = 2;
+const Z: u8 = 3;
+const R: u8 = 0;
+const G: u8 = 1;
+const B: u8 = 2;
+
+/// BC6H 模式参数
+struct Bc6hMode {
+    /// 模式位的值，最低两位为 `00` 或 `01` 时模式位只有两位，否则为五位
+    id: u32,
+    /// 是否为双分区模式
+    two_regions: bool,
+    /// `X`、`Y`、`Z` 是否存储为相对 `W` 的差值
+    transformed: bool,
+    /// 端点的精度
+    endpoint_bits: u32,
+    /// 差值在三个通道上的位数
+    delta_bits: [u32; 3],
+    /// 端点字段的存储顺序，每项为端点、通道、起始位与位数
+    fields: &'static [(u8, u8, u8, u8)],
+}
+
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode {
+        id: 0b00,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 10,
+        delta_bits: [5, 5, 5],
+        fields: &[
+            (Y, G, 4, 1),
+            (Y, B, 4, 1),
+            (Z, B, 4, 1),
+            (W, R, 0, 10),
+            (W, G, 0, 10),
+            (W, B, 0, 10),
+            (X, R, 0, 5),
+            (Z, G, 4, 1),
+            (Y, G, 0, 4),
+            (X, G, 0, 5),
+            (Z, B, 0, 1),
+            (Z, G, 0, 4),
+            (X, B, 0, 5),
+            (Z, B, 1, 1),
+            (Y, B, 0, 4),
+            (Y, R, 0, 5),
+            (Z, B, 2, 1),
+            (Z, R, 0, 5),
+            (Z, B, 3, 1),
+        ],
+    },
+    Bc6hMode {
+        id: 0b01,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 7,
+        delta_bits: [6, 6, 6],
+        fields: &[
+            (Y, G, 5, 1),
+            (Z, G, 4, 1),
+            (Z, G, 5, 1),
+            (W, R, 0, 7),
+            (Z, B, 0, 1),
+            (Z, B, 1, 1),
+            (Y, B, 4, 1),
+            (W, G, 0, 7),
+            (Y, B, 5, 1),
+            (Z, B, 2, 1),
+            (Y, G, 4, 1),
+            (W, B, 0, 7),
+            (Z, B, 3, 1),
+            (Z, B, 5, 1),
+            (Z, B, 4, 1),
+            (X, R, 0, 6),
+            (Y, G, 0, 4),
+            (X, G, 0, 6),
+            (Z, G, 0, 4),
+            (X, B, 0, 6),
+            (Y, B, 0, 4),
+            (Y, R, 0, 6),
+            (Z, R, 0, 6),
+        ],
+    },
+    Bc6hMode {
+        id: 0b00010,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [5, 4, 4],
+        fields: &[
+            (W, R, 0, 10),
+            (W, G, 0, 10),
+            (W, B, 0, 10),
+            (X, R, 0, 5),
+            (W, R, 10, 1),
+            (Y, G, 0, 4),
+            (X, G, 0, 4),
+            (W, G, 10, 1),
+            (Z, B, 0, 1),
+            (Z, G, 0, 4),
+            (X, B, 0, 4),
+            (W, B, 10, 1),
+            (Z, B, 1, 1),
+            (Y, B, 0, 4),
+            (Y, R, 0, 5),
+            (Z, B, 2, 1),
+            (Z, R, 0, 5),
+            (Z, B, 3, 1),
+        ],
+    },
+    Bc6hMode {
+        id: 0b00110,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 5, 4],
+        fields: &[
+            (W, R, 0, 10),
+            (W, G, 0, 10),
+            (W, B, 0, 10),
+            (X, R, 0, 4),
+            (W, R, 10, 1),
+            (Z, G, 4, 1),
+            (Y, G, 0, 4),
+            (X, G, 0, 5),
+            (W, G, 10, 1),
+            (Z, G, 0, 4),
+            (X, B, 0, 4),
+            (W, B, 10, 1),
+            (Z, B, 1, 1),
+            (Y, B, 0, 4),
+            (Y, R, 0, 4),
+            (Z, B, 0, 1),
+            (Z, B, 2, 1),
+            (Z, R, 0, 4),
+            (Y, G, 4, 1),
+            (Z, B, 3, 1),
+        ],
+    },
+    Bc6hMode {
+        id: 0b01010,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 4, 5],
+        fields: &[
+            (W, R, 0, 10),
+            (W, G, 0, 10),
+            (W, B, 0, 10),
+            (X, R, 0, 4),
+            (W, R, 10, 1),
+            (Y, B, 4, 1),
+            (Y, G, 0, 4),
+            (X, G, 0, 4),
+            (W, G, 10, 1),
+            (Z, B, 0, 1),
+            (Z, G, 0, 4),
+            (X, B, 0, 5),
+            (W, B, 10, 1),
+            (Y, B, 0, 4),
+            (Y, R, 0, 4),
+            (Z, B, 1, 1),
+            (Z, B, 2, 1),
+            (Z, R, 0, 4),
+            (Z, B, 4, 1),
+            (Z, B, 3, 1),
+        ],
+    },
+    Bc6hMode {
+        id: 0b01110,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 9,
+        delta_bits: [5, 5, 5],
+        fields: &[
+            (W, R, 0, 9),
+            (Y, B, 4, 1),
+            (W, G, 0, 9),
+            (Y, G, 4, 1),
+            (W, B, 0, 9),
+            (Z, B, 4, 1),
+            (X, R, 0, 5),
+            (Z, G, 4, 1),
+            (Y, G, 0, 4),
+            (X, G, 0, 5),
+            (Z, B, 0, 1),
+            (Z, G, 0, 4),
+            (X, B, 0, 5),
+            (Z, B, 1, 1),
+            (Y, B, 0, 4),
+            (Y, R, 0, 5),
+            (Z, B, 2, 1),
+            (Z, R, 0, 5),
+            (Z, B, 3, 1),
+        ],
+    },
+    Bc6hMode {
+        id: 0b10010,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [6, 5, 5],
+        fields: &[
+            (W, R, 0, 8),
+            (Z, G, 4, 1),
+            (Y, B, 4, 1),
+            (W, G, 0, 8),
+            (Z, B, 2, 1),
+            (Y, G, 4, 1),
+            (W, B, 0, 8),
+            (Z, B, 3, 1),
+            (Z, B, 4, 1),
+            (X, R, 0, 6),
+            (Y, G, 0, 4),
+            (X, G, 0, 5),
+            (Z, B, 0, 1),
+            (Z, G, 0, 4),
+            (X, B, 0, 5),
+            (Z, B, 1, 1),
+            (Y, B, 0, 4),
+            (Y, R, 0, 6),
+            (Z, R, 0, 6),
+        ],
+    },
+    Bc6hMode {
+        id: 0b10110,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 6, 5],
+        fields: &[
+            (W, R, 0, 8),
+            (Z, B, 0, 1),
+            (Y, B, 4, 1),
+            (W, G, 0, 8),
+            (Y, G, 5, 1),
+            (Y, G, 4, 1),
+            (W, B, 0, 8),
+            (Z, G, 5, 1),
+            (Z, B, 4, 1),
+            (X, R, 0, 5),
+            (Z, G, 4, 1),
+            (Y, G, 0, 4),
+            (X, G, 0, 6),
+            (Z, G, 0, 4),
+            (X, B, 0, 5),
+            (Z, B, 1, 1),
+            (Y, B, 0, 4),
+            (Y, R, 0, 5),
+            (Z, B, 2, 1),
+            (Z, R, 0, 5),
+            (Z, B, 3, 1),
+        ],
+    },
+    Bc6hMode {
+        id: 0b11010,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 5, 6],
+        fields: &[
+            (W, R, 0, 8),
+            (Z, B, 1, 1),
+            (Y, B, 4, 1),
+            (W, G, 0, 8),
+            (Y, B, 5, 1),
+            (Y, G, 4, 1),
+            (W, B, 0, 8),
+            (Z, B, 5, 1),
+            (Z, B, 4, 1),
+            (X, R, 0, 5),
+            (Z, G, 4, 1),
+            (Y, G, 0, 4),
+            (X, G, 0, 5),
+            (Z, B, 0, 1),
+            (Z, G, 0, 4),
+            (X, B, 0, 6),
+            (Y, B, 0, 4),
+            (Y, R, 0, 5),
+            (Z, B, 2, 1),
+            (Z, R, 0, 5),
+            (Z, B, 3, 1),
+        ],
+    },
+    Bc6hMode {
+        id: 0b11110,
+        two_regions: true,
+        transformed: false,
+        endpoint_bits: 6,
+        delta_bits: [6, 6, 6],
+        fields: &[
+            (W, R, 0, 6),
+            (Z, G, 4, 1),
+            (Z, B, 0, 1),
+            (Z, B, 1, 1),
+            (Y, B, 4, 1),
+            (W, G, 0, 6),
+            (Y, G, 5, 1),
+            (Y, B, 5, 1),
+            (Z, B, 2, 1),
+            (Y, G, 4, 1),
+            (W, B, 0, 6),
+            (Z, G, 5, 1),
+            (Z, B, 3, 1),
+            (Z, B, 5, 1),
+            (Z, B, 4, 1),
+            (X, R, 0, 6),
+            (Y, G, 0, 4),
+            (X, G, 0, 6),
+            (Z, G, 0, 4),
+            (X, B, 0, 6),
+            (Y, B, 0, 4),
+            (Y, R, 0, 6),
+            (Z, R, 0, 6),
+        ],
+    },
+    Bc6hMode {
+        id: 0b00011,
+        two_regions: false,
+        transformed: false,
+        endpoint_bits: 10,
+        delta_bits: [10, 10, 10],
+        fields: &[
+            (W, R, 0, 10),
+            (W, G, 0, 10),
+            (W, B, 0, 10),
+            (X, R, 0, 10),
+            (X, G, 0, 10),
+            (X, B, 0, 10),
+        ],
+    },
+    Bc6hMode {
+        id: 0b00111,
+        two_regions: false,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [9, 9, 9],
+        fields: &[
+            (W, R, 0, 10),
+            (W, G, 0, 10),
+            (W, B, 0, 10),
+            (X, R, 0, 9),
+            (W, R, 10, 1),
+            (X, G, 0, 9),
+            (W, G, 10, 1),
+            (X, B, 0, 9),
+            (W, B, 10, 1),
+        ],
+    },
+    // 以下两种模式中端点的高位按从高到低的顺序存储
+    Bc6hMode {
+        id: 0b01011,
+        two_regions: false,
+        transformed: true,
+        endpoint_bits: 12,
+        delta_bits: [8, 8, 8],
+        fields: &[
+            (W, R, 0, 10),
+            (W, G, 0, 10),
+            (W, B, 0, 10),
+            (X, R, 0, 8),
+            (W, R, 11, 1),
+            (W, R, 10, 1),
+            (X, G, 0, 8),
+            (W, G, 11, 1),
+            (W, G, 10, 1),
+            (X, B, 0, 8),
+            (W, B, 11, 1),
+            (W, B, 10, 1),
+        ],
+    },
+    Bc6hMode {
+        id: 0b01111,
+        two_regions: false,
+        transformed: true,
+        endpoint_bits: 16,
+        delta_bits: [4, 4, 4],
+        fields: &[
+            (W, R, 0, 10),
+            (W, G, 0, 10),
+            (W, B, 0, 10),
+            (X, R, 0, 4),
+            (W, R, 15, 1),
+            (W, R, 14, 1),
+            (W, R, 13, 1),
+            (W, R, 12, 1),
+            (W, R, 11, 1),
+            (W, R, 10, 1),
+            (X, G, 0, 4),
+            (W, G, 15, 1),
+            (W, G, 14, 1),
+            (W, G, 13, 1),
+            (W, G, 12, 1),
+            (W, G, 11, 1),
+            (W, G, 10, 1),
+            (X, B, 0, 4),
+            (W, B, 15, 1),
+            (W, B, 14, 1),
+            (W, B, 13, 1),
+            (W, B, 12, 1),
+            (W, B, 11, 1),
+            (W, B, 10, 1),
+        ],
+    },
+];
+
+/// 将 `bits` 位的补码扩展为 32 位有符号整数
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+/// 将端点反量化为 16 位精度
+fn unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if signed {
+        if bits >= 16 {
+            return value;
+        }
+        let magnitude = value.abs();
+        let ret = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7FFF
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        if value < 0 {
+            -ret
+        } else {
+            ret
+        }
+    } else if bits >= 15 || value == 0 {
+        value
+    } else if value == (1 << bits) - 1 {
+        0xFFFF
+    } else {
+        ((value << 16) + 0x8000) >> bits
+    }
+}
+
+/// 将插值结果转换为半精度浮点数的位模式
+fn finish_unquantize(value: i32, signed: bool) -> u16 {
+    if signed {
+        if value < 0 {
+            0x8000 | ((-value * 31) >> 5) as u16
+        } else {
+            ((value * 31) >> 5) as u16
+        }
+    } else {
+        ((value * 31) >> 6) as u16
+    }
+}
+
+/// 半精度浮点数转换为单精度浮点数
+fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1F) as i32;
+    let mantissa = (half & 0x3FF) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1F if mantissa == 0.0 => f32::INFINITY,
+        0x1F => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// 解码 BC6H 块，结果为 RGB 浮点颜色
+///
+/// # 注解 Note
+///
+/// 保留的模式解码为黑色
+pub(crate) fn decode_bc6h_block(block: &[u8], signed: bool) -> [[f32; 3]; 16] {
+    let mut reader = BitReader::new(block);
+    let mut id = reader.read(2);
+    if id > 1 {
+        id |= reader.read(3) << 2;
+    }
+    let mode = match BC6H_MODES.iter().find(|mode| mode.id == id) {
+        Some(mode) => mode,
+        None => return [[0.0; 3]; 16],
+    };
+    let mut endpoints = [[0i32; 3]; 4];
+    for &(endpoint, channel, bit, count) in mode.fields {
+        endpoints[endpoint as usize][channel as usize] |= (reader.read(count as u32) as i32) << bit;
+    }
+    let (regions, index_bits) = if mode.two_regions { (2, 3) } else { (1, 4) };
+    let partition = reader.read(if mode.two_regions { 5 } else { 0 }) as usize;
+    let bits = mode.endpoint_bits;
+    for channel in 0..3 {
+        if signed {
+            endpoints[0][channel] = sign_extend(endpoints[0][channel], bits);
+        }
+        let base = endpoints[0][channel];
+        for endpoint in endpoints.iter_mut().take(regions * 2).skip(1) {
+            if mode.transformed {
+                let delta = sign_extend(endpoint[channel], mode.delta_bits[channel]);
+                endpoint[channel] = (base + delta) & ((1 << bits) - 1);
+            }
+            if signed {
+                endpoint[channel] = sign_extend(endpoint[channel], bits);
+            }
+        }
+    }
+    for endpoint in endpoints.iter_mut().take(regions * 2) {
+        for value in endpoint.iter_mut() {
+            *value = unquantize(*value, bits, signed);
+        }
+    }
+
+    let mut ret = [[0.0; 3]; 16];
+    for (pixel, color) in ret.iter_mut().enumerate() {
+        let anchor = is_anchor(regions, partition, pixel) as u32;
+        let w = weight(index_bits, reader.read(index_bits - anchor)) as i32;
+        let s = subset(regions, partition, pixel);
+        let (e0, e1) = (endpoints[s * 2], endpoints[s * 2 + 1]);
+        for channel in 0..3 {
+            let value = ((64 - w) * e0[channel] + w * e1[channel] + 32) >> 6;
+            color[channel] = half_to_f32(finish_unquantize(value, signed));
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按从低到高的顺序写入各字段，每项为值与位数
+    fn pack(fields: &[(u32, u32)]) -> [u8; 16] {
+        let (mut bits, mut pos) = (0u128, 0);
+        for &(value, count) in fields {
+            bits |= (value as u128) << pos;
+            pos += count;
+        }
+        assert_eq!(pos, 128);
+        bits.to_le_bytes()
+    }
+
+    #[test]
+    fn test_bc7_mode6() {
+        // 模式 6：单分区，7 位颜色与透明度，每个端点一个 P 位，4 位索引
+        let mut fields = vec![(1 << 6, 7)];
+        // R0 R1 G0 G1 B0 B1 A0 A1
+        for value in [0x10, 0x7F, 0x20, 0x00, 0x30, 0x40, 0x7F, 0x00] {
+            fields.push((value, 7));
+        }
+        // P0 = 1, P1 = 0
+        fields.extend([(1, 1), (0, 1)]);
+        // 第 i 个像素的索引为 i，锚点只有 3 位
+        fields.push((0, 3));
+        fields.extend((1..16).map(|i| (i, 4)));
+        let pixels = decode_bc7_block(&pack(&fields));
+        assert_eq!(pixels[0], [0x21, 0x41, 0x61, 0xFF]);
+        assert_eq!(pixels[15], [0xFE, 0x00, 0x80, 0x00]);
+        // 索引 5 的权重为 21
+        assert_eq!(pixels[5], [0x6A, 0x2C, 0x6B, 0xAB]);
+    }
+
+    #[test]
+    fn test_bc7_mode1_partition() {
+        // 模式 1：双分区，划分方式 0 中右侧两列属于第二个分区，两个分区共享 P 位
+        let mut fields = vec![(0b10, 2), (0, 6)];
+        for channel in [[63, 63, 0, 0], [0, 0, 0, 0], [0, 0, 63, 63]] {
+            fields.extend(channel.map(|value| (value, 6)));
+        }
+        fields.extend([(1, 1), (1, 1)]);
+        // 两个锚点各少一位
+        fields.push((0, 46));
+        let pixels = decode_bc7_block(&pack(&fields));
+        for (i, pixel) in pixels.iter().enumerate() {
+            // P 位为 1 时值为 0 的分量扩展为 2
+            let expected = if i % 4 < 2 {
+                [255, 2, 2, 255]
+            } else {
+                [2, 2, 255, 255]
+            };
+            assert_eq!(*pixel, expected, "像素 {}", i);
+        }
+        assert_eq!(decode_bc7_block(&[0; 16]), [[0; 4]; 16]);
+    }
+
+    #[test]
+    fn test_bc6h_mode11() {
+        // 模式 11：单分区，10 位端点，不使用差值
+        let mut fields = vec![(0b00011, 5)];
+        for value in [0, 0, 0, 1023, 1023, 0] {
+            fields.push((value, 10));
+        }
+        fields.push((0, 3));
+        fields.extend((1..16).map(|i| (i, 4)));
+        let pixels = decode_bc6h_block(&pack(&fields), false);
+        assert_eq!(pixels[0], [0.0, 0.0, 0.0]);
+        // 最大的端点对应半精度浮点数的最大有限值
+        assert_eq!(pixels[15], [65504.0, 65504.0, 0.0]);
+        // 索引 8 的权重为 34，插值结果为半精度浮点数 0x41DF
+        let value = 2.0 * (1.0 + 479.0 / 1024.0);
+        assert_eq!(pixels[8], [value, value, 0.0]);
+
+        // 保留的模式解码为黑色
+        let reserved = pack(&[(0b10011, 5), (u32::MAX, 32), (u32::MAX, 32), (0, 59)]);
+        assert_eq!(decode_bc6h_block(&reserved, false), [[0.0; 3]; 16]);
+    }
+
+    #[test]
+    fn test_half_to_f32() {
+        assert_eq!(half_to_f32(0x3C00), 1.0);
+        assert_eq!(half_to_f32(0xC000), -2.0);
+        assert_eq!(half_to_f32(0x7BFF), 65504.0);
+        assert_eq!(half_to_f32(0x0001), 2f32.powi(-24));
+        assert!(half_to_f32(0x7C00).is_infinite());
+    }
+}
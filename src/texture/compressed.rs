@@ -0,0 +1,423 @@
+use gl::types::*;
+
+use super::{bcn::BcKind, info::mip_count};
+
+/// `GL_EXT_texture_compression_s3tc` 与 `GL_EXT_texture_sRGB` 中定义的常量
+const COMPRESSED_RGB_S3TC_DXT1: GLenum = 0x83F0;
+const COMPRESSED_RGBA_S3TC_DXT1: GLenum = 0x83F1;
+const COMPRESSED_RGBA_S3TC_DXT3: GLenum = 0x83F2;
+const COMPRESSED_RGBA_S3TC_DXT5: GLenum = 0x83F3;
+const COMPRESSED_SRGB_S3TC_DXT1: GLenum = 0x8C4C;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT1: GLenum = 0x8C4D;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT3: GLenum = 0x8C4E;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT5: GLenum = 0x8C4F;
+
+/// 块压缩格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressedFormat {
+    /// BC1(DXT1)，`alpha` 表示是否使用 1 位透明
+    Bc1 { alpha: bool },
+    /// BC2(DXT3)
+    Bc2,
+    /// BC3(DXT5)
+    Bc3,
+    /// BC4，单通道
+    Bc4 { signed: bool },
+    /// BC5，双通道，常用于法线贴图
+    Bc5 { signed: bool },
+    /// BC6H，高动态范围 RGB
+    Bc6h { signed: bool },
+    /// BC7，高质量 RGBA
+    Bc7,
+}
+
+impl CompressedFormat {
+    /// 每个 4x4 像素块占用的字节数
+    pub fn block_bytes(self) -> usize {
+        match self {
+            CompressedFormat::Bc1 { .. } | CompressedFormat::Bc4 { .. } => 8,
+            _ => 16,
+        }
+    }
+
+    /// 指定尺寸的一级纹理占用的字节数
+    ///
+    /// # 返回值 Returns
+    /// 字节数超出 `usize` 的范围时返回 `None`
+    pub fn level_size(self, width: u32, height: u32) -> Option<usize> {
+        let blocks_x = width.div_ceil(4).max(1) as usize;
+        let blocks_y = height.div_ceil(4).max(1) as usize;
+        blocks_x
+            .checked_mul(blocks_y)?
+            .checked_mul(self.block_bytes())
+    }
+
+    /// 第 `level` 级纹理占用的字节数，级数超出范围或字节数溢出时返回 `None`
+    fn mip_level_size(self, width: u32, height: u32, level: u32) -> Option<usize> {
+        let width = width.checked_shr(level)?.max(1);
+        let height = height.checked_shr(level)?.max(1);
+        self.level_size(width, height)
+    }
+
+    /// 对应的 OpenGL 内部格式
+    pub(crate) fn gl_internal_format(self, srgb: bool) -> GLenum {
+        match (self, srgb) {
+            (CompressedFormat::Bc1 { alpha: false }, false) => COMPRESSED_RGB_S3TC_DXT1,
+            (CompressedFormat::Bc1 { alpha: false }, true) => COMPRESSED_SRGB_S3TC_DXT1,
+            (CompressedFormat::Bc1 { alpha: true }, false) => COMPRESSED_RGBA_S3TC_DXT1,
+            (CompressedFormat::Bc1 { alpha: true }, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT1,
+            (CompressedFormat::Bc2, false) => COMPRESSED_RGBA_S3TC_DXT3,
+            (CompressedFormat::Bc2, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT3,
+            (CompressedFormat::Bc3, false) => COMPRESSED_RGBA_S3TC_DXT5,
+            (CompressedFormat::Bc3, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT5,
+            (CompressedFormat::Bc4 { signed: false }, _) => gl::COMPRESSED_RED_RGTC1,
+            (CompressedFormat::Bc4 { signed: true }, _) => gl::COMPRESSED_SIGNED_RED_RGTC1,
+            (CompressedFormat::Bc5 { signed: false }, _) => gl::COMPRESSED_RG_RGTC2,
+            (CompressedFormat::Bc5 { signed: true }, _) => gl::COMPRESSED_SIGNED_RG_RGTC2,
+            (CompressedFormat::Bc6h { signed: false }, _) => gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT,
+            (CompressedFormat::Bc6h { signed: true }, _) => gl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT,
+            (CompressedFormat::Bc7, false) => gl::COMPRESSED_RGBA_BPTC_UNORM,
+            (CompressedFormat::Bc7, true) => gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
+        }
+    }
+
+    /// 使用此格式所需的 OpenGL 扩展，`None` 表示 OpenGL 3.3 核心模式已支持
+    pub(crate) fn required_extension(self) -> Option<&'static str> {
+        match self {
+            CompressedFormat::Bc1 { .. } | CompressedFormat::Bc2 | CompressedFormat::Bc3 => {
+                Some("GL_EXT_texture_compression_s3tc")
+            }
+            CompressedFormat::Bc4 { .. } | CompressedFormat::Bc5 { .. } => None,
+            CompressedFormat::Bc6h { .. } | CompressedFormat::Bc7 => {
+                Some("GL_ARB_texture_compression_bptc")
+            }
+        }
+    }
+
+    /// 在 CPU 上解压缩时对应的块格式
+    pub(crate) fn bc_kind(self) -> BcKind {
+        match self {
+            CompressedFormat::Bc1 { .. } => BcKind::Bc1,
+            CompressedFormat::Bc2 => BcKind::Bc2,
+            CompressedFormat::Bc3 => BcKind::Bc3,
+            CompressedFormat::Bc4 { signed } => BcKind::Bc4 { signed },
+            CompressedFormat::Bc5 { signed } => BcKind::Bc5 { signed },
+            CompressedFormat::Bc6h { signed } => BcKind::Bc6h { signed },
+            CompressedFormat::Bc7 => BcKind::Bc7,
+        }
+    }
+}
+
+/// 从 DDS 或 KTX2 文件中读取的压缩纹理
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: CompressedFormat,
+    /// 文件中是否标记为 sRGB 格式
+    pub srgb: bool,
+    /// 各级多级渐远纹理的数据，第 0 级为原始尺寸
+    pub levels: Vec<Vec<u8>>,
+}
+
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or("文件头不完整".to_string())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, String> {
+    Ok(read_u32(data, offset)? as u64 | (read_u32(data, offset + 4)? as u64) << 32)
+}
+
+impl CompressedImage {
+    /// 数据是否为支持的压缩纹理容器
+    pub fn is_container(data: &[u8]) -> bool {
+        data.starts_with(DDS_MAGIC) || data.starts_with(&KTX2_MAGIC)
+    }
+
+    /// 按文件头自动识别 DDS 或 KTX2 格式并解析
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.starts_with(DDS_MAGIC) {
+            Self::parse_dds(data)
+        } else if data.starts_with(&KTX2_MAGIC) {
+            Self::parse_ktx2(data)
+        } else {
+            Err("不是 DDS 或 KTX2 文件".to_string())
+        }
+    }
+
+    /// 按顺序切分各级纹理数据
+    ///
+    /// # 注解 Note
+    ///
+    /// 级数不超过完整的多级渐远纹理级数，多出的级数被忽略
+    fn split_levels(
+        data: &[u8],
+        mut offset: usize,
+        width: u32,
+        height: u32,
+        format: CompressedFormat,
+        count: u32,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let mut levels = vec![];
+        for level in 0..count.clamp(1, mip_count(width, height)) {
+            let end = format
+                .mip_level_size(width, height, level)
+                .and_then(|size| offset.checked_add(size))
+                .ok_or(format!("第 {} 级纹理数据的尺寸超出范围", level))?;
+            match data.get(offset..end) {
+                Some(bytes) => levels.push(bytes.to_vec()),
+                None => return Err(format!("第 {} 级纹理数据不完整", level)),
+            }
+            offset = end;
+        }
+        Ok(levels)
+    }
+
+    /// 解析 DDS 文件，支持传统 FourCC 与 DX10 扩展头
+    ///
+    /// # 注解 Note
+    ///
+    /// 仅支持二维纹理，立方体贴图、纹理数组与体积纹理将返回错误
+    pub fn parse_dds(data: &[u8]) -> Result<Self, String> {
+        if !data.starts_with(DDS_MAGIC) || read_u32(data, 4)? != 124 {
+            return Err("无效的 DDS 文件头".to_string());
+        }
+        let height = read_u32(data, 12)?;
+        let width = read_u32(data, 16)?;
+        let depth = read_u32(data, 24)?;
+        let mip_count = read_u32(data, 28)?;
+        let four_cc = data.get(84..88).ok_or("文件头不完整".to_string())?;
+        let caps2 = read_u32(data, 112)?;
+        // DDSCAPS2_CUBEMAP 与 DDSCAPS2_VOLUME
+        if caps2 & 0x200 != 0 || caps2 & 0x200000 != 0 || depth > 1 {
+            return Err("仅支持二维 DDS 纹理".to_string());
+        }
+        let (format, srgb, offset) = match four_cc {
+            b"DXT1" => (CompressedFormat::Bc1 { alpha: true }, false, 128),
+            b"DXT2" | b"DXT3" => (CompressedFormat::Bc2, false, 128),
+            b"DXT4" | b"DXT5" => (CompressedFormat::Bc3, false, 128),
+            b"ATI1" | b"BC4U" => (CompressedFormat::Bc4 { signed: false }, false, 128),
+            b"BC4S" => (CompressedFormat::Bc4 { signed: true }, false, 128),
+            b"ATI2" | b"BC5U" => (CompressedFormat::Bc5 { signed: false }, false, 128),
+            b"BC5S" => (CompressedFormat::Bc5 { signed: true }, false, 128),
+            b"DX10" => {
+                let (format, srgb) = match read_u32(data, 128)? {
+                    71 => (CompressedFormat::Bc1 { alpha: true }, false),
+                    72 => (CompressedFormat::Bc1 { alpha: true }, true),
+                    74 => (CompressedFormat::Bc2, false),
+                    75 => (CompressedFormat::Bc2, true),
+                    77 => (CompressedFormat::Bc3, false),
+                    78 => (CompressedFormat::Bc3, true),
+                    80 => (CompressedFormat::Bc4 { signed: false }, false),
+                    81 => (CompressedFormat::Bc4 { signed: true }, false),
+                    83 => (CompressedFormat::Bc5 { signed: false }, false),
+                    84 => (CompressedFormat::Bc5 { signed: true }, false),
+                    95 => (CompressedFormat::Bc6h { signed: false }, false),
+                    96 => (CompressedFormat::Bc6h { signed: true }, false),
+                    98 => (CompressedFormat::Bc7, false),
+                    99 => (CompressedFormat::Bc7, true),
+                    other => return Err(format!("不支持的 DXGI 格式: {}", other)),
+                };
+                // 资源维度必须为 TEXTURE2D，且不是立方体贴图或数组
+                if read_u32(data, 132)? != 3
+                    || read_u32(data, 136)? & 0x4 != 0
+                    || read_u32(data, 140)? > 1
+                {
+                    return Err("仅支持二维 DDS 纹理".to_string());
+                }
+                (format, srgb, 148)
+            }
+            other => {
+                return Err(format!(
+                    "不支持的 DDS 像素格式: {}",
+                    String::from_utf8_lossy(other)
+                ))
+            }
+        };
+        let levels = Self::split_levels(data, offset, width, height, format, mip_count)?;
+        Ok(Self {
+            width,
+            height,
+            format,
+            srgb,
+            levels,
+        })
+    }
+
+    /// 解析 KTX2 文件
+    ///
+    /// # 注解 Note
+    ///
+    /// 仅支持未经超压缩(如 Basis Universal、Zstandard)的二维 BC 格式纹理
+    pub fn parse_ktx2(data: &[u8]) -> Result<Self, String> {
+        if !data.starts_with(&KTX2_MAGIC) {
+            return Err("无效的 KTX2 文件头".to_string());
+        }
+        let vk_format = read_u32(data, 12)?;
+        let width = read_u32(data, 20)?;
+        let height = read_u32(data, 24)?;
+        let depth = read_u32(data, 28)?;
+        let layers = read_u32(data, 32)?;
+        let faces = read_u32(data, 36)?;
+        let level_count = read_u32(data, 40)?.clamp(1, mip_count(width, height));
+        let supercompression = read_u32(data, 44)?;
+        if depth > 0 || layers > 0 || faces != 1 {
+            return Err("仅支持二维 KTX2 纹理".to_string());
+        }
+        if supercompression != 0 {
+            return Err(format!("不支持的 KTX2 超压缩方案: {}", supercompression));
+        }
+        let (format, srgb) = match vk_format {
+            131 => (CompressedFormat::Bc1 { alpha: false }, false),
+            132 => (CompressedFormat::Bc1 { alpha: false }, true),
+            133 => (CompressedFormat::Bc1 { alpha: true }, false),
+            134 => (CompressedFormat::Bc1 { alpha: true }, true),
+            135 => (CompressedFormat::Bc2, false),
+            136 => (CompressedFormat::Bc2, true),
+            137 => (CompressedFormat::Bc3, false),
+            138 => (CompressedFormat::Bc3, true),
+            139 => (CompressedFormat::Bc4 { signed: false }, false),
+            140 => (CompressedFormat::Bc4 { signed: true }, false),
+            141 => (CompressedFormat::Bc5 { signed: false }, false),
+            142 => (CompressedFormat::Bc5 { signed: true }, false),
+            143 => (CompressedFormat::Bc6h { signed: false }, false),
+            144 => (CompressedFormat::Bc6h { signed: true }, false),
+            145 => (CompressedFormat::Bc7, false),
+            146 => (CompressedFormat::Bc7, true),
+            other => return Err(format!("不支持的 KTX2 格式: VkFormat {}", other)),
+        };
+        let mut levels = vec![];
+        for level in 0..level_count {
+            let index = 80 + level as usize * 24;
+            let offset = usize::try_from(read_u64(data, index)?);
+            let length = usize::try_from(read_u64(data, index + 8)?);
+            let (offset, length) = match (offset, length) {
+                (Ok(offset), Ok(length)) => (offset, length),
+                _ => return Err(format!("第 {} 级纹理数据的偏移或长度超出范围", level)),
+            };
+            let expected = format
+                .mip_level_size(width, height, level)
+                .ok_or(format!("第 {} 级纹理数据的尺寸超出范围", level))?;
+            if length != expected {
+                return Err(format!(
+                    "第 {} 级纹理数据长度为 {}, 应为 {}",
+                    level, length, expected
+                ));
+            }
+            let end = offset
+                .checked_add(length)
+                .ok_or(format!("第 {} 级纹理数据的偏移或长度超出范围", level))?;
+            match data.get(offset..end) {
+                Some(bytes) => levels.push(bytes.to_vec()),
+                None => return Err(format!("第 {} 级纹理数据不完整", level)),
+            }
+        }
+        Ok(Self {
+            width,
+            height,
+            format,
+            srgb,
+            levels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dds(four_cc: &[u8; 4], width: u32, height: u32, mips: u32) -> Vec<u8> {
+        let mut data = vec![0u8; 128];
+        data[..4].copy_from_slice(DDS_MAGIC);
+        data[4..8].copy_from_slice(&124u32.to_le_bytes());
+        data[12..16].copy_from_slice(&height.to_le_bytes());
+        data[16..20].copy_from_slice(&width.to_le_bytes());
+        data[28..32].copy_from_slice(&mips.to_le_bytes());
+        data[84..88].copy_from_slice(four_cc);
+        data
+    }
+
+    #[test]
+    fn test_level_size() {
+        let bc1 = CompressedFormat::Bc1 { alpha: false };
+        assert_eq!(bc1.level_size(16, 16), Some(128));
+        assert_eq!(bc1.level_size(1, 1), Some(8));
+        assert_eq!(CompressedFormat::Bc7.level_size(6, 5), Some(64));
+        assert_eq!(bc1.mip_level_size(16, 16, 2), Some(8));
+        assert_eq!(bc1.mip_level_size(16, 16, 32), None);
+    }
+
+    #[test]
+    fn test_parse_dds() {
+        let mut data = dds(b"DXT5", 8, 8, 4);
+        // 8x8、4x4、2x2、1x1 分别需要 4、1、1、1 个块
+        data.extend((0..7 * 16).map(|i| i as u8));
+        let image = CompressedImage::parse(&data).unwrap();
+        assert_eq!(image.format, CompressedFormat::Bc3);
+        assert_eq!(image.levels.len(), 4);
+        assert_eq!(image.levels[0].len(), 64);
+        assert_eq!(image.levels[1][0], 64);
+
+        data.truncate(data.len() - 1);
+        assert!(CompressedImage::parse(&data).is_err());
+
+        let mut data = dds(b"DX10", 4, 4, 1);
+        for value in [99u32, 3, 0, 1, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend([0u8; 16]);
+        let image = CompressedImage::parse(&data).unwrap();
+        assert_eq!((image.format, image.srgb), (CompressedFormat::Bc7, true));
+
+        // 级数超出完整的多级渐远纹理级数时被截断
+        let mut data = dds(b"DXT1", 1, 1, u32::MAX);
+        data.extend([0u8; 8]);
+        assert_eq!(CompressedImage::parse(&data).unwrap().levels.len(), 1);
+        // 尺寸过大，数据不完整
+        let data = dds(b"DXT5", u32::MAX, u32::MAX, 64);
+        assert!(CompressedImage::parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_parse_ktx2() {
+        let mut data = KTX2_MAGIC.to_vec();
+        for value in [131u32, 1, 4, 4, 0, 0, 1, 1, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.resize(80, 0);
+        for value in [104u64, 8, 8] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend([7u8; 8]);
+        let image = CompressedImage::parse(&data).unwrap();
+        assert_eq!(image.format, CompressedFormat::Bc1 { alpha: false });
+        assert_eq!(image.levels, vec![vec![7u8; 8]]);
+
+        // 偏移与长度之和溢出
+        let mut overflow = data.clone();
+        overflow[80..88].copy_from_slice(&(u64::MAX - 4).to_le_bytes());
+        assert!(CompressedImage::parse(&overflow).is_err());
+
+        // 级数超出完整的多级渐远纹理级数时被截断
+        let mut levels = data.clone();
+        levels[20..28].copy_from_slice(&[1, 0, 0, 0, 1, 0, 0, 0]);
+        levels[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(CompressedImage::parse(&levels).unwrap().levels.len(), 1);
+
+        // 尺寸过大
+        let mut huge = data.clone();
+        huge[20..28].copy_from_slice(&[0xFF; 8]);
+        assert!(CompressedImage::parse(&huge).is_err());
+
+        // Basis Universal 超压缩
+        data[44] = 1;
+        assert!(CompressedImage::parse(&data).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use gl::types::*;
+use image::{DynamicImage, RgbaImage};
+
+use super::TextureOptions;
+
+/// 像素数据格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8 位单通道，适用于灰度图、高度图
+    R8,
+    /// 8 位 RGBA
+    Rgba8,
+    /// 16 位单通道
+    R16,
+    /// 16 位 RGBA
+    Rgba16,
+    /// 32 位浮点单通道，适用于亮度等标量数据
+    R32F,
+    /// 32 位浮点 RGB，由 `.hdr`、`.exr` 等高动态范围图像解码得到
+    Rgb32F,
+    /// 32 位浮点 RGBA
+    Rgba32F,
+}
+
+impl PixelFormat {
+    /// 每个像素的通道数
+    pub fn channels(self) -> usize {
+        match self {
+            PixelFormat::R8 | PixelFormat::R16 | PixelFormat::R32F => 1,
+            PixelFormat::Rgb32F => 3,
+            PixelFormat::Rgba8 | PixelFormat::Rgba16 | PixelFormat::Rgba32F => 4,
+        }
+    }
+
+    /// 每个像素占用的字节数
+    pub fn bytes_per_pixel(self) -> usize {
+        let channel = match self {
+            PixelFormat::R8 | PixelFormat::Rgba8 => 1,
+            PixelFormat::R16 | PixelFormat::Rgba16 => 2,
+            PixelFormat::R32F | PixelFormat::Rgb32F | PixelFormat::Rgba32F => 4,
+        };
+        self.channels() * channel
+    }
+
+    /// 指定尺寸的图像占用的字节数
+    pub fn image_size(self, width: u32, height: u32) -> usize {
+        width as usize * height as usize * self.bytes_per_pixel()
+    }
+
+    /// 是否为浮点格式
+    pub fn is_float(self) -> bool {
+        matches!(
+            self,
+            PixelFormat::R32F | PixelFormat::Rgb32F | PixelFormat::Rgba32F
+        )
+    }
+
+    /// 按选项存储时，每个像素在显存中占用的字节数
+    pub(crate) fn stored_bytes_per_pixel(self, options: &TextureOptions) -> usize {
+        if self.is_float() && options.half_float {
+            self.bytes_per_pixel() / 2
+        } else {
+            self.bytes_per_pixel()
+        }
+    }
+
+    /// 上传像素数据时使用的格式
+    pub(crate) fn gl_format(self) -> GLenum {
+        match self.channels() {
+            1 => gl::RED,
+            3 => gl::RGB,
+            _ => gl::RGBA,
+        }
+    }
+
+    /// 上传像素数据时使用的数据类型
+    pub(crate) fn gl_type(self) -> GLenum {
+        match self {
+            PixelFormat::R8 | PixelFormat::Rgba8 => gl::UNSIGNED_BYTE,
+            PixelFormat::R16 | PixelFormat::Rgba16 => gl::UNSIGNED_SHORT,
+            PixelFormat::R32F | PixelFormat::Rgb32F | PixelFormat::Rgba32F => gl::FLOAT,
+        }
+    }
+
+    /// 纹理在显存中的内部格式
+    ///
+    /// # 注解 Note
+    ///
+    /// `srgb` 仅对 8 位 RGBA 生效；`half_float` 决定浮点数据以 16 位还是 32 位浮点存储
+    pub(crate) fn gl_internal_format(self, options: &TextureOptions) -> GLenum {
+        match self {
+            PixelFormat::R8 => gl::R8,
+            PixelFormat::Rgba8 if options.srgb => gl::SRGB8_ALPHA8,
+            PixelFormat::Rgba8 => gl::RGBA8,
+            PixelFormat::R16 => gl::R16,
+            PixelFormat::Rgba16 => gl::RGBA16,
+            PixelFormat::R32F if options.half_float => gl::R16F,
+            PixelFormat::R32F => gl::R32F,
+            PixelFormat::Rgb32F if options.half_float => gl::RGB16F,
+            PixelFormat::Rgb32F => gl::RGB32F,
+            PixelFormat::Rgba32F if options.half_float => gl::RGBA16F,
+            PixelFormat::Rgba32F => gl::RGBA32F,
+        }
+    }
+}
+
+/// 深度(与模板)格式，用于帧缓冲的深度附件与阴影贴图
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthFormat {
+    /// 24 位定点深度
+    Depth24,
+    /// 32 位浮点深度
+    Depth32F,
+    /// 24 位深度与 8 位模板
+    Depth24Stencil8,
+    /// 32 位浮点深度与 8 位模板
+    Depth32FStencil8,
+}
+
+impl DepthFormat {
+    /// 是否包含模板分量
+    pub fn has_stencil(self) -> bool {
+        matches!(
+            self,
+            DepthFormat::Depth24Stencil8 | DepthFormat::Depth32FStencil8
+        )
+    }
+
+    /// 每个像素在显存中占用的字节数
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            DepthFormat::Depth32FStencil8 => 8,
+            _ => 4,
+        }
+    }
+
+    /// 纹理或渲染缓冲在显存中的内部格式
+    pub(crate) fn gl_internal_format(self) -> GLenum {
+        match self {
+            DepthFormat::Depth24 => gl::DEPTH_COMPONENT24,
+            DepthFormat::Depth32F => gl::DEPTH_COMPONENT32F,
+            DepthFormat::Depth24Stencil8 => gl::DEPTH24_STENCIL8,
+            DepthFormat::Depth32FStencil8 => gl::DEPTH32F_STENCIL8,
+        }
+    }
+
+    /// 分配存储空间时使用的格式
+    pub(crate) fn gl_format(self) -> GLenum {
+        if self.has_stencil() {
+            gl::DEPTH_STENCIL
+        } else {
+            gl::DEPTH_COMPONENT
+        }
+    }
+
+    /// 分配存储空间时使用的数据类型
+    pub(crate) fn gl_type(self) -> GLenum {
+        match self {
+            DepthFormat::Depth24 => gl::UNSIGNED_INT,
+            DepthFormat::Depth32F => gl::FLOAT,
+            DepthFormat::Depth24Stencil8 => gl::UNSIGNED_INT_24_8,
+            DepthFormat::Depth32FStencil8 => gl::FLOAT_32_UNSIGNED_INT_24_8_REV,
+        }
+    }
+
+    /// 附加到帧缓冲时使用的附着点
+    pub(crate) fn gl_attachment(self) -> GLenum {
+        if self.has_stencil() {
+            gl::DEPTH_STENCIL_ATTACHMENT
+        } else {
+            gl::DEPTH_ATTACHMENT
+        }
+    }
+}
+
+/// 保留原始精度的解码图像
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+    /// 按行紧密排列的像素数据，多字节通道使用本机字节序
+    pub data: Vec<u8>,
+}
+
+impl ImageData {
+    /// 由原始像素数据构造，数据长度必须与尺寸和格式相符
+    pub fn new(
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: Vec<u8>,
+    ) -> Result<Self, String> {
+        check_data(width, height, format, &data)?;
+        Ok(Self {
+            width,
+            height,
+            format,
+            data,
+        })
+    }
+
+    /// 由解码结果构造，尽量保留原始的通道数与精度
+    ///
+    /// # 注解 Note
+    ///
+    /// 单通道图像保持单通道；带透明度的灰度图与 8 位 RGB 图像转换为 RGBA8；
+    /// 16 位图像转换为 RGBA16；浮点图像保持浮点
+    pub fn from_dynamic(img: DynamicImage) -> Self {
+        let (width, height) = (img.width(), img.height());
+        let (format, data) = match img {
+            DynamicImage::ImageLuma8(img) => (PixelFormat::R8, img.into_raw()),
+            DynamicImage::ImageLuma16(img) => (PixelFormat::R16, u16_bytes(&img.into_raw())),
+            DynamicImage::ImageLumaA16(_)
+            | DynamicImage::ImageRgb16(_)
+            | DynamicImage::ImageRgba16(_) => {
+                (PixelFormat::Rgba16, u16_bytes(&img.to_rgba16().into_raw()))
+            }
+            DynamicImage::ImageRgb32F(img) => (PixelFormat::Rgb32F, f32_bytes(&img.into_raw())),
+            DynamicImage::ImageRgba32F(img) => (PixelFormat::Rgba32F, f32_bytes(&img.into_raw())),
+            img => (PixelFormat::Rgba8, img.to_rgba8().into_raw()),
+        };
+        Self {
+            width,
+            height,
+            format,
+            data,
+        }
+    }
+}
+
+impl From<RgbaImage> for ImageData {
+    fn from(img: RgbaImage) -> Self {
+        Self {
+            width: img.width(),
+            height: img.height(),
+            format: PixelFormat::Rgba8,
+            data: img.into_raw(),
+        }
+    }
+}
+
+/// 检查像素数据长度是否与尺寸和格式相符
+pub(crate) fn check_data(
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    data: &[u8],
+) -> Result<(), String> {
+    let expected = format.image_size(width, height);
+    if data.len() != expected {
+        return Err(format!(
+            "{}x{} 的 {:?} 图像需要 {} 字节, 实际为 {} 字节",
+            width,
+            height,
+            format,
+            expected,
+            data.len()
+        ));
+    }
+    Ok(())
+}
+
+/// 检查区域是否位于纹理范围内
+pub(crate) fn check_region(
+    size: (u32, u32),
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    if x as u64 + width as u64 > size.0 as u64 || y as u64 + height as u64 > size.1 as u64 {
+        return Err(format!(
+            "区域 ({}, {}, {}x{}) 超出了 {}x{} 的纹理范围",
+            x, y, width, height, size.0, size.1
+        ));
+    }
+    Ok(())
+}
+
+fn u16_bytes(data: &[u16]) -> Vec<u8> {
+    data.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+fn f32_bytes(data: &[f32]) -> Vec<u8> {
+    data.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, ImageBuffer, Luma, Rgb, Rgb32FImage, RgbImage};
+
+    #[test]
+    fn test_from_dynamic() {
+        let gray = ImageData::from_dynamic(DynamicImage::ImageLuma8(GrayImage::new(3, 2)));
+        assert_eq!(gray.format, PixelFormat::R8);
+        assert_eq!(gray.data.len(), 6);
+
+        let rgb = ImageData::from_dynamic(DynamicImage::ImageRgb8(RgbImage::new(2, 2)));
+        assert_eq!(rgb.format, PixelFormat::Rgba8);
+        assert_eq!(rgb.data.len(), 16);
+
+        let gray16 = ImageBuffer::<Luma<u16>, _>::from_pixel(2, 1, Luma([0x1234]));
+        let gray16 = ImageData::from_dynamic(DynamicImage::ImageLuma16(gray16));
+        assert_eq!(gray16.format, PixelFormat::R16);
+        assert_eq!(gray16.data[..2], 0x1234u16.to_ne_bytes());
+
+        let hdr = Rgb32FImage::from_pixel(1, 1, Rgb([1.5, 2.0, 100.0]));
+        let hdr = ImageData::from_dynamic(DynamicImage::ImageRgb32F(hdr));
+        assert_eq!(hdr.format, PixelFormat::Rgb32F);
+        assert_eq!(hdr.data.len(), hdr.format.bytes_per_pixel());
+        assert_eq!(hdr.data[8..12], 100.0f32.to_ne_bytes());
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(ImageData::new(2, 2, PixelFormat::R8, vec![0; 4]).is_ok());
+        assert!(ImageData::new(2, 2, PixelFormat::Rgba8, vec![0; 4]).is_err());
+        assert_eq!(PixelFormat::Rgb32F.image_size(3, 2), 72);
+        assert!(check_region((16, 16), 8, 8, 8, 8).is_ok());
+        assert!(check_region((16, 16), 8, 9, 8, 8).is_err());
+        assert!(check_region((16, 16), u32::MAX, 0, 2, 1).is_err());
+    }
+
+    #[test]
+    fn test_internal_format() {
+        let options = TextureOptions::default();
+        assert_eq!(PixelFormat::Rgb32F.gl_internal_format(&options), gl::RGB16F);
+        assert_eq!(
+            PixelFormat::Rgb32F.gl_internal_format(&options.half_float(false)),
+            gl::RGB32F
+        );
+        assert_eq!(
+            PixelFormat::Rgba8.gl_internal_format(&options.srgb(true)),
+            gl::SRGB8_ALPHA8
+        );
+        assert_eq!(
+            PixelFormat::R8.gl_internal_format(&options.srgb(true)),
+            gl::R8
+        );
+        assert_eq!(PixelFormat::Rgba16.gl_type(), gl::UNSIGNED_SHORT);
+        assert_eq!(
+            DepthFormat::Depth24Stencil8.gl_attachment(),
+            gl::DEPTH_STENCIL_ATTACHMENT
+        );
+        assert_eq!(DepthFormat::Depth32F.gl_format(), gl::DEPTH_COMPONENT);
+        assert_eq!(PixelFormat::Rgba32F.stored_bytes_per_pixel(&options), 8);
+        assert_eq!(PixelFormat::R32F.gl_internal_format(&options), gl::R16F);
+        assert_eq!(PixelFormat::R32F.gl_format(), gl::RED);
+        assert_eq!(PixelFormat::Rgba8.stored_bytes_per_pixel(&options), 4);
+    }
+}
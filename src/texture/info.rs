@@ -0,0 +1,68 @@
+use gl::types::*;
+
+use super::PixelFormat;
+
+/// 纹理的尺寸、格式与显存占用等元数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureInfo {
+    pub width: u32,
+    pub height: u32,
+    /// 纹理数组的层数，立方体贴图为 6，二维纹理为 1
+    pub layers: u32,
+    /// 多级渐远纹理的级数，未启用时为 1
+    pub mip_levels: u32,
+    /// 纹理目标，例如 `GL_TEXTURE_2D`
+    pub target: GLenum,
+    /// 纹理在显存中的内部格式
+    pub internal_format: GLenum,
+    /// 未压缩纹理的像素格式，块压缩纹理与深度纹理为 `None`
+    pub format: Option<PixelFormat>,
+    /// 估算的显存占用(字节)
+    pub memory: usize,
+}
+
+/// 完整的多级渐远纹理级数
+pub(crate) fn mip_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// 估算单层纹理前 `levels` 级占用的字节数
+///
+/// # 参数 Parameters
+/// * `bytes_per_pixel` - 显存中每个像素占用的字节数
+pub(crate) fn mip_chain_size(
+    width: u32,
+    height: u32,
+    levels: u32,
+    bytes_per_pixel: usize,
+) -> usize {
+    (0..levels)
+        .map(|level| {
+            let w = (width >> level).max(1) as usize;
+            let h = (height >> level).max(1) as usize;
+            w * h * bytes_per_pixel
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mip_count() {
+        assert_eq!(mip_count(1, 1), 1);
+        assert_eq!(mip_count(256, 256), 9);
+        assert_eq!(mip_count(300, 17), 9);
+        assert_eq!(mip_count(0, 0), 1);
+    }
+
+    #[test]
+    fn test_mip_chain_size() {
+        assert_eq!(mip_chain_size(4, 4, 1, 4), 64);
+        // 4x4 + 2x2 + 1x1
+        assert_eq!(mip_chain_size(4, 4, 3, 4), 84);
+        // 4x1 + 2x1 + 1x1
+        assert_eq!(mip_chain_size(4, 1, 3, 1), 7);
+    }
+}
@@ -0,0 +1,294 @@
+use image::{imageops, RgbaImage};
+use json::JsonValue;
+
+use super::TextureOptions;
+
+/// 立方体贴图的面，顺序与 `GL_TEXTURE_CUBE_MAP_POSITIVE_X + i` 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    /// 右
+    PositiveX,
+    /// 左
+    NegativeX,
+    /// 上
+    PositiveY,
+    /// 下
+    NegativeY,
+    /// 前
+    PositiveZ,
+    /// 后
+    NegativeZ,
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    /// 由文件名(不含扩展名)识别立方体贴图的面
+    ///
+    /// # 注解 Note
+    ///
+    /// 支持 `right/left/top/bottom/front/back` 与 `px/nx/py/ny/pz/nz` 两套命名，不区分大小写
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "right" | "px" | "posx" => Some(CubeFace::PositiveX),
+            "left" | "nx" | "negx" => Some(CubeFace::NegativeX),
+            "top" | "up" | "py" | "posy" => Some(CubeFace::PositiveY),
+            "bottom" | "down" | "ny" | "negy" => Some(CubeFace::NegativeY),
+            "front" | "pz" | "posz" => Some(CubeFace::PositiveZ),
+            "back" | "nz" | "negz" => Some(CubeFace::NegativeZ),
+            _ => None,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 检查各层图像尺寸是否一致，返回该尺寸
+pub(crate) fn check_layers(images: &[RgbaImage]) -> Result<(u32, u32), String> {
+    let first = match images.first() {
+        Some(img) => img.dimensions(),
+        None => return Err("纹理数组中没有任何图像".to_string()),
+    };
+    for (i, img) in images.iter().enumerate() {
+        if img.dimensions() != first {
+            return Err(format!(
+                "第 {} 层图像尺寸为 {:?}, 与第 0 层的 {:?} 不一致",
+                i,
+                img.dimensions(),
+                first
+            ));
+        }
+    }
+    Ok(first)
+}
+
+/// 检查立方体贴图的六个面是否为尺寸一致的正方形，返回边长
+pub(crate) fn check_faces(faces: &[RgbaImage]) -> Result<u32, String> {
+    if faces.len() != 6 {
+        return Err(format!("立方体贴图需要 6 个面, 实际为 {}", faces.len()));
+    }
+    let (width, height) = check_layers(faces)?;
+    if width != height {
+        return Err(format!(
+            "立方体贴图的面必须为正方形, 实际为 {}x{}",
+            width, height
+        ));
+    }
+    Ok(width)
+}
+
+/// 将十字布局的图像拆分为立方体贴图的六个面
+///
+/// # 注解 Note
+///
+/// 支持横向(4x3)与纵向(3x4)两种十字布局：
+/// ```text
+///  横向           纵向
+///     +Y            +Y
+/// -X  +Z  +X  -Z  -X  +Z  +X
+///     -Y            -Y
+///                   -Z
+/// ```
+/// 纵向布局中的 -Z 面是倒置的，拆分时会旋转 180 度
+pub fn split_cross(img: &RgbaImage) -> Result<Vec<RgbaImage>, String> {
+    let (width, height) = img.dimensions();
+    let (size, cells, vertical) = if width * 3 == height * 4 && width % 4 == 0 {
+        (
+            width / 4,
+            [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)],
+            false,
+        )
+    } else if width * 4 == height * 3 && width % 3 == 0 {
+        (
+            width / 3,
+            [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)],
+            true,
+        )
+    } else {
+        return Err(format!("图像尺寸 {}x{} 不是十字布局", width, height));
+    };
+    let faces = cells
+        .iter()
+        .enumerate()
+        .map(|(i, &(cx, cy))| {
+            let face = imageops::crop_imm(img, cx * size, cy * size, size, size).to_image();
+            if vertical && i == CubeFace::NegativeZ.index() {
+                imageops::rotate180(&face)
+            } else {
+                face
+            }
+        })
+        .collect();
+    Ok(faces)
+}
+
+/// 多层纹理的来源
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LayeredSource {
+    /// 纹理数组，每项为一层图像的路径
+    Array(Vec<String>),
+    /// 由六个面的图像构成的立方体贴图
+    CubeFaces(Vec<String>),
+    /// 由十字布局图像构成的立方体贴图
+    CubeCross(String),
+}
+
+/// 多层纹理清单
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LayeredManifest {
+    pub name: Option<String>,
+    pub source: LayeredSource,
+    pub options: TextureOptions,
+}
+
+impl LayeredManifest {
+    /// 解析清单文件
+    ///
+    /// # 参数 Parameters
+    /// * `json` - 清单内容
+    /// * `dir` - 清单所在目录，清单中的路径相对于此目录
+    /// * `defaults` - 默认纹理选项，将被清单中的 `options` 字段覆盖
+    pub fn parse(json: &JsonValue, dir: &str, defaults: TextureOptions) -> Result<Self, String> {
+        let path = |value: &JsonValue| -> Result<String, String> {
+            match value.as_str() {
+                Some(path) if dir.is_empty() => Ok(path.to_string()),
+                Some(path) => Ok(format!("{}/{}", dir, path)),
+                None => Err("纹理清单中的路径不是字符串".to_string()),
+            }
+        };
+        let source = match json["type"].as_str() {
+            Some("array") => {
+                if !json["layers"].is_array() || json["layers"].is_empty() {
+                    return Err("纹理数组清单缺少 layers 字段".to_string());
+                }
+                let layers = json["layers"]
+                    .members()
+                    .map(path)
+                    .collect::<Result<Vec<_>, _>>()?;
+                LayeredSource::Array(layers)
+            }
+            Some("cubemap") if json.has_key("cross") => {
+                LayeredSource::CubeCross(path(&json["cross"])?)
+            }
+            Some("cubemap") => {
+                let mut faces = vec![String::new(); 6];
+                for (key, value) in json["faces"].entries() {
+                    match CubeFace::from_name(key) {
+                        Some(face) => faces[face.index()] = path(value)?,
+                        None => return Err(format!("无效的立方体贴图面: {}", key)),
+                    }
+                }
+                if faces.iter().any(|face| face.is_empty()) {
+                    return Err("立方体贴图清单需要 cross 字段或完整的 faces 字段".to_string());
+                }
+                LayeredSource::CubeFaces(faces)
+            }
+            _ => return Err("纹理清单的 type 字段必须是 array 或 cubemap".to_string()),
+        };
+        let options = if json.has_key("options") {
+            defaults.merge_json(&json["options"])?
+        } else {
+            defaults
+        };
+        Ok(Self {
+            name: json["name"].as_str().map(|name| name.to_string()),
+            source,
+            options,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// 每个格子填充为其坐标编码的颜色
+    fn cross(cols: u32, rows: u32, size: u32) -> RgbaImage {
+        RgbaImage::from_fn(cols * size, rows * size, |x, y| {
+            Rgba([(x / size) as u8, (y / size) as u8, 0, 255])
+        })
+    }
+
+    #[test]
+    fn test_split_cross() {
+        let faces = split_cross(&cross(4, 3, 2)).unwrap();
+        let cells = faces
+            .iter()
+            .map(|f| f.get_pixel(0, 0).0)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cells.iter().map(|c| (c[0], c[1])).collect::<Vec<_>>(),
+            vec![(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)]
+        );
+        assert_eq!(faces[0].dimensions(), (2, 2));
+
+        let mut img = cross(3, 4, 2);
+        img.put_pixel(2, 6, Rgba([9, 9, 9, 255]));
+        let faces = split_cross(&img).unwrap();
+        assert_eq!(
+            faces[CubeFace::NegativeZ.index()].get_pixel(1, 1).0,
+            [9, 9, 9, 255]
+        );
+
+        assert!(split_cross(&RgbaImage::new(5, 3)).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let a = RgbaImage::new(4, 4);
+        let b = RgbaImage::new(4, 2);
+        assert_eq!(check_layers(&[a.clone(), a.clone()]), Ok((4, 4)));
+        assert!(check_layers(&[a.clone(), b.clone()]).is_err());
+        assert!(check_layers(&[]).is_err());
+        assert_eq!(check_faces(&vec![a.clone(); 6]), Ok(4));
+        assert!(check_faces(&vec![b; 6]).is_err());
+        assert!(check_faces(&vec![a; 5]).is_err());
+    }
+
+    #[test]
+    fn test_manifest() {
+        let defaults = TextureOptions::default();
+        let json = json::parse(r#"{ "type": "array", "layers": ["a.png", "b.png"] }"#).unwrap();
+        let manifest = LayeredManifest::parse(&json, "blocks", defaults).unwrap();
+        assert_eq!(
+            manifest.source,
+            LayeredSource::Array(vec!["blocks/a.png".into(), "blocks/b.png".into()])
+        );
+
+        let json = json::parse(
+            r#"{ "type": "cubemap", "name": "sky", "options": { "mipmaps": false },
+                 "faces": { "px": "1", "nx": "2", "py": "3", "ny": "4", "pz": "5", "back": "6" } }"#,
+        )
+        .unwrap();
+        let manifest = LayeredManifest::parse(&json, "", defaults).unwrap();
+        assert_eq!(manifest.name.as_deref(), Some("sky"));
+        assert!(!manifest.options.mipmaps);
+        assert_eq!(
+            manifest.source,
+            LayeredSource::CubeFaces(
+                vec!["1", "2", "3", "4", "5", "6"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            )
+        );
+
+        for text in [
+            r#"{ "type": "cubemap", "faces": { "px": "1" } }"#,
+            r#"{ "type": "array", "layers": [] }"#,
+            r#"{ "type": "volume" }"#,
+        ] {
+            assert!(LayeredManifest::parse(&json::parse(text).unwrap(), "", defaults).is_err());
+        }
+    }
+}
@@ -1,20 +1,92 @@
 use std::collections::HashMap;
+mod atlas;
+mod bcn;
+mod bptc;
+mod compressed;
+mod format;
+mod info;
+mod layered;
+mod options;
 pub mod texture;
-use crate::debug;
+use crate::{
+    assets::loader::{UploadTask, ASSET_LOADER},
+    debug,
+    vfs::{normalize, ScanEntry, ScanOptions, VFS},
+    Handle, ASSETS,
+};
+pub use atlas::{AtlasLayout, AtlasRect, TextureAtlas, TextureAtlasBuilder, UvRect};
+pub use compressed::{CompressedFormat, CompressedImage};
+pub use format::{DepthFormat, ImageData, PixelFormat};
+pub use info::TextureInfo;
+pub use layered::{split_cross, CubeFace};
+use layered::{LayeredManifest, LayeredSource};
+pub use options::{Filter, TextureOptions, Wrap};
 use texture::Texture;
 
 /// 纹理资源管理器
+///
+/// 纹理数据存储于资源系统 `ASSETS` 中，管理器仅持有其句柄
 pub struct TextureManager {
-    textures: HashMap<String, Texture>,
+    textures: HashMap<String, Handle<Texture>>,
+    /// 纹理名称对应的文件路径，用于检测名称冲突
+    sources: HashMap<String, String>,
+    /// 从目录加载纹理时使用的默认选项
+    default_options: TextureOptions,
+    /// 纹理数组中各层的名称，按层序号排列
+    layers: HashMap<String, Vec<String>>,
 }
 
 impl TextureManager {
-    pub fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             textures: HashMap::new(),
+            sources: HashMap::new(),
+            default_options: TextureOptions::default(),
+            layers: HashMap::new(),
         }
     }
 
+    /// 按选项扫描目录，并剔除名称冲突的文件
+    ///
+    /// # 注解 Note
+    ///
+    /// 同一次扫描中名称相同的文件仅保留路径排序靠前的一个；
+    /// 与之前加载的纹理名称相同时，新纹理将覆盖旧纹理。两种情况都会输出警告信息
+    fn scan(&mut self, dir: &str, options: &ScanOptions) -> Vec<ScanEntry> {
+        let entries = VFS.scan(dir, options);
+        if entries.is_empty() {
+            warn!("TextureManager", "目录 {} 中没有任何匹配的文件", dir);
+        }
+        let mut seen: HashMap<String, String> = HashMap::new();
+        let mut ret = vec![];
+        for entry in entries {
+            // 跳过纹理附属选项文件
+            if entry.ext == "json" {
+                continue;
+            }
+            debug!("TextureManager", "检索到文件 {}", entry.path);
+            if let Some(first) = seen.get(&entry.name) {
+                warn!(
+                    "TextureManager",
+                    "纹理名称冲突: {} 同时对应 {} 与 {}, 已忽略后者", entry.name, first, entry.path
+                );
+                continue;
+            }
+            if let Some(old) = self.sources.get(&entry.name) {
+                if *old != entry.path {
+                    warn!(
+                        "TextureManager",
+                        "纹理名称冲突: {} 原对应 {}, 将被 {} 覆盖", entry.name, old, entry.path
+                    );
+                }
+            }
+            seen.insert(entry.name.clone(), entry.path.clone());
+            self.sources.insert(entry.name.clone(), entry.path.clone());
+            ret.push(entry);
+        }
+        ret
+    }
+
     /// 加载指定目录下的纹理
     ///
     /// # 参数 Parameters
@@ -24,58 +96,202 @@ impl TextureManager {
     ///
     /// 加载的纹理将以它的文件名作为名称存储在管理器中
     pub fn load_from(&mut self, dir: &str) {
-        use std::fs::*;
-        let entries = if let Ok(entries) = read_dir(dir) {
-            entries
-        } else {
-            warn!("TextureManager", "无法读取目录 {}", dir);
-            return;
+        self.load_from_with(dir, &ScanOptions::default());
+    }
+
+    /// 按扫描选项加载指定目录下的纹理
+    ///
+    /// # 参数 Parameters
+    /// * `dir` - 目录路径
+    /// * `options` - 扫描选项，可指定递归扫描与通配符过滤
+    ///
+    /// # 注解 Note
+    ///
+    /// 纹理名称为相对于 `dir`、不含扩展名的路径，例如 `blocks/stone`
+    ///
+    /// 纹理使用管理器的默认选项，若存在附属选项文件 `<文件名>.json`(如 `stone.png.json`)，
+    /// 则以其中的字段覆盖默认选项
+    pub fn load_from_with(&mut self, dir: &str, options: &ScanOptions) {
+        for entry in self.scan(dir, options) {
+            let tex_options = self.default_options.merge_sidecar(&entry.path);
+            let handle = ASSETS.load_with(&entry.name, || {
+                Texture::new_from_with(&entry.path, &tex_options)
+                    .ok_or(format!("无法加载纹理 {}", entry.path))
+            });
+            if ASSETS.state(&handle) == Some(LoadState::Ready) {
+                self.textures.insert(entry.name, handle);
+            }
+        }
+    }
+
+    /// 在后台线程中解码指定目录下的纹理，并在渲染线程中分批上传
+    ///
+    /// # 参数 Parameters
+    /// * `dir` - 目录路径
+    /// * `options` - 扫描选项
+    ///
+    /// # 注解 Note
+    ///
+    /// 纹理在提交后立即以 `Queued` 状态登记，上传完成前绑定该纹理不会产生任何效果
+    pub fn load_from_async_with(&mut self, dir: &str, options: &ScanOptions) {
+        for entry in self.scan(dir, options) {
+            let handle = ASSETS.reserve::<Texture>(&entry.name);
+            self.textures.insert(entry.name, handle.clone());
+            let path = entry.path;
+            let default_options = self.default_options;
+            ASSET_LOADER.submit(move || -> UploadTask {
+                ASSETS.set_state(&handle, LoadState::Loading);
+                let options = default_options.merge_sidecar(&path);
+                let result = Texture::decode_any(&path);
+                Box::new(move || {
+                    let result = result.and_then(|data| Texture::from_data(&data, &options));
+                    ASSETS.complete(&handle, result);
+                })
+            });
+        }
+    }
+
+    /// 加载多层纹理并登记到管理器中
+    fn load_layered(&mut self, name: &str, source: &LayeredSource, options: &TextureOptions) {
+        let decode_all = |paths: &[String]| -> Result<Vec<image::RgbaImage>, String> {
+            paths.iter().map(|path| Texture::decode(path)).collect()
         };
-        // 遍历目录中的文件
-        for entry in entries {
-            let entry = match entry {
-                Ok(entry) => entry,
-                Err(err) => {
-                    warn!("TextureManager", "异常由于 {}", err);
-                    continue;
-                }
-            };
-            debug!("TextureManager", "检索到文件 {}", entry.path().display());
-            let path = entry.path();
-            let str_path = if let Some(str_path) = path.to_str() {
-                str_path
-            } else {
-                warn!("TextureManager", "无法转换路径为字符串 {}", path.display());
-                continue;
-            };
-            let name = if let Some(name) = path.file_stem() {
-                if let Some(name) = name.to_str() {
-                    name.to_string()
-                } else {
-                    warn!(
-                        "TextureManager",
-                        "无法转换文件名为字符串 {}",
-                        path.display()
-                    );
-                    continue;
+        let handle = ASSETS.load_with(name, || match source {
+            LayeredSource::Array(paths) => Texture::array_from_images(&decode_all(paths)?, options),
+            LayeredSource::CubeFaces(paths) => {
+                Texture::cubemap_from_faces(&decode_all(paths)?, options)
+            }
+            LayeredSource::CubeCross(path) => {
+                Texture::cubemap_from_faces(&split_cross(&Texture::decode(path)?)?, options)
+            }
+        });
+        match ASSETS.state(&handle) {
+            Some(LoadState::Ready) => {
+                if let LayeredSource::Array(paths) = source {
+                    let names = paths.iter().map(|path| layer_name(path)).collect();
+                    self.layers.insert(name.to_string(), names);
                 }
-            } else {
-                warn!("TextureManager", "无效的文件路径 {}", path.display());
-                continue;
-            };
-            let tex = if let Some(tex) = Texture::new_from(str_path) {
-                tex
-            } else {
-                continue;
+                self.textures.insert(name.to_string(), handle);
+            }
+            Some(LoadState::Failed(e)) => {
+                warn!("TextureManager", "无法加载纹理 {}: {}", name, e);
+            }
+            _ => {}
+        }
+    }
+
+    /// 将目录下的所有图像按路径顺序加载为二维纹理数组
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理名称
+    /// * `dir` - 目录路径，目录下的图像尺寸必须一致
+    /// * `options` - 扫描选项
+    pub fn load_array(&mut self, name: &str, dir: &str, options: &ScanOptions) {
+        let paths = VFS
+            .scan(dir, options)
+            .into_iter()
+            .filter(|entry| entry.ext != "json")
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        let tex_options = self.default_options;
+        self.load_layered(name, &LayeredSource::Array(paths), &tex_options);
+    }
+
+    /// 加载立方体贴图
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理名称
+    /// * `path` - 十字布局图像的路径，或包含六个面图像的目录
+    ///
+    /// # 注解 Note
+    ///
+    /// 目录中的面图像以 `right/left/top/bottom/front/back` 或 `px/nx/py/ny/pz/nz` 命名，
+    /// 扩展名任意
+    pub fn load_cubemap(&mut self, name: &str, path: &str) {
+        let tex_options = self.default_options.wrap(Wrap::ClampToEdge);
+        if VFS.exists(path) {
+            self.load_layered(
+                name,
+                &LayeredSource::CubeCross(path.to_string()),
+                &tex_options,
+            );
+            return;
+        }
+        let mut faces = vec![String::new(); 6];
+        for entry in VFS.scan(path, &ScanOptions::default()) {
+            if let Some(face) = CubeFace::from_name(&entry.name) {
+                faces[face.index()] = entry.path;
+            }
+        }
+        if let Some(i) = faces.iter().position(|face| face.is_empty()) {
+            warn!(
+                "TextureManager",
+                "无法加载立方体贴图 {}: 目录 {} 中缺少 {:?} 面",
+                name,
+                path,
+                CubeFace::ALL[i]
+            );
+            return;
+        }
+        self.load_layered(name, &LayeredSource::CubeFaces(faces), &tex_options);
+    }
+
+    /// 按清单文件加载纹理数组或立方体贴图
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 清单文件路径
+    ///
+    /// # 注解 Note
+    ///
+    /// 清单中的路径相对于清单所在目录，纹理名称默认为清单的文件名(不含扩展名)：
+    /// ```json
+    /// { "type": "array", "layers": ["stone.png", "dirt.png"], "options": { "filter": "nearest" } }
+    /// { "type": "cubemap", "cross": "sky.png" }
+    /// { "type": "cubemap", "faces": { "right": "px.png", "left": "nx.png", "top": "py.png",
+    ///                                 "bottom": "ny.png", "front": "pz.png", "back": "nz.png" } }
+    /// ```
+    pub fn load_manifest(&mut self, path: &str) {
+        let path = normalize(path);
+        let (dir, file) = match path.rfind('/') {
+            Some(pos) => (&path[..pos], &path[pos + 1..]),
+            None => ("", path.as_str()),
+        };
+        let defaults = self.default_options;
+        let manifest = VFS.read_to_string(&path).and_then(|text| {
+            let json = json::parse(&text).map_err(|e| format!("JSON 解析错误: {}", e))?;
+            let defaults = match json["type"].as_str() {
+                Some("cubemap") => defaults.wrap(Wrap::ClampToEdge),
+                _ => defaults,
             };
-            self.textures.insert(name, tex);
+            LayeredManifest::parse(&json, dir, defaults)
+        });
+        match manifest {
+            Ok(manifest) => {
+                let name = match &manifest.name {
+                    Some(name) => name.clone(),
+                    None => file.split('.').next().unwrap_or(file).to_string(),
+                };
+                self.load_layered(&name, &manifest.source, &manifest.options);
+            }
+            Err(e) => {
+                warn!("TextureManager", "无效的纹理清单 {}: {}", path, e);
+            }
         }
     }
 }
 
+/// 纹理数组中一层的名称，即图像的文件名(不含扩展名)
+fn layer_name(path: &str) -> String {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    match file.rfind('.') {
+        Some(pos) => file[..pos].to_string(),
+        None => file.to_string(),
+    }
+}
+
 use lazy_static::lazy_static;
 
-use crate::{warn, RustCraftWrapper};
+use crate::{warn, LoadState, RustCraftWrapper};
 lazy_static! {
     /// 纹理资源管理器
     pub static ref TEXTURE_MANAGER: RustCraftWrapper<TextureManager> =
@@ -96,6 +312,126 @@ impl RustCraftWrapper<TextureManager> {
         self.apply(|tm| tm.load_from(dir));
     }
 
+    /// 在后台线程中加载指定目录下的纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数可以在任意线程中调用，纹理的上传由渲染线程在每帧开始时分批完成，
+    /// 加载进度可通过 `ASSET_LOADER.progress()` 查询
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 目录路径
+    pub fn load_from_async(&self, dir: &str) {
+        debug!("RCW<TextureManager>", "正在从 {} 异步加载纹理", dir);
+        self.apply(|tm| tm.load_from_async_with(dir, &ScanOptions::default()));
+    }
+
+    /// 按扫描选项加载指定目录下的纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `dir` - 目录路径
+    /// * `options` - 扫描选项，可指定递归扫描与通配符过滤
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// // 递归加载 texture/ 下的所有 png 纹理，名称形如 `blocks/stone`
+    /// TEXTURE_MANAGER.load_from_with("texture/", &ScanOptions::recursive().include("**/*.png"));
+    /// ```
+    pub fn load_from_with(&self, dir: &str, options: &ScanOptions) {
+        debug!("RCW<TextureManager>", "正在从 {} 加载纹理", dir);
+        self.apply(|tm| tm.load_from_with(dir, options));
+    }
+
+    /// 按扫描选项在后台线程中加载指定目录下的纹理
+    ///
+    /// # 参数 Parameters
+    /// * `dir` - 目录路径
+    /// * `options` - 扫描选项
+    pub fn load_from_async_with(&self, dir: &str, options: &ScanOptions) {
+        debug!("RCW<TextureManager>", "正在从 {} 异步加载纹理", dir);
+        self.apply(|tm| tm.load_from_async_with(dir, options));
+    }
+
+    /// 以指定名称登记纹理，例如由 `Texture::from_raw` 生成的程序化纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 已存在同名纹理时，新纹理将覆盖旧纹理
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理名称
+    /// * `texture` - 纹理
+    pub fn add(&self, name: &str, texture: Texture) -> Handle<Texture> {
+        let handle = ASSETS.insert(name, texture);
+        self.apply(|tm| {
+            tm.sources.remove(name);
+            tm.layers.remove(name);
+            tm.textures.insert(name.to_string(), handle.clone());
+        });
+        handle
+    }
+
+    /// 更新指定名称纹理的部分区域，参见 `Texture::update_region`
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn update_region(
+        &self,
+        name: &str,
+        (x, y, width, height): (u32, u32, u32, u32),
+        format: PixelFormat,
+        data: &[u8],
+    ) {
+        self.apply(|tm| {
+            let handle = match tm.textures.get(name) {
+                Some(handle) => handle,
+                None => {
+                    warn!("RCW<TextureManager>", "没有名为 {} 的纹理", name);
+                    return;
+                }
+            };
+            let result = ASSETS.with_mut(handle, |texture| {
+                texture.update_region(x, y, width, height, format, data)
+            });
+            if let Some(Err(e)) = result {
+                warn!("RCW<TextureManager>", "无法更新纹理 {}: {}", name, e);
+            }
+        });
+    }
+
+    /// 设置从目录加载纹理时使用的默认选项
+    ///
+    /// # 注解 Note
+    ///
+    /// 仅影响此后加载的纹理，附属选项文件中的字段优先于默认选项
+    pub fn set_default_options(&self, options: TextureOptions) {
+        self.apply(|tm| tm.default_options = options);
+    }
+
+    /// 修改指定名称纹理的采样选项
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理名称
+    /// * `options` - 纹理选项，其中 `srgb` 将被忽略
+    pub fn set_options(&self, name: &str, options: &TextureOptions) {
+        self.apply(|tm| {
+            if let Some(handle) = tm.textures.get(name) {
+                ASSETS.with_mut(handle, |texture| texture.set_options(options));
+            } else {
+                warn!("RCW<TextureManager>", "没有名为 {} 的纹理", name);
+            }
+        });
+    }
+
     /// 将指定名称的纹理绑定到指定 ID
     ///
     /// # 注解 Note
@@ -107,12 +443,149 @@ impl RustCraftWrapper<TextureManager> {
     /// * `id` - 纹理 ID
     pub fn bind(&self, name: &str, id: u32) {
         self.apply(|tm| {
-            if tm.textures.contains_key(name) {
-                let texture = tm.textures.get(name).unwrap();
-                texture.bind(id);
+            if let Some(handle) = tm.textures.get(name) {
+                ASSETS.with(handle, |texture| texture.bind(id));
             } else {
                 warn!("RCW<TextureManager>", "没有名为 {} 的纹理", name);
             }
         });
     }
+
+    /// 将目录下的所有图像按路径顺序加载为二维纹理数组
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理名称
+    /// * `dir` - 目录路径，目录下的图像尺寸必须一致
+    /// * `options` - 扫描选项
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// TEXTURE_MANAGER.load_array("blocks", "texture/blocks/", &ScanOptions::default());
+    /// let stone = TEXTURE_MANAGER.layer("blocks", "stone").unwrap();
+    /// ```
+    pub fn load_array(&self, name: &str, dir: &str, options: &ScanOptions) {
+        debug!(
+            "RCW<TextureManager>",
+            "正在从 {} 加载纹理数组 {}", dir, name
+        );
+        self.apply(|tm| tm.load_array(name, dir, options));
+    }
+
+    /// 加载立方体贴图
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理名称
+    /// * `path` - 十字布局图像的路径，或包含六个面图像的目录
+    pub fn load_cubemap(&self, name: &str, path: &str) {
+        debug!(
+            "RCW<TextureManager>",
+            "正在从 {} 加载立方体贴图 {}", path, name
+        );
+        self.apply(|tm| tm.load_cubemap(name, path));
+    }
+
+    /// 按清单文件加载纹理数组或立方体贴图
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 清单文件路径
+    pub fn load_manifest(&self, path: &str) {
+        debug!("RCW<TextureManager>", "正在按清单 {} 加载纹理", path);
+        self.apply(|tm| tm.load_manifest(path));
+    }
+
+    /// 查询纹理数组中指定图像的层序号
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理数组名称
+    /// * `layer` - 图像的文件名(不含扩展名)
+    pub fn layer(&self, name: &str, layer: &str) -> Option<u32> {
+        let mut ret = None;
+        self.apply(|tm| {
+            ret = tm
+                .layers
+                .get(name)
+                .and_then(|layers| layers.iter().position(|l| l == layer))
+                .map(|i| i as u32)
+        });
+        ret
+    }
+
+    /// 查询指定名称纹理的尺寸、格式与显存占用
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理名称
+    ///
+    /// # 返回值 Returns
+    ///
+    /// 纹理不存在或尚未加载完成时返回 `None`
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// if let Some(info) = TEXTURE_MANAGER.info("grass") {
+    ///     sprite.set_size(info.width as f32, info.height as f32);
+    /// }
+    /// ```
+    pub fn info(&self, name: &str) -> Option<TextureInfo> {
+        let mut ret = None;
+        self.apply(|tm| {
+            ret = tm
+                .textures
+                .get(name)
+                .and_then(|handle| ASSETS.with(handle, |texture| texture.info()))
+        });
+        ret
+    }
+
+    /// 估算管理器中所有已加载纹理的显存占用总量(字节)
+    pub fn memory_usage(&self) -> usize {
+        let mut ret = 0;
+        self.apply(|tm| {
+            ret = tm
+                .textures
+                .values()
+                .filter_map(|handle| ASSETS.with(handle, |texture| texture.memory()))
+                .sum()
+        });
+        ret
+    }
+
+    /// 获取指定名称纹理的资源句柄
+    ///
+    /// # 参数 Parameters
+    /// * `name` - 纹理名称
+    pub fn handle(&self, name: &str) -> Option<Handle<Texture>> {
+        let mut ret = None;
+        self.apply(|tm| ret = tm.textures.get(name).cloned());
+        ret
+    }
+
+    /// 卸载指定名称的纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 管理器释放其持有的句柄，纹理在其他句柄与依赖也释放后由 `ASSETS.collect_garbage` 卸载
+    ///
+    /// # 返回值 Returns
+    /// 管理器中存在该名称时返回 `true`
+    pub fn unload(&self, name: &str) -> bool {
+        let mut ret = false;
+        self.apply(|tm| {
+            ret = tm.textures.remove(name).is_some();
+            tm.sources.remove(name);
+            tm.layers.remove(name);
+        });
+        ret
+    }
 }
@@ -0,0 +1,308 @@
+use gl::types::*;
+use json::JsonValue;
+
+use crate::{debug, vfs::VFS, warn};
+
+/// 纹理环绕方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+}
+
+impl Wrap {
+    pub(crate) fn gl_enum(self) -> GLenum {
+        match self {
+            Wrap::Repeat => gl::REPEAT,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+            Wrap::ClampToBorder => gl::CLAMP_TO_BORDER,
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "repeat" => Ok(Wrap::Repeat),
+            "mirrored_repeat" => Ok(Wrap::MirroredRepeat),
+            "clamp_to_edge" => Ok(Wrap::ClampToEdge),
+            "clamp_to_border" => Ok(Wrap::ClampToBorder),
+            _ => Err(format!("无效的环绕方式: {}", value)),
+        }
+    }
+}
+
+/// 纹理过滤方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// 最近点采样，适用于像素风格纹理
+    Nearest,
+    /// 线性插值采样
+    Linear,
+}
+
+impl Filter {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "nearest" => Ok(Filter::Nearest),
+            "linear" => Ok(Filter::Linear),
+            _ => Err(format!("无效的过滤方式: {}", value)),
+        }
+    }
+}
+
+/// 纹理采样与存储选项
+///
+/// 默认值为：重复环绕、线性过滤、生成多级渐远纹理、不启用各向异性过滤与 sRGB、
+/// 浮点图像以 16 位浮点存储
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::texture::{Filter, TextureOptions};
+///
+/// // 像素风格方块纹理
+/// let options = TextureOptions::default()
+///     .filter(Filter::Nearest)
+///     .mipmaps(false);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureOptions {
+    pub wrap_s: Wrap,
+    pub wrap_t: Wrap,
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    /// 是否生成多级渐远纹理
+    pub mipmaps: bool,
+    /// 各向异性过滤等级，`1.0` 表示不启用
+    pub anisotropy: f32,
+    /// 是否将颜色数据视为 sRGB 色彩空间
+    pub srgb: bool,
+    /// 浮点图像是否以 16 位浮点存储，否则以 32 位浮点存储
+    pub half_float: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            wrap_s: Wrap::Repeat,
+            wrap_t: Wrap::Repeat,
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            mipmaps: true,
+            anisotropy: 1.0,
+            srgb: false,
+            half_float: true,
+        }
+    }
+}
+
+impl TextureOptions {
+    /// 同时设置 S 与 T 方向的环绕方式
+    pub fn wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self
+    }
+
+    /// 同时设置缩小与放大过滤方式
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.min_filter = filter;
+        self.mag_filter = filter;
+        self
+    }
+
+    /// 设置是否生成多级渐远纹理
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// 设置各向异性过滤等级
+    pub fn anisotropy(mut self, anisotropy: f32) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    /// 设置是否将颜色数据视为 sRGB 色彩空间
+    pub fn srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// 设置浮点图像是否以 16 位浮点存储
+    pub fn half_float(mut self, half_float: bool) -> Self {
+        self.half_float = half_float;
+        self
+    }
+
+    /// 缩小过滤方式对应的 OpenGL 枚举值
+    pub(crate) fn gl_min_filter(&self) -> GLenum {
+        match (self.min_filter, self.mipmaps) {
+            (Filter::Nearest, false) => gl::NEAREST,
+            (Filter::Linear, false) => gl::LINEAR,
+            (Filter::Nearest, true) => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Linear, true) => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    /// 放大过滤方式对应的 OpenGL 枚举值
+    pub(crate) fn gl_mag_filter(&self) -> GLenum {
+        match self.mag_filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+        }
+    }
+
+    /// 以 JSON 中的字段覆盖当前选项
+    ///
+    /// # 注解 Note
+    ///
+    /// 支持的字段(均为可选)：
+    /// ```json
+    /// {
+    ///     "wrap": "repeat",
+    ///     "wrap_s": "clamp_to_edge",
+    ///     "wrap_t": "mirrored_repeat",
+    ///     "filter": "nearest",
+    ///     "min_filter": "linear",
+    ///     "mag_filter": "nearest",
+    ///     "mipmaps": false,
+    ///     "anisotropy": 16.0,
+    ///     "srgb": true,
+    ///     "half_float": false
+    /// }
+    /// ```
+    /// 其中 `wrap` 与 `filter` 同时作用于两个方向，具体方向的字段优先
+    pub fn merge_json(mut self, json: &JsonValue) -> Result<Self, String> {
+        if !json.is_object() {
+            return Err("纹理选项不是 JSON 对象".to_string());
+        }
+        let string = |key: &str| -> Result<Option<&str>, String> {
+            if !json.has_key(key) {
+                return Ok(None);
+            }
+            match json[key].as_str() {
+                Some(value) => Ok(Some(value)),
+                None => Err(format!("纹理选项中 {} 字段不是字符串", key)),
+            }
+        };
+        let boolean = |key: &str| -> Result<Option<bool>, String> {
+            if !json.has_key(key) {
+                return Ok(None);
+            }
+            match json[key].as_bool() {
+                Some(value) => Ok(Some(value)),
+                None => Err(format!("纹理选项中 {} 字段不是布尔值", key)),
+            }
+        };
+        if let Some(value) = string("wrap")? {
+            self = self.wrap(Wrap::parse(value)?);
+        }
+        if let Some(value) = string("wrap_s")? {
+            self.wrap_s = Wrap::parse(value)?;
+        }
+        if let Some(value) = string("wrap_t")? {
+            self.wrap_t = Wrap::parse(value)?;
+        }
+        if let Some(value) = string("filter")? {
+            self = self.filter(Filter::parse(value)?);
+        }
+        if let Some(value) = string("min_filter")? {
+            self.min_filter = Filter::parse(value)?;
+        }
+        if let Some(value) = string("mag_filter")? {
+            self.mag_filter = Filter::parse(value)?;
+        }
+        if let Some(value) = boolean("mipmaps")? {
+            self.mipmaps = value;
+        }
+        if let Some(value) = boolean("srgb")? {
+            self.srgb = value;
+        }
+        if let Some(value) = boolean("half_float")? {
+            self.half_float = value;
+        }
+        if json.has_key("anisotropy") {
+            match json["anisotropy"].as_f32() {
+                Some(value) if value >= 1.0 => self.anisotropy = value,
+                _ => return Err("纹理选项中 anisotropy 字段必须是不小于 1 的数字".to_string()),
+            }
+        }
+        Ok(self)
+    }
+
+    /// 以纹理文件的附属选项文件覆盖当前选项
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 纹理文件路径，附属选项文件为 `<path>.json`，例如 `stone.png.json`
+    ///
+    /// # 注解 Note
+    ///
+    /// 附属选项文件不存在时返回原选项；文件无效时输出警告信息并返回原选项
+    pub fn merge_sidecar(self, path: &str) -> Self {
+        let sidecar = format!("{}.json", path);
+        let text = match VFS.read_to_string(&sidecar) {
+            Ok(text) => text,
+            Err(_) => return self,
+        };
+        debug!("TextureOptions", "读取纹理选项文件 {}", sidecar);
+        let result = match json::parse(&text) {
+            Ok(json) => self.merge_json(&json),
+            Err(e) => Err(format!("JSON 解析错误: {}", e)),
+        };
+        match result {
+            Ok(options) => options,
+            Err(e) => {
+                warn!("TextureOptions", "纹理选项文件 {} 无效: {}", sidecar, e);
+                self
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_json() {
+        let json = json::parse(
+            r#"{ "wrap": "clamp_to_edge", "wrap_t": "repeat", "filter": "nearest", "mipmaps": false }"#,
+        )
+        .unwrap();
+        let options = TextureOptions::default().merge_json(&json).unwrap();
+        assert_eq!(options.wrap_s, Wrap::ClampToEdge);
+        assert_eq!(options.wrap_t, Wrap::Repeat);
+        assert_eq!(options.gl_min_filter(), gl::NEAREST);
+        assert_eq!(options.gl_mag_filter(), gl::NEAREST);
+        assert!(!options.srgb);
+    }
+
+    #[test]
+    fn test_gl_filter() {
+        let mut options = TextureOptions {
+            min_filter: Filter::Nearest,
+            mipmaps: true,
+            ..Default::default()
+        };
+        assert_eq!(options.gl_min_filter(), gl::NEAREST_MIPMAP_NEAREST);
+        options.min_filter = Filter::Linear;
+        assert_eq!(options.gl_min_filter(), gl::LINEAR_MIPMAP_LINEAR);
+        options.mipmaps = false;
+        assert_eq!(options.gl_min_filter(), gl::LINEAR);
+    }
+
+    #[test]
+    fn test_merge_json_invalid() {
+        let options = TextureOptions::default();
+        for text in [
+            r#"{ "wrap": "clamp" }"#,
+            r#"{ "mipmaps": "yes" }"#,
+            r#"{ "anisotropy": 0.5 }"#,
+            r#"[]"#,
+        ] {
+            assert!(options.merge_json(&json::parse(text).unwrap()).is_err());
+        }
+    }
+}
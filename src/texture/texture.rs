@@ -1,37 +1,570 @@
-use crate::{gl_utils, warn};
+use crate::{gl_utils, vfs::VFS, warn, GL_STATE};
 use gl::types::*;
 
+use super::{
+    bcn,
+    compressed::CompressedImage,
+    format::{check_data, check_region, DepthFormat, ImageData, PixelFormat},
+    info::{mip_chain_size, mip_count, TextureInfo},
+    layered::{check_faces, check_layers},
+    TextureOptions,
+};
+
 pub struct Texture {
     pub(crate) id: GLuint,
+    /// 纹理目标，例如 `GL_TEXTURE_2D`、`GL_TEXTURE_2D_ARRAY` 或 `GL_TEXTURE_CUBE_MAP`
+    target: GLenum,
+    /// 纹理在显存中的内部格式，例如 `GL_RGBA8`、`GL_R8` 或 `GL_RGB16F`
+    internal_format: GLenum,
+    width: u32,
+    height: u32,
+    /// 未压缩纹理的像素格式，块压缩纹理与深度纹理为 `None`
+    format: Option<PixelFormat>,
+    /// 纹理数组的层数，立方体贴图为 6
+    layers: u32,
+    /// 已分配的多级渐远纹理级数
+    mip_levels: u32,
+    /// 估算的显存占用(字节)
+    memory: usize,
+    /// 是否由块压缩数据创建，此时多级渐远纹理由文件提供，不再生成
+    compressed: bool,
+    options: TextureOptions,
+}
+
+/// 解码后、上传前的纹理数据
+pub enum TextureData {
+    /// 普通图像
+    Image(ImageData),
+    /// 来自 DDS 或 KTX2 文件的块压缩数据
+    Compressed(CompressedImage),
 }
 
 impl Texture {
     pub fn new_from(path: &str) -> Option<Self> {
-        let tex = match unsafe { gl_utils::load_texture_from_file(path) } {
-            Ok(tex) => tex,
+        Self::new_from_with(path, &TextureOptions::default())
+    }
+
+    /// 以指定选项从文件创建纹理
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 文件路径
+    /// * `options` - 纹理采样与存储选项
+    pub fn new_from_with(path: &str, options: &TextureOptions) -> Option<Self> {
+        let result = Self::decode_any(path).and_then(|data| Self::from_data(&data, options));
+        match result {
+            Ok(texture) => Some(texture),
             Err(e) => {
                 warn!(
                     "Texture::new_from",
                     "无法从文件 {} 加载纹理, 由于 {}", path, e
                 );
-                return None;
+                None
             }
+        }
+    }
+
+    fn decode_dynamic(path: &str) -> Result<image::DynamicImage, String> {
+        let data = VFS.read(path)?;
+        let img = match image::ImageFormat::from_path(path) {
+            Ok(format) => image::load_from_memory_with_format(&data, format),
+            Err(_) => image::load_from_memory(&data),
         };
-        Some(Self { id: tex })
+        img.map_err(|e| format!("无法从文件 {} 解码图像, 由于 {}", path, e))
     }
 
-    pub fn bind(&self, id: u32) {
+    /// 在不使用 OpenGL 的情况下将图像文件解码为 RGBA8
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数可以在任意线程中调用，解码结果通过 `from_rgba` 上传
+    pub fn decode(path: &str) -> Result<image::RgbaImage, String> {
+        Self::decode_dynamic(path).map(|img| img.to_rgba8())
+    }
+
+    /// 在不使用 OpenGL 的情况下解码图像文件，并保留其通道数与精度
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数可以在任意线程中调用，解码结果通过 `from_image` 上传
+    pub fn decode_image(path: &str) -> Result<ImageData, String> {
+        Self::decode_dynamic(path).map(ImageData::from_dynamic)
+    }
+
+    /// 在不使用 OpenGL 的情况下读取纹理文件
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数可以在任意线程中调用。DDS 与 KTX2 文件保持压缩格式，其余图像按 `decode_image` 解码
+    pub fn decode_any(path: &str) -> Result<TextureData, String> {
+        let data = VFS.read(path)?;
+        if CompressedImage::is_container(&data) {
+            CompressedImage::parse(&data)
+                .map(TextureData::Compressed)
+                .map_err(|e| format!("无法解析压缩纹理 {}, 由于 {}", path, e))
+        } else {
+            Self::decode_image(path).map(TextureData::Image)
+        }
+    }
+
+    /// 从 `decode_any` 的结果创建纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn from_data(data: &TextureData, options: &TextureOptions) -> Result<Self, String> {
+        match data {
+            TextureData::Image(img) => Ok(Self::from_image(img, options)),
+            TextureData::Compressed(img) => Self::from_compressed(img, options),
+        }
+    }
+
+    /// 从块压缩数据创建纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 文件中的 sRGB 标记与 `options.srgb` 任一为真即视为 sRGB 纹理。
+    /// 显卡不支持对应的压缩格式时将在 CPU 上解压缩后上传：BC1-BC5 与 BC7 解压缩为 RGBA8，
+    /// BC6H 与有符号的 BC4、BC5 解压缩为浮点数据
+    pub fn from_compressed(
+        img: &CompressedImage,
+        options: &TextureOptions,
+    ) -> Result<Self, String> {
+        let srgb = img.srgb || options.srgb;
+        let options = options
+            .srgb(srgb)
+            .mipmaps(options.mipmaps && img.levels.len() > 1);
+        let supported = match img.format.required_extension() {
+            Some(ext) => unsafe { gl_utils::has_extension(ext) },
+            None => true,
+        };
+        let levels_size = |levels: &[&[u8]]| levels.iter().map(|l| l.len()).sum::<usize>();
+        let (id, internal_format, format, memory) = if supported {
+            let internal_format = img.format.gl_internal_format(srgb);
+            let levels = img.levels.iter().map(|l| l.as_slice()).collect::<Vec<_>>();
+            let id = unsafe {
+                gl_utils::upload_texture_levels(
+                    img.width,
+                    img.height,
+                    &levels,
+                    Some(internal_format),
+                    PixelFormat::Rgba8,
+                    &options,
+                )
+            };
+            (id, internal_format, None, levels_size(&levels))
+        } else {
+            let kind = img.format.bc_kind();
+            warn!(
+                "Texture::from_compressed",
+                "显卡不支持 {:?} 压缩格式, 将在 CPU 上解压缩", img.format
+            );
+            let mut levels = vec![];
+            for (level, data) in img.levels.iter().enumerate() {
+                let w = (img.width >> level).max(1);
+                let h = (img.height >> level).max(1);
+                levels.push(bcn::decompress(kind, w, h, data)?);
+            }
+            let format = levels[0].format;
+            let memory = levels
+                .iter()
+                .map(|l| l.width as usize * l.height as usize)
+                .sum::<usize>()
+                * format.stored_bytes_per_pixel(&options);
+            let levels = levels.iter().map(|l| l.data.as_slice()).collect::<Vec<_>>();
+            let id = unsafe {
+                gl_utils::upload_texture_levels(
+                    img.width, img.height, &levels, None, format, &options,
+                )
+            };
+            (
+                id,
+                format.gl_internal_format(&options),
+                Some(format),
+                memory,
+            )
+        };
+        Ok(Self {
+            id,
+            target: gl::TEXTURE_2D,
+            internal_format,
+            width: img.width,
+            height: img.height,
+            format,
+            layers: 1,
+            mip_levels: img.levels.len() as u32,
+            memory,
+            compressed: true,
+            options,
+        })
+    }
+
+    /// 以指定选项从保留原始格式的图像创建纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 单通道纹理在着色器中采样时 RGB 分量均为该通道的值
+    pub fn from_image(img: &ImageData, options: &TextureOptions) -> Self {
+        Self::upload(img.width, img.height, img.format, Some(&img.data), options)
+    }
+
+    /// 从已解码的图像创建纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn from_rgba(img: &image::RgbaImage) -> Self {
+        Self::from_rgba_with(img, &TextureOptions::default())
+    }
+
+    /// 以指定选项从已解码的图像创建纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn from_rgba_with(img: &image::RgbaImage, options: &TextureOptions) -> Self {
+        let (width, height) = img.dimensions();
+        Self::upload(
+            width,
+            height,
+            PixelFormat::Rgba8,
+            Some(img.as_raw()),
+            options,
+        )
+    }
+
+    /// 从内存中的已编码图像创建纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 支持 `image` 库可识别的所有格式，以及 DDS 与 KTX2 压缩纹理
+    ///
+    /// # 参数 Parameters
+    /// * `bytes` - 文件内容，例如 `include_bytes!("icon.png")`
+    /// * `options` - 纹理选项
+    pub fn from_memory(bytes: &[u8], options: &TextureOptions) -> Result<Self, String> {
+        if CompressedImage::is_container(bytes) {
+            return Self::from_compressed(&CompressedImage::parse(bytes)?, options);
+        }
+        let img =
+            image::load_from_memory(bytes).map_err(|e| format!("无法解码图像, 由于 {}", e))?;
+        Ok(Self::from_image(&ImageData::from_dynamic(img), options))
+    }
+
+    /// 从原始像素数据创建纹理
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// # 参数 Parameters
+    /// * `width` - 宽度
+    /// * `height` - 高度
+    /// * `format` - 像素格式
+    /// * `data` - 按行紧密排列的像素数据，长度必须为 `format.image_size(width, height)`
+    /// * `options` - 纹理选项
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// // 生成 64x64 的单通道噪声纹理
+    /// let noise = (0..64 * 64).map(|i| (i * 7919 % 251) as u8).collect::<Vec<_>>();
+    /// let texture = Texture::from_raw(64, 64, PixelFormat::R8, &noise, &TextureOptions::default())?;
+    /// ```
+    pub fn from_raw(
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: &[u8],
+        options: &TextureOptions,
+    ) -> Result<Self, String> {
+        check_data(width, height, format, data)?;
+        Ok(Self::upload(width, height, format, Some(data), options))
+    }
+
+    /// 创建指定尺寸与格式的空纹理，内容未定义
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用，可配合 `update_region` 逐步填充内容
+    pub fn empty(width: u32, height: u32, format: PixelFormat, options: &TextureOptions) -> Self {
+        Self::upload(width, height, format, None, options)
+    }
+
+    /// 创建指定尺寸与格式的深度纹理，内容未定义
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 深度纹理不生成多级渐远纹理，`options.mipmaps` 将被忽略
+    pub fn depth(width: u32, height: u32, format: DepthFormat, options: &TextureOptions) -> Self {
+        let options = options.mipmaps(false);
+        let id = unsafe { gl_utils::upload_depth_texture(width, height, format, &options) };
+        Self {
+            id,
+            target: gl::TEXTURE_2D,
+            internal_format: format.gl_internal_format(),
+            width,
+            height,
+            format: None,
+            layers: 1,
+            mip_levels: 1,
+            memory: mip_chain_size(width, height, 1, format.bytes_per_pixel()),
+            compressed: false,
+            options,
+        }
+    }
+
+    fn upload(
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: Option<&[u8]>,
+        options: &TextureOptions,
+    ) -> Self {
+        let id = unsafe { gl_utils::upload_texture(width, height, format, data, options) };
+        Self::with_storage(id, gl::TEXTURE_2D, width, height, format, 1, options)
+    }
+
+    /// 由已上传的未压缩纹理构造，并估算其显存占用
+    fn with_storage(
+        id: GLuint,
+        target: GLenum,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        layers: u32,
+        options: &TextureOptions,
+    ) -> Self {
+        let mip_levels = if options.mipmaps {
+            mip_count(width, height)
+        } else {
+            1
+        };
+        let bytes_per_pixel = format.stored_bytes_per_pixel(options);
+        Self {
+            id,
+            target,
+            internal_format: format.gl_internal_format(options),
+            width,
+            height,
+            format: Some(format),
+            layers,
+            mip_levels,
+            memory: mip_chain_size(width, height, mip_levels, bytes_per_pixel) * layers as usize,
+            compressed: false,
+            options: *options,
+        }
+    }
+
+    /// 更新纹理的部分区域
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 仅支持未压缩的二维纹理；启用多级渐远纹理时会重新生成各级纹理
+    ///
+    /// # 参数 Parameters
+    /// * `x`, `y` - 区域左上角在纹理中的位置
+    /// * `width`, `height` - 区域尺寸
+    /// * `format` - `data` 的像素格式，可以与纹理的格式不同
+    /// * `data` - 按行紧密排列的像素数据
+    pub fn update_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: &[u8],
+    ) -> Result<(), String> {
+        if self.target != gl::TEXTURE_2D || self.format.is_none() {
+            return Err("只能更新未压缩的二维纹理".to_string());
+        }
+        check_region((self.width, self.height), x, y, width, height)?;
+        check_data(width, height, format, data)?;
         unsafe {
-            gl::ActiveTexture(gl::TEXTURE0 + id);
-            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl_utils::update_texture_region(
+                self.id,
+                (x, y, width, height),
+                format,
+                data,
+                self.options.mipmaps,
+            );
         }
+        Ok(())
+    }
+
+    /// 由若干尺寸相同的图像创建二维纹理数组，图像顺序即为层序号
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// 着色器中使用 `sampler2DArray` 采样，纹理坐标的第三个分量为层序号
+    pub fn array_from_images(
+        images: &[image::RgbaImage],
+        options: &TextureOptions,
+    ) -> Result<Self, String> {
+        let (width, height) = check_layers(images)?;
+        let layers = images
+            .iter()
+            .map(|img| img.as_raw().as_slice())
+            .collect::<Vec<_>>();
+        let id = unsafe { gl_utils::upload_texture_array(width, height, &layers, options) };
+        Ok(Self::with_storage(
+            id,
+            gl::TEXTURE_2D_ARRAY,
+            width,
+            height,
+            PixelFormat::Rgba8,
+            layers.len() as u32,
+            options,
+        ))
+    }
+
+    /// 由六个面的图像创建立方体贴图
+    ///
+    /// # 参数 Parameters
+    /// * `faces` - 按 +X、-X、+Y、-Y、+Z、-Z 顺序排列的正方形图像，参见 `CubeFace`
+    /// * `options` - 纹理选项
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    pub fn cubemap_from_faces(
+        faces: &[image::RgbaImage],
+        options: &TextureOptions,
+    ) -> Result<Self, String> {
+        let size = check_faces(faces)?;
+        let faces = faces
+            .iter()
+            .map(|img| img.as_raw().as_slice())
+            .collect::<Vec<_>>();
+        let id = unsafe { gl_utils::upload_cubemap(size, &faces, options) };
+        Ok(Self::with_storage(
+            id,
+            gl::TEXTURE_CUBE_MAP,
+            size,
+            size,
+            PixelFormat::Rgba8,
+            6,
+            options,
+        ))
+    }
+
+    /// 纹理宽度
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// 纹理高度
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 纹理尺寸 `(宽度, 高度)`
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// 未压缩纹理的像素格式，块压缩纹理与深度纹理为 `None`
+    pub fn format(&self) -> Option<PixelFormat> {
+        self.format
+    }
+
+    /// 纹理数组的层数，立方体贴图为 6，二维纹理为 1
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    /// 已分配的多级渐远纹理级数
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    /// 估算的显存占用(字节)
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    /// 纹理的全部元数据
+    pub fn info(&self) -> TextureInfo {
+        TextureInfo {
+            width: self.width,
+            height: self.height,
+            layers: self.layers,
+            mip_levels: self.mip_levels,
+            target: self.target,
+            internal_format: self.internal_format,
+            format: self.format,
+            memory: self.memory,
+        }
+    }
+
+    /// 纹理目标
+    pub fn target(&self) -> GLenum {
+        self.target
+    }
+
+    /// 纹理在显存中的内部格式
+    pub fn internal_format(&self) -> GLenum {
+        self.internal_format
+    }
+
+    /// 纹理当前的选项
+    pub fn options(&self) -> &TextureOptions {
+        &self.options
+    }
+
+    /// 修改纹理的采样选项
+    ///
+    /// # 注解 Note
+    ///
+    /// 此函数只有在 OpenGL 上下文激活后才能调用
+    ///
+    /// `srgb` 决定了纹理的存储格式，创建后无法修改，此处将被忽略。
+    /// 由块压缩数据创建的纹理只能使用文件提供的多级渐远纹理，不会重新生成
+    pub fn set_options(&mut self, options: &TextureOptions) {
+        // 只有一级的压缩纹理无法启用多级渐远纹理
+        let options = &TextureOptions {
+            mipmaps: options.mipmaps && (!self.compressed || self.mip_levels > 1),
+            ..*options
+        };
+        GL_STATE.bind_texture(0, self.target, self.id);
+        unsafe {
+            gl_utils::apply_texture_options(self.target, options);
+            if options.mipmaps && !self.options.mipmaps && !self.compressed {
+                gl::GenerateMipmap(self.target);
+            }
+        }
+        GL_STATE.bind_texture(0, self.target, 0);
+        if let (true, 1, Some(format)) = (options.mipmaps, self.mip_levels, self.format) {
+            self.mip_levels = mip_count(self.width, self.height);
+            let bytes_per_pixel = format.stored_bytes_per_pixel(&self.options);
+            self.memory = mip_chain_size(self.width, self.height, self.mip_levels, bytes_per_pixel)
+                * self.layers as usize;
+        }
+        self.options = TextureOptions {
+            srgb: self.options.srgb,
+            ..*options
+        };
+    }
+
+    /// 将纹理绑定到纹理单元，纹理已绑定时不调用 OpenGL
+    pub fn bind(&self, id: u32) {
+        GL_STATE.bind_texture(id, self.target, self.id);
     }
 }
 
 impl Drop for Texture {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteTextures(1, &self.id);
-        }
+        GL_STATE.delete_texture(self.id);
     }
 }
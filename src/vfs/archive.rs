@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+/// 资源包文件头标识
+const MAGIC: &[u8; 4] = b"RCPK";
+/// 资源包格式版本
+const VERSION: u32 = 1;
+
+/// 资源包
+///
+/// 格式(小端序)：
+/// + 文件头：`RCPK`、版本号(u32)、文件数量(u32)
+/// + 索引：每项为名称长度(u32)、名称(UTF-8)、数据偏移(u64)、数据长度(u64)
+/// + 数据区：各文件内容依次排列，偏移量相对于数据区起始位置
+pub struct Archive {
+    data: Vec<u8>,
+    entries: HashMap<String, (usize, usize)>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = match self.pos.checked_add(len) {
+            Some(end) if end <= self.data.len() => end,
+            _ => return Err("资源包数据不完整".to_string()),
+        };
+        let ret = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(ret)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// 读取 u64 并转换为 `usize`，超出范围时返回错误
+    fn size(&mut self) -> Result<usize, String> {
+        usize::try_from(self.u64()?).map_err(|_| "资源包中的偏移或长度超出范围".to_string())
+    }
+}
+
+impl Archive {
+    /// 从资源包数据创建
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, String> {
+        let mut reader = Reader {
+            data: &data,
+            pos: 0,
+        };
+        if reader.take(4)? != MAGIC {
+            return Err("不是有效的资源包文件".to_string());
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(format!("不支持的资源包版本: {}", version));
+        }
+        let count = reader.u32()?;
+        let mut index = vec![];
+        for _ in 0..count {
+            let len = reader.u32()? as usize;
+            let name = match std::str::from_utf8(reader.take(len)?) {
+                Ok(name) => name.to_string(),
+                Err(e) => return Err(format!("资源包中的文件名无效: {}", e)),
+            };
+            let offset = reader.size()?;
+            let size = reader.size()?;
+            index.push((name, offset, size));
+        }
+        let base = reader.pos;
+        let mut entries = HashMap::new();
+        for (name, offset, size) in index {
+            let start = base.checked_add(offset);
+            match start.and_then(|start| start.checked_add(size)) {
+                Some(end) if end <= data.len() => {
+                    entries.insert(name, (base + offset, size));
+                }
+                _ => return Err(format!("资源包中的文件 {} 超出数据范围", name)),
+            }
+        }
+        Ok(Self { data, entries })
+    }
+
+    /// 从资源包文件创建
+    pub fn open(path: &str) -> Result<Self, String> {
+        match std::fs::read(path) {
+            Ok(data) => Self::from_bytes(data),
+            Err(e) => Err(format!("无法读取资源包 {}: {}", path, e)),
+        }
+    }
+
+    /// 读取资源包中的文件
+    pub fn read(&self, name: &str) -> Option<&[u8]> {
+        self.entries
+            .get(name)
+            .map(|&(offset, size)| &self.data[offset..offset + size])
+    }
+
+    /// 资源包中是否存在指定文件
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// 资源包中所有文件的名称
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|name| name.as_str())
+    }
+}
+
+/// 将文件打包为资源包数据
+///
+/// # 参数 Parameters
+/// * `files` - `(虚拟路径, 文件内容)` 列表
+pub fn write_archive(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut ret = vec![];
+    ret.extend_from_slice(MAGIC);
+    ret.extend_from_slice(&VERSION.to_le_bytes());
+    ret.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    let mut offset = 0u64;
+    for (name, content) in files {
+        ret.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        ret.extend_from_slice(name.as_bytes());
+        ret.extend_from_slice(&offset.to_le_bytes());
+        ret.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        offset += content.len() as u64;
+    }
+    for (_, content) in files {
+        ret.extend_from_slice(content);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let files = vec![
+            ("shader/cube.vert".to_string(), b"vertex".to_vec()),
+            ("texture/empty.png".to_string(), vec![]),
+            ("model/cube.json".to_string(), b"{}".to_vec()),
+        ];
+        let archive = Archive::from_bytes(write_archive(&files)).unwrap();
+        assert_eq!(archive.read("shader/cube.vert"), Some(&b"vertex"[..]));
+        assert_eq!(archive.read("texture/empty.png"), Some(&[][..]));
+        assert_eq!(archive.read("model/cube.json"), Some(&b"{}"[..]));
+        assert_eq!(archive.read("model/none.json"), None);
+        assert_eq!(archive.names().count(), 3);
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        assert!(Archive::from_bytes(b"PK\x03\x04".to_vec()).is_err());
+        let mut data = write_archive(&[("a".to_string(), b"abc".to_vec())]);
+        data.pop();
+        assert!(Archive::from_bytes(data).is_err());
+        // 偏移量溢出时返回错误而不是崩溃
+        let mut data = write_archive(&[("a".to_string(), b"abc".to_vec())]);
+        data[17..25].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(Archive::from_bytes(data).is_err());
+    }
+}
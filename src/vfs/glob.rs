@@ -0,0 +1,81 @@
+/// 判断路径是否匹配通配符模式
+///
+/// # 参数 Parameters
+/// * `pattern` - 通配符模式
+/// * `path` - 以 `/` 分隔的路径
+///
+/// # 注解 Note
+///
+/// 支持的通配符：
+/// + `*`: 匹配任意数量的字符，但不跨越目录
+/// + `?`: 匹配单个字符，但不匹配 `/`
+/// + `**`: 匹配任意数量的字符，可以跨越目录；`**/` 也可以匹配零层目录
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::vfs::glob::glob_match;
+///
+/// assert!(glob_match("*.png", "stone.png"));
+/// assert!(!glob_match("*.png", "blocks/stone.png"));
+/// assert!(glob_match("**/*.png", "blocks/stone.png"));
+/// ```
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let path = path.chars().collect::<Vec<_>>();
+    matches(&pattern, &path)
+}
+
+fn matches(p: &[char], t: &[char]) -> bool {
+    match p.first() {
+        None => t.is_empty(),
+        Some('*') if p.get(1) == Some(&'*') => {
+            let rest = &p[2..];
+            if rest.first() == Some(&'/') && matches(&rest[1..], t) {
+                return true;
+            }
+            (0..=t.len()).any(|i| matches(rest, &t[i..]))
+        }
+        Some('*') => {
+            for i in 0..=t.len() {
+                if matches(&p[1..], &t[i..]) {
+                    return true;
+                }
+                if i < t.len() && t[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => !t.is_empty() && t[0] != '/' && matches(&p[1..], &t[1..]),
+        Some(c) => t.first() == Some(c) && matches(&p[1..], &t[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_star() {
+        assert!(glob_match("*.png", "stone.png"));
+        assert!(glob_match("stone.*", "stone.png"));
+        assert!(!glob_match("*.png", "stone.jpg"));
+        assert!(!glob_match("*.png", "blocks/stone.png"));
+        assert!(glob_match("blocks/*", "blocks/stone.png"));
+    }
+
+    #[test]
+    fn test_double_star() {
+        assert!(glob_match("**/*.png", "stone.png"));
+        assert!(glob_match("**/*.png", "blocks/ore/stone.png"));
+        assert!(glob_match("blocks/**", "blocks/ore/stone.png"));
+        assert!(!glob_match("items/**", "blocks/stone.png"));
+    }
+
+    #[test]
+    fn test_question_mark() {
+        assert!(glob_match("stone_?.png", "stone_1.png"));
+        assert!(!glob_match("stone_?.png", "stone_10.png"));
+        assert!(!glob_match("a?b", "a/b"));
+    }
+}
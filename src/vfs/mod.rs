@@ -0,0 +1,582 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{debug, warn, RustCraftWrapper};
+
+pub mod archive;
+pub mod glob;
+use archive::Archive;
+use glob::glob_match;
+
+/// 挂载源接口
+///
+/// 所有路径均为以 `/` 分隔的虚拟路径，例如 `shader/cube.vert`
+pub trait MountSource: Send {
+    /// 读取文件内容，文件不存在时返回 `None`
+    fn read(&self, path: &str) -> Option<Vec<u8>>;
+    /// 文件是否存在，默认实现读取文件内容
+    fn exists(&self, path: &str) -> bool {
+        self.read(path).is_some()
+    }
+    /// 列出目录下的所有文件(不含子目录)
+    fn list(&self, dir: &str) -> Vec<String>;
+    /// 递归列出目录及其子目录下的所有文件
+    fn list_recursive(&self, dir: &str) -> Vec<String> {
+        self.list(dir)
+    }
+}
+
+/// 规范化虚拟路径
+///
+/// 统一使用 `/` 作为分隔符，并移除多余的 `.`、空段与末尾分隔符；
+/// 绝对路径开头的 `/` 与 Windows 盘符被保留
+pub fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let joined = path
+        .split('/')
+        .filter(|seg| !seg.is_empty() && *seg != ".")
+        .collect::<Vec<_>>()
+        .join("/");
+    if path.starts_with('/') {
+        format!("/{}", joined)
+    } else {
+        joined
+    }
+}
+
+/// 路径是否为文件系统中的绝对路径，即以 `/` 或 Windows 盘符(例如 `C:`)开头
+///
+/// 绝对路径不经过挂载源，直接访问文件系统
+pub fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with('/')
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// 虚拟路径所在的目录
+fn parent(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(pos) => &path[..pos],
+        None => "",
+    }
+}
+
+/// 虚拟路径是否位于目录或其子目录下
+fn is_under(path: &str, dir: &str) -> bool {
+    dir.is_empty() || (path.starts_with(dir) && path[dir.len()..].starts_with('/'))
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// 文件系统目录挂载源
+pub struct DirSource {
+    root: PathBuf,
+}
+
+impl MountSource for DirSource {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.root.join(path)).ok()
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.root.join(path).is_file()
+    }
+
+    fn list(&self, dir: &str) -> Vec<String> {
+        let mut ret = vec![];
+        let entries = match std::fs::read_dir(self.root.join(dir)) {
+            Ok(entries) => entries,
+            Err(_) => return ret,
+        };
+        for entry in entries.flatten() {
+            if !entry.path().is_file() {
+                continue;
+            }
+            match entry.file_name().to_str() {
+                Some(name) => ret.push(join(dir, name)),
+                None => {
+                    warn!("Vfs", "无法转换文件名为字符串 {}", entry.path().display());
+                }
+            }
+        }
+        ret
+    }
+
+    fn list_recursive(&self, dir: &str) -> Vec<String> {
+        let mut ret = vec![];
+        let mut dirs = vec![dir.to_string()];
+        while let Some(dir) = dirs.pop() {
+            let entries = match std::fs::read_dir(self.root.join(&dir)) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let name = match entry.file_name().to_str() {
+                    Some(name) => join(&dir, name),
+                    None => {
+                        warn!("Vfs", "无法转换文件名为字符串 {}", entry.path().display());
+                        continue;
+                    }
+                };
+                let path = entry.path();
+                if path.is_dir() {
+                    dirs.push(name);
+                } else if path.is_file() {
+                    ret.push(name);
+                }
+            }
+        }
+        ret
+    }
+}
+
+/// 资源包挂载源
+pub struct ArchiveSource {
+    archive: Archive,
+}
+
+impl MountSource for ArchiveSource {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        self.archive.read(path).map(|data| data.to_vec())
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.archive.contains(path)
+    }
+
+    fn list(&self, dir: &str) -> Vec<String> {
+        self.archive
+            .names()
+            .filter(|name| parent(name) == dir)
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    fn list_recursive(&self, dir: &str) -> Vec<String> {
+        self.archive
+            .names()
+            .filter(|name| is_under(name, dir))
+            .map(|name| name.to_string())
+            .collect()
+    }
+}
+
+/// 嵌入式文件挂载源
+pub struct EmbeddedSource {
+    files: HashMap<String, &'static [u8]>,
+}
+
+impl MountSource for EmbeddedSource {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        self.files.get(path).map(|data| data.to_vec())
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn list(&self, dir: &str) -> Vec<String> {
+        self.files
+            .keys()
+            .filter(|name| parent(name) == dir)
+            .cloned()
+            .collect()
+    }
+
+    fn list_recursive(&self, dir: &str) -> Vec<String> {
+        self.files
+            .keys()
+            .filter(|name| is_under(name, dir))
+            .cloned()
+            .collect()
+    }
+}
+
+/// 目录扫描选项
+///
+/// # 示例 Examples
+/// ```
+/// use rustcraft::vfs::ScanOptions;
+///
+/// let options = ScanOptions::recursive()
+///     .include("blocks/**")
+///     .exclude("**/*_old.png");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// 是否扫描子目录
+    pub recursive: bool,
+    /// 包含的文件模式，为空时包含所有文件
+    pub include: Vec<String>,
+    /// 排除的文件模式，优先于包含模式
+    pub exclude: Vec<String>,
+}
+
+impl ScanOptions {
+    /// 递归扫描子目录的选项
+    pub fn recursive() -> Self {
+        Self {
+            recursive: true,
+            ..Default::default()
+        }
+    }
+
+    /// 添加包含模式，模式相对于被扫描的目录，语法见 `glob::glob_match`
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(pattern.to_string());
+        self
+    }
+
+    /// 添加排除模式，模式相对于被扫描的目录，语法见 `glob::glob_match`
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    /// 判断相对路径是否被选项接受
+    pub fn accepts(&self, relative: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| glob_match(p, relative)))
+            && !self.exclude.iter().any(|p| glob_match(p, relative))
+    }
+}
+
+/// 目录扫描结果项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanEntry {
+    /// 资源名称，即相对于被扫描目录、不含扩展名的路径，例如 `blocks/stone`
+    pub name: String,
+    /// 文件扩展名
+    pub ext: String,
+    /// 文件的完整虚拟路径
+    pub path: String,
+}
+
+/// 虚拟文件系统
+///
+/// 所有资源管理器通过此系统读取文件，后挂载的源会覆盖先挂载的源中的同名文件
+pub struct Vfs {
+    mounts: Vec<Box<dyn MountSource>>,
+    /// 访问绝对路径的文件系统根目录
+    root: DirSource,
+}
+
+impl Vfs {
+    /// 创建虚拟文件系统，默认挂载当前工作目录
+    fn new() -> Self {
+        Self {
+            mounts: vec![Box::new(DirSource {
+                root: PathBuf::from("."),
+            })],
+            root: DirSource {
+                root: PathBuf::new(),
+            },
+        }
+    }
+
+    /// 路径对应的挂载源，按挂载顺序排列；绝对路径只对应文件系统根目录
+    fn sources(&self, path: &str) -> Vec<&dyn MountSource> {
+        if is_absolute(path) {
+            vec![&self.root]
+        } else {
+            self.mounts.iter().map(|source| source.as_ref()).collect()
+        }
+    }
+
+    /// 挂载自定义源
+    pub fn mount(&mut self, source: Box<dyn MountSource>) {
+        self.mounts.push(source);
+    }
+
+    /// 移除所有挂载源，包括默认挂载的当前工作目录；绝对路径仍直接访问文件系统
+    pub fn unmount_all(&mut self) {
+        self.mounts.clear();
+    }
+
+    /// 读取文件内容
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        let path = normalize(path);
+        for source in self.sources(&path).into_iter().rev() {
+            if let Some(data) = source.read(&path) {
+                return Ok(data);
+            }
+        }
+        Err(format!("虚拟文件系统中找不到文件 {}", path))
+    }
+
+    /// 文件是否存在，不读取文件内容
+    pub fn exists(&self, path: &str) -> bool {
+        let path = normalize(path);
+        self.sources(&path)
+            .into_iter()
+            .any(|source| source.exists(&path))
+    }
+
+    /// 列出目录下的所有文件(不含子目录)，结果已排序且去重
+    pub fn list(&self, dir: &str) -> Vec<String> {
+        let dir = normalize(dir);
+        let mut ret = BTreeSet::new();
+        for source in self.sources(&dir) {
+            ret.extend(source.list(&dir));
+        }
+        ret.into_iter().collect()
+    }
+
+    /// 按选项扫描目录，结果按路径排序
+    pub fn scan(&self, dir: &str, options: &ScanOptions) -> Vec<ScanEntry> {
+        let dir = normalize(dir);
+        let mut files = BTreeSet::new();
+        for source in self.sources(&dir) {
+            if options.recursive {
+                files.extend(source.list_recursive(&dir));
+            } else {
+                files.extend(source.list(&dir));
+            }
+        }
+        let mut ret = vec![];
+        for path in files {
+            let relative = path[dir.len()..].trim_start_matches('/');
+            if !options.accepts(relative) {
+                continue;
+            }
+            let (name, ext) = match relative.rfind('.') {
+                Some(pos) if pos > relative.rfind('/').map_or(0, |p| p + 1) => {
+                    (&relative[..pos], &relative[pos + 1..])
+                }
+                _ => (relative, ""),
+            };
+            ret.push(ScanEntry {
+                name: name.to_string(),
+                ext: ext.to_string(),
+                path: path.clone(),
+            });
+        }
+        ret
+    }
+}
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// 虚拟文件系统
+    pub static ref VFS: RustCraftWrapper<Vfs> = RustCraftWrapper::new(Vfs::new());
+}
+
+impl RustCraftWrapper<Vfs> {
+    /// 挂载文件系统目录
+    ///
+    /// # 参数 Parameters
+    /// * `dir` - 目录路径，虚拟路径 `a/b` 将映射到 `dir/a/b`
+    pub fn mount_dir<P: AsRef<Path>>(&self, dir: P) {
+        let root = dir.as_ref().to_path_buf();
+        debug!("Vfs", "挂载目录 {}", root.display());
+        self.apply(|vfs| vfs.mount(Box::new(DirSource { root })));
+    }
+
+    /// 挂载相对于可执行文件所在目录的目录
+    ///
+    /// # 注解 Note
+    ///
+    /// 使用此函数可以使程序在任意工作目录下运行时都能找到资源
+    pub fn mount_exe_dir(&self, dir: &str) {
+        let exe = match std::env::current_exe() {
+            Ok(exe) => exe,
+            Err(e) => {
+                warn!("Vfs", "无法获取可执行文件路径: {}", e);
+                return;
+            }
+        };
+        match exe.parent() {
+            Some(parent) => self.mount_dir(parent.join(dir)),
+            None => {
+                warn!("Vfs", "无法获取可执行文件所在目录");
+            }
+        }
+    }
+
+    /// 挂载资源包文件
+    ///
+    /// # 参数 Parameters
+    /// * `path` - 资源包文件路径，资源包可由 `pack_assets` 工具生成
+    pub fn mount_archive(&self, path: &str) {
+        debug!("Vfs", "挂载资源包 {}", path);
+        match Archive::open(path) {
+            Ok(archive) => self.apply(|vfs| vfs.mount(Box::new(ArchiveSource { archive }))),
+            Err(e) => {
+                warn!("Vfs", "挂载资源包 {} 失败: {}", path, e);
+            }
+        }
+    }
+
+    /// 挂载嵌入程序中的资源包数据
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// VFS.mount_archive_bytes(include_bytes!("../assets.pak"));
+    /// ```
+    pub fn mount_archive_bytes(&self, data: &[u8]) {
+        match Archive::from_bytes(data.to_vec()) {
+            Ok(archive) => self.apply(|vfs| vfs.mount(Box::new(ArchiveSource { archive }))),
+            Err(e) => {
+                warn!("Vfs", "挂载嵌入的资源包失败: {}", e);
+            }
+        }
+    }
+
+    /// 挂载嵌入程序中的文件
+    ///
+    /// # 参数 Parameters
+    /// * `files` - `(虚拟路径, 文件内容)` 列表
+    ///
+    /// # 示例 Examples
+    /// ```ignore
+    /// VFS.mount_embedded(&[
+    ///     ("shader/cube.vert", include_bytes!("../shader/cube.vert")),
+    ///     ("shader/cube.frag", include_bytes!("../shader/cube.frag")),
+    /// ]);
+    /// ```
+    pub fn mount_embedded(&self, files: &[(&str, &'static [u8])]) {
+        let files = files
+            .iter()
+            .map(|&(path, data)| (normalize(path), data))
+            .collect();
+        self.apply(|vfs| vfs.mount(Box::new(EmbeddedSource { files })));
+    }
+
+    /// 读取文件内容
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        let mut ret = Err(String::new());
+        self.apply(|vfs| ret = vfs.read(path));
+        ret
+    }
+
+    /// 文件是否存在，不读取文件内容
+    pub fn exists(&self, path: &str) -> bool {
+        let mut ret = false;
+        self.apply(|vfs| ret = vfs.exists(path));
+        ret
+    }
+
+    /// 以 UTF-8 文本读取文件内容
+    pub fn read_to_string(&self, path: &str) -> Result<String, String> {
+        match String::from_utf8(self.read(path)?) {
+            Ok(string) => Ok(string),
+            Err(e) => Err(format!("文件 {} 不是有效的 UTF-8 文本: {}", path, e)),
+        }
+    }
+
+    /// 列出目录下的所有文件(不含子目录)
+    pub fn list(&self, dir: &str) -> Vec<String> {
+        let mut ret = vec![];
+        self.apply(|vfs| ret = vfs.list(dir));
+        ret
+    }
+
+    /// 按选项扫描目录
+    ///
+    /// # 参数 Parameters
+    /// * `dir` - 目录路径
+    /// * `options` - 扫描选项
+    ///
+    /// # 返回值 Returns
+    /// 按路径排序的扫描结果，资源名称为相对于 `dir`、不含扩展名的路径
+    pub fn scan(&self, dir: &str, options: &ScanOptions) -> Vec<ScanEntry> {
+        let mut ret = vec![];
+        self.apply(|vfs| ret = vfs.scan(dir, options));
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vfs() -> Vfs {
+        let mut vfs = Vfs::new();
+        vfs.unmount_all();
+        let files = [
+            ("texture/blocks/stone.png", &b"1"[..]),
+            ("texture/items/stone.png", &b"2"[..]),
+            ("texture/wall.jpg", &b"3"[..]),
+        ];
+        vfs.mount(Box::new(EmbeddedSource {
+            files: files.iter().map(|&(p, d)| (p.to_string(), d)).collect(),
+        }));
+        vfs
+    }
+
+    #[test]
+    fn test_scan_names() {
+        let vfs = vfs();
+        let flat = vfs.scan("texture/", &ScanOptions::default());
+        assert_eq!(flat.len(), 1);
+        assert_eq!(flat[0].name, "wall");
+        let names = vfs
+            .scan("./texture", &ScanOptions::recursive())
+            .into_iter()
+            .map(|e| e.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["blocks/stone", "items/stone", "wall"]);
+    }
+
+    #[test]
+    fn test_scan_filter() {
+        let vfs = vfs();
+        let options = ScanOptions::recursive()
+            .include("**/*.png")
+            .exclude("items/**");
+        let entries = vfs.scan("texture", &options);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "texture/blocks/stone.png");
+        assert_eq!(entries[0].ext, "png");
+    }
+
+    #[test]
+    fn test_later_mount_overrides() {
+        let mut vfs = vfs();
+        vfs.mount(Box::new(EmbeddedSource {
+            files: [("texture/wall.jpg".to_string(), &b"4"[..])].into(),
+        }));
+        assert_eq!(vfs.read("texture/wall.jpg").unwrap(), b"4");
+        assert_eq!(vfs.read("texture/items/stone.png").unwrap(), b"2");
+    }
+
+    #[test]
+    fn test_exists() {
+        let vfs = vfs();
+        assert!(vfs.exists("./texture/wall.jpg"));
+        assert!(!vfs.exists("texture/wall.png"));
+        assert!(!vfs.exists("texture"));
+    }
+
+    #[test]
+    fn test_absolute_path() {
+        assert_eq!(normalize("/tmp//assets/./a.png"), "/tmp/assets/a.png");
+        assert_eq!(normalize("C:\\assets\\a.png"), "C:/assets/a.png");
+        assert!(is_absolute("/tmp") && is_absolute("C:/assets") && !is_absolute("assets"));
+
+        let dir = std::env::temp_dir().join(format!("rustcraft_vfs_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("blocks")).unwrap();
+        std::fs::write(dir.join("blocks/stone.png"), b"5").unwrap();
+        let dir_str = dir.to_str().unwrap().replace('\\', "/");
+        // 未挂载任何源时绝对路径仍然可用
+        let vfs = vfs();
+        let path = format!("{}/blocks/stone.png", dir_str);
+        assert_eq!(vfs.read(&path).unwrap(), b"5");
+        assert!(vfs.exists(&path) && !vfs.exists(&dir_str));
+        let entries = vfs.scan(&dir_str, &ScanOptions::recursive());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "blocks/stone");
+        assert_eq!(entries[0].path, normalize(&path));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}